@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+#[derive(Debug, Parser)]
+#[clap(version = "1.0")]
+pub struct Cli {
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Launches scenarios of all the environments and serves their stats.
+    Run(TimetableArgs),
+
+    /// Parses the timetable and performs pre-flight checks of all the environments. Exits with
+    /// a non-zero status if any error is found.
+    Validate(TimetableArgs),
+
+    /// Lists available scenario kinds together with their parameters.
+    ListKinds,
+
+    /// Launches a single scenario once and prints its logs. Exits with a non-zero status if the
+    /// run fails.
+    Once {
+        /// Identifier of the scenario (`ident` in the timetable).
+        ident: String,
+
+        /// Environment with the scenario. Needed only if more environments have a scenario with
+        /// this identifier.
+        #[clap(long)]
+        environment: Option<String>,
+
+        #[clap(flatten)]
+        timetable: TimetableArgs,
+    },
+
+    /// Prints status of scenarios and nodes reported by a running instance.
+    Report {
+        /// Address where the instance exposes its stats.
+        #[clap(long, default_value = "http://127.0.0.1:8080")]
+        host: String,
+
+        /// Environment to report on. By default, all of them.
+        #[clap(long)]
+        environment: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct TimetableArgs {
+    /// Path to the timetable.
+    #[clap(long, default_value = "Timetable.toml")]
+    pub timetable: PathBuf,
+}
@@ -1,79 +1,217 @@
+use anyhow::Result as AnyResult;
 use serde::Deserialize;
-use std::time::Duration;
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
-use chain_support::{create_connection, AnyConnection, Connection};
-use common::{parse_interval, Ident, Scenario, ScheduledScenario};
-use scenarios_multisig::Multisig;
-use scenarios_transfer::{RandomTransfers, RoundRobin, SimpleTransfer};
-use scenarios_vesting::{SchedulesMerging, Vest};
+use toml::Value;
 
-/// This struct combines both the execution environment (including hosts and chain address), as well
-/// as the scenario configurations.
+use chain_support::{
+    check_requirements, AccountPool, AccountPools, ChainScenarioRegistry, Finding, NodeConnection,
+    NodePool, NodeSelection, Preflight, Requirements, SecretSource, Severity,
+};
+use common::{parse_interval, Ident, ScheduledScenario};
+
+use crate::faucet::{FaucetConfig, FAUCET};
+
+/// This struct combines the execution environments (including chain addresses and scenario
+/// configurations), account pools used by scenarios, as well as the host where stats are exposed.
 ///
 /// It should be read from `Timetable.toml`.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
-    environment: Environment,
-    scenarios: Vec<ScenarioInstanceConfig>,
+    /// Where to expose stats
+    expose_host: String,
+    /// Accounts used by scenarios (in every environment)
+    #[serde(default)]
+    account_pools: Vec<AccountPool>,
+    environments: Vec<Environment>,
 }
 
 impl Config {
-    pub fn construct_scenarios(&self) -> Vec<ScheduledScenario<Connection>> {
-        let connection = self.environment.get_new_connection();
-        self.scenarios
+    pub fn account_pools(&self) -> Arc<AccountPools> {
+        Arc::new(AccountPools::new(&self.account_pools).expect("Should read account pools"))
+    }
+
+    pub fn environments(&self) -> &[Environment] {
+        &self.environments
+    }
+
+    /// The environment named `name`, if there is any.
+    pub fn environment(&self, name: &str) -> Option<&Environment> {
+        self.environments
             .iter()
-            .map(|sc| sc.construct_scenario(&connection))
-            .collect()
+            .find(|environment| environment.name == name)
     }
 
     pub fn get_expose_host(&self) -> &str {
-        self.environment.get_expose_host()
+        self.expose_host.as_str()
+    }
+
+    /// Panics if environment names are not unique (stats are namespaced by them).
+    pub fn ensure_unique_environments(&self) {
+        let mut names = HashSet::new();
+        for environment in &self.environments {
+            assert!(
+                names.insert(environment.name.as_str()),
+                "Environment `{}` is defined more than once",
+                environment.name
+            );
+        }
     }
 }
 
+/// A single chain (like devnet or testnet) together with the scenarios launched against it.
 #[derive(Debug, Clone, Deserialize)]
-struct Environment {
-    /// WS endpoint address of the node to connect to
-    node: String,
+pub struct Environment {
+    /// Name under which stats of this environment are exposed
+    name: String,
 
-    /// Where to expose stats
-    expose_host: String,
+    /// WS endpoint address of the node to connect to (if there is only one)
+    #[serde(default)]
+    node: Option<String>,
+
+    /// Named WS endpoints of all the nodes to connect to
+    #[serde(default)]
+    nodes: Vec<NodeEndpoint>,
+
+    /// Where the base seed for all the accounts of this environment is kept. By default, it is
+    /// read from env `SECRET_PHRASE_SEED`.
+    #[serde(default)]
+    secret: SecretSource,
+
+    /// Keeps the accounts of all the pools funded (optional)
+    #[serde(default)]
+    faucet: Option<FaucetConfig>,
+
+    scenarios: Vec<ScenarioInstanceConfig>,
+}
+
+fn read_secret(secret: &SecretSource) -> String {
+    secret
+        .read()
+        .unwrap_or_else(|e| panic!("Should read base seed from {:?}: {:?}", secret, e))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct NodeEndpoint {
+    name: String,
+    address: String,
 }
 
 impl Environment {
-    pub fn get_new_connection(&self) -> Connection {
-        create_connection(&*self.node)
+    pub fn name(&self) -> &str {
+        self.name.as_str()
     }
 
-    pub fn get_expose_host(&self) -> &str {
-        self.expose_host.as_str()
+    /// Whether the scenario `ident` (or the faucet) is launched in this environment.
+    pub fn has_scenario(&self, ident: &str) -> bool {
+        self.scenarios.iter().any(|sc| sc.ident.0 == ident)
+            || (ident == FAUCET && self.faucet.is_some())
     }
-}
 
-/// All implemented scenarios should be included here.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(tag = "kind")]
-enum ScenarioConfig {
-    SimpleTransfer(SimpleTransfer),
-    RoundRobin(RoundRobin),
-    RandomTransfers(RandomTransfers),
-    Multisig(Multisig),
-    VestingSchedulesMerging,
-    VestingVest(Vest),
-}
+    /// The same environment, but with only the scenario `ident` (or only the faucet).
+    pub fn only(&self, ident: &str) -> Environment {
+        Environment {
+            scenarios: self
+                .scenarios
+                .iter()
+                .filter(|sc| sc.ident.0 == ident)
+                .cloned()
+                .collect(),
+            faucet: self.faucet.clone().filter(|_| ident == FAUCET),
+            ..self.clone()
+        }
+    }
+
+    /// Reports scenario identifiers that are not unique within this environment (scenarios are
+    /// told apart by them in logs and stats). `FAUCET` is reserved for the faucet.
+    fn check_idents(&self) -> Vec<Finding> {
+        let mut seen = HashSet::new();
+        let mut duplicated = HashSet::new();
+        let mut findings = vec![];
+        for ident in self.scenarios.iter().map(|sc| sc.ident.0.as_str()) {
+            let message = if ident == FAUCET {
+                "Identifier is reserved for the faucet"
+            } else if !seen.insert(ident) && duplicated.insert(ident) {
+                "Identifier is used by more than one scenario"
+            } else {
+                continue;
+            };
+            findings.push(Finding {
+                severity: Severity::Error,
+                subject: ident.to_string(),
+                message: message.to_string(),
+            });
+        }
+        findings
+    }
+
+    /// Pairs of node name and address. A single `node` is named after its address.
+    fn endpoints(&self) -> Vec<(String, String)> {
+        let single = self.node.iter().map(|node| (node.clone(), node.clone()));
+        let named = self
+            .nodes
+            .iter()
+            .map(|endpoint| (endpoint.name.clone(), endpoint.address.clone()));
+        single.chain(named).collect()
+    }
+
+    pub async fn connect(&self, pools: Arc<AccountPools>) -> AnyResult<NodePool> {
+        let base_seed = read_secret(&self.secret);
+        NodePool::connect(&self.endpoints(), &base_seed, pools).await
+    }
 
-impl ScenarioConfig {
-    fn to_scenario(&self, connection: &Connection) -> Box<dyn Scenario<Connection>> {
-        use ScenarioConfig::*;
-
-        match self.clone() {
-            SimpleTransfer(s) => Box::new(s),
-            RoundRobin(s) => Box::new(s),
-            RandomTransfers(s) => Box::new(s),
-            Multisig(s) => Box::new(s),
-            VestingSchedulesMerging => Box::new(SchedulesMerging::new(connection).unwrap()),
-            VestingVest(s) => Box::new(s),
+    /// Constructs all the scenarios of this environment (together with the faucet, if configured)
+    /// and checks their requirements against the first node.
+    ///
+    /// Scenarios (or the faucet) that cannot be constructed, as well as scenarios sharing an
+    /// identifier, are reported among the findings. Scenarios with their own `secret` are checked
+    /// separately, as their accounts differ.
+    pub async fn prepare(
+        &self,
+        pool: &NodePool,
+        registry: &ChainScenarioRegistry,
+    ) -> (Vec<ScheduledScenario<NodeConnection>>, Vec<Finding>) {
+        let mut scenarios = vec![];
+        let mut findings = self.check_idents();
+        let mut shared = vec![];
+        for sc in &self.scenarios {
+            let node_pool = sc.node_pool(pool);
+            let requirements = match sc.construct_scenario(&self.name, &node_pool, registry) {
+                Ok((scenario, requirements)) => {
+                    scenarios.push(scenario);
+                    (sc.ident.0.clone(), requirements)
+                }
+                Err(e) => {
+                    findings.push(Finding {
+                        severity: Severity::Error,
+                        subject: sc.ident.0.clone(),
+                        message: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            match sc.secret {
+                Some(_) => {
+                    findings.extend(check_requirements(node_pool.first(), &[requirements]).await)
+                }
+                None => shared.push(requirements),
+            }
+        }
+        if let Some(faucet) = &self.faucet {
+            match faucet.construct_faucet(&self.name, pool) {
+                Ok(scenario) => {
+                    scenarios.push(scenario);
+                    shared.push((FAUCET.to_string(), faucet.requirements()));
+                }
+                Err(e) => findings.push(Finding {
+                    severity: Severity::Error,
+                    subject: FAUCET.to_string(),
+                    message: e.to_string(),
+                }),
+            }
         }
+        findings.extend(check_requirements(pool.first(), &shared).await);
+        (scenarios, findings)
     }
 }
 
@@ -82,21 +220,46 @@ struct ScenarioInstanceConfig {
     ident: Ident,
     #[serde(deserialize_with = "parse_interval")]
     interval: Duration,
+    /// Which node(s) the scenario should use. By default, all of them in turns.
+    #[serde(default)]
+    node: NodeSelection,
+    /// Where the base seed for the accounts of this scenario is kept. By default, the one of
+    /// the environment is used.
+    #[serde(default)]
+    secret: Option<SecretSource>,
+    /// `kind` of the scenario together with its parameters (see `ChainScenarioRegistry`)
     #[serde(rename = "scenario")]
-    scenario_config: ScenarioConfig,
+    scenario_config: Value,
 }
 
 impl ScenarioInstanceConfig {
-    pub fn construct_scenario<C: AnyConnection>(
+    /// Nodes of the environment, deriving accounts from the base seed of this scenario.
+    fn node_pool(&self, pool: &NodePool) -> NodePool {
+        match &self.secret {
+            Some(secret) => pool.with_base_seed(&read_secret(secret)),
+            None => pool.clone(),
+        }
+    }
+
+    /// Constructs the scenario (against the first node of `pool`) and reads its requirements.
+    fn construct_scenario(
         &self,
-        connection: &C,
-    ) -> ScheduledScenario<Connection> {
-        ScheduledScenario::new(
-            self.ident.clone(),
-            self.interval,
-            connection.as_connection(),
-            self.scenario_config
-                .to_scenario(&connection.as_connection()),
-        )
+        environment: &str,
+        pool: &NodePool,
+        registry: &ChainScenarioRegistry,
+    ) -> AnyResult<(ScheduledScenario<NodeConnection>, Requirements)> {
+        let selector = pool.selector(self.node.clone())?;
+        let scenario = registry.construct(&self.scenario_config, pool.first())?;
+        let requirements = scenario.requirements();
+        Ok((
+            ScheduledScenario::new(
+                environment.to_string(),
+                self.ident.clone(),
+                self.interval,
+                selector,
+                scenario,
+            ),
+            requirements,
+        ))
     }
 }
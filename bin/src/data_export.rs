@@ -5,6 +5,8 @@ use std::sync::{Arc, Mutex};
 pub trait DataExporter {
     fn export_details(&self) -> String;
     fn export_logs(&self, scenario_ident: Ident) -> String;
+    fn export_latency(&self) -> String;
+    fn export_health(&self) -> String;
 }
 
 impl<DE: DataExporter> DataExporter for Arc<Mutex<DE>> {
@@ -15,4 +17,39 @@ impl<DE: DataExporter> DataExporter for Arc<Mutex<DE>> {
     fn export_logs(&self, scenario_ident: Ident) -> String {
         self.lock().unwrap().export_logs(scenario_ident)
     }
+
+    fn export_latency(&self) -> String {
+        self.lock().unwrap().export_latency()
+    }
+
+    fn export_health(&self) -> String {
+        self.lock().unwrap().export_health()
+    }
+}
+
+/// Data exporters of all the environments, in the configuration order.
+pub struct Environments<DE> {
+    exporters: Vec<(String, DE)>,
+}
+
+impl<DE: DataExporter> Environments<DE> {
+    pub fn new(exporters: Vec<(String, DE)>) -> Self {
+        Environments { exporters }
+    }
+
+    pub fn export_names(&self) -> String {
+        let names = self
+            .exporters
+            .iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        serde_json::to_string(&names).expect("Names should be serializable")
+    }
+
+    pub fn get(&self, environment: &str) -> Option<&DE> {
+        self.exporters
+            .iter()
+            .find(|(name, _)| name == environment)
+            .map(|(_, exporter)| exporter)
+    }
 }
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use anyhow::Result as AnyResult;
+use serde::Deserialize;
+use thiserror::Error;
+
+use chain_support::{
+    account_from_keypair, free_balance, free_balances, keypair_from_string, top_ups,
+    transfer_batch, KeyPair, NodeConnection, NodePool, NodeSelection, Preflight, Requirements,
+    SecretSource, WaitFor, DECIMALS,
+};
+use common::{parse_interval, Ident, Scenario, ScenarioLogging, ScheduledScenario};
+
+/// Identifier under which the faucet is scheduled.
+pub const FAUCET: &str = "Faucet";
+
+#[derive(Debug, Error)]
+pub enum FaucetError {
+    #[error("🚰❌ Treasury has {1} tokens, but {0} are needed for top-ups.")]
+    TreasuryExhausted(u128, u128),
+}
+
+fn default_threshold() -> u8 {
+    50
+}
+
+/// Configuration of the faucet, which keeps the accounts of all the pools funded.
+///
+/// Every `interval`, the faucet checks free balances of all the accounts of the pools that have
+/// a target `balance`. Accounts that have fallen below `threshold` percent of it are topped up
+/// (back to the target balance) by a transfer from the treasury account.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FaucetConfig {
+    /// Where the seed phrase of the treasury account is kept. Unlike bot accounts, it is not
+    /// derived from the base seed.
+    treasury: SecretSource,
+    #[serde(deserialize_with = "parse_interval")]
+    interval: Duration,
+    /// Percentage of the target balance below which an account is topped up. By default, 50.
+    #[serde(default = "default_threshold")]
+    threshold: u8,
+    /// Which node(s) the faucet should use. By default, all of them in turns.
+    #[serde(default)]
+    node: NodeSelection,
+}
+
+impl FaucetConfig {
+    /// The faucet is scheduled like any other scenario (under the identifier `Faucet`), so its
+    /// runs, logs and extrinsics are visible in stats.
+    ///
+    /// Fails if the treasury seed cannot be read or `node` does not select existing nodes.
+    pub fn construct_faucet(
+        &self,
+        environment: &str,
+        pool: &NodePool,
+    ) -> AnyResult<ScheduledScenario<NodeConnection>> {
+        let phrase = self.treasury.read()?;
+        let selector = pool.selector(self.node.clone())?;
+        Ok(ScheduledScenario::new(
+            environment.to_string(),
+            Ident::from(FAUCET),
+            self.interval,
+            selector,
+            Faucet {
+                treasury: keypair_from_string(&phrase),
+                threshold: self.threshold,
+            },
+        ))
+    }
+}
+
+impl Preflight for FaucetConfig {
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+            .call("Balances", "transfer")
+            .call("Utility", "batch_all")
+            .ensure(self.threshold <= 100, "Threshold should be a percentage")
+    }
+}
+
+struct Faucet {
+    treasury: KeyPair,
+    threshold: u8,
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Faucet {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let mut transfers = vec![];
+        for accounts in connection.all_accounts() {
+            let target = accounts.balance();
+            if target == 0 {
+                continue;
+            }
+
+            let members = accounts.accounts();
+            let balances = free_balances(connection, &members).await?;
+            let balances = members.into_iter().zip(balances).collect();
+            let threshold = target / 100 * self.threshold as u128;
+
+            for (account, amount) in top_ups(balances, target, threshold) {
+                logger.info(format!(
+                    "Topping up {} from pool `{}` with {} tokens.",
+                    account,
+                    accounts.name(),
+                    amount / DECIMALS
+                ));
+                transfers.push((account, amount));
+            }
+        }
+
+        if transfers.is_empty() {
+            logger.info("All accounts have enough funds.");
+            return Ok(());
+        }
+
+        let needed = transfers.iter().map(|(_, amount)| amount).sum::<u128>();
+        let available = free_balance(connection, &account_from_keypair(&self.treasury)).await?;
+        if available < needed {
+            return logger.log_result(Err(FaucetError::TreasuryExhausted(
+                needed / DECIMALS,
+                available / DECIMALS,
+            )
+            .into()));
+        }
+
+        logger.log_result(
+            transfer_batch(connection, &self.treasury, &transfers, WaitFor::Finalized).await,
+        )?;
+        logger.info(format!(
+            "Topped up {} accounts with {} tokens in total.",
+            transfers.len(),
+            needed / DECIMALS
+        ));
+        Ok(())
+    }
+}
@@ -1,52 +1,241 @@
 use std::{
     fs,
     io::Result,
+    path::Path,
+    process,
     sync::{Arc, Mutex},
 };
 
 use actix_cors::Cors;
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 
-use traffic::run_schedule;
+use chain_support::{
+    register_combinators, AccountPools, ChainScenarioRegistry, Finding, NodeConnection, NodePool,
+    Severity,
+};
+use clap::Parser;
+use common::ScheduledScenario;
+use traffic::{run_once, run_schedule, setup_logging, Logger};
 
-use crate::{config::Config, data_export::DataExporter, stats::Stats};
+use crate::{
+    cli::{Cli, Command},
+    config::{Config, Environment},
+    data_export::{DataExporter, Environments},
+    report::report,
+    stats::Stats,
+};
 
+mod cli;
 mod config;
 mod data_export;
+mod faucet;
+mod report;
 mod stats;
 
-async fn serve_details<DE: DataExporter>(data: web::Data<DE>) -> impl Responder {
+/// Responds with the data exported by `environment` (or with 404, if there is no such one).
+fn export<DE: DataExporter, F: FnOnce(&DE) -> String>(
+    environments: &Environments<DE>,
+    environment: &str,
+    extract: F,
+) -> HttpResponse {
+    match environments.get(environment) {
+        Some(exporter) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(extract(exporter)),
+        None => HttpResponse::NotFound().body(format!("Unknown environment `{}`", environment)),
+    }
+}
+
+async fn serve_environments<DE: DataExporter>(data: web::Data<Environments<DE>>) -> impl Responder {
     HttpResponse::Ok()
         .content_type("application/json")
-        .body(data.export_details())
+        .body(data.export_names())
+}
+
+async fn serve_details<DE: DataExporter>(
+    data: web::Data<Environments<DE>>,
+    environment: web::Path<String>,
+) -> impl Responder {
+    export(&data, &environment, DE::export_details)
 }
 
 async fn serve_logs<DE: DataExporter>(
-    data: web::Data<DE>,
-    scenario_ident: web::Path<String>,
+    data: web::Data<Environments<DE>>,
+    path: web::Path<(String, String)>,
 ) -> impl Responder {
-    HttpResponse::Ok()
-        .content_type("application/json")
-        .body(data.export_logs(scenario_ident.into_inner().into()))
+    let (environment, scenario_ident) = path.into_inner();
+    export(&data, &environment, |exporter| {
+        exporter.export_logs(scenario_ident.into())
+    })
 }
 
-fn parse_config() -> Config {
+async fn serve_latency<DE: DataExporter>(
+    data: web::Data<Environments<DE>>,
+    environment: web::Path<String>,
+) -> impl Responder {
+    export(&data, &environment, DE::export_latency)
+}
+
+async fn serve_health<DE: DataExporter>(
+    data: web::Data<Environments<DE>>,
+    environment: web::Path<String>,
+) -> impl Responder {
+    export(&data, &environment, DE::export_health)
+}
+
+fn parse_config(path: &Path) -> Config {
     let config_content =
-        fs::read_to_string("Timetable.toml").expect("Config file should exist and be readable");
-    toml::from_str(&*config_content).expect("Should deserialize timetable")
+        fs::read_to_string(path).expect("Config file should exist and be readable");
+    let config: Config = toml::from_str(&*config_content).expect("Should deserialize timetable");
+    config.ensure_unique_environments();
+    config
 }
 
-#[actix_web::main]
-async fn main() -> Result<()> {
-    let config = parse_config();
+/// Prints what pre-flight checks have found in `environment`. Returns whether there is any error.
+fn report_findings(environment: &str, findings: &[Finding]) -> bool {
+    println!("Pre-flight checks of `{}`:", environment);
+    if findings.is_empty() {
+        println!("  ✅ Everything is in place");
+    }
+    for finding in findings {
+        println!("  {}", finding);
+    }
+    findings
+        .iter()
+        .any(|finding| finding.severity == Severity::Error)
+}
+
+/// All the scenario kinds that can be used in the timetable.
+fn scenario_registry() -> ChainScenarioRegistry {
+    let mut registry = ChainScenarioRegistry::default();
+    register_combinators(&mut registry).expect("Should register combinators");
+    scenarios_transfer::register(&mut registry).expect("Should register transfer scenarios");
+    scenarios_multisig::register(&mut registry).expect("Should register multisig scenarios");
+    scenarios_vesting::register(&mut registry).expect("Should register vesting scenarios");
+    scenarios_staking::register(&mut registry).expect("Should register staking scenarios");
+    scenarios_observer::register(&mut registry).expect("Should register observer scenarios");
+    scenarios_utility::register(&mut registry).expect("Should register utility scenarios");
+    scenarios_proxy::register(&mut registry).expect("Should register proxy scenarios");
+    scenarios_identity::register(&mut registry).expect("Should register identity scenarios");
+    scenarios_treasury::register(&mut registry).expect("Should register treasury scenarios");
+    scenarios_sudo::register(&mut registry).expect("Should register sudo scenarios");
+    scenarios_contracts::register(&mut registry).expect("Should register contract scenarios");
+    registry
+}
+
+/// Connects to all the nodes of `environments`, constructs their scenarios and performs their
+/// pre-flight checks. Exits if any error has been found.
+async fn prepare<'a>(
+    environments: &[&'a Environment],
+    account_pools: Arc<AccountPools>,
+) -> Vec<(
+    &'a Environment,
+    NodePool,
+    Vec<ScheduledScenario<NodeConnection>>,
+)> {
+    let registry = scenario_registry();
+    let mut prepared = vec![];
+    let mut refuse = false;
+    for environment in environments {
+        let findings = match environment.connect(account_pools.clone()).await {
+            Ok(pool) => {
+                let (scenarios, findings) = environment.prepare(&pool, &registry).await;
+                prepared.push((*environment, pool, scenarios));
+                findings
+            }
+            Err(e) => vec![Finding {
+                severity: Severity::Error,
+                subject: "nodes".to_string(),
+                message: e.to_string(),
+            }],
+        };
+        refuse |= report_findings(environment.name(), &findings);
+    }
+    if refuse {
+        eprintln!("Pre-flight checks failed. Fix the errors above before launching scenarios.");
+        process::exit(1);
+    }
+    prepared
+}
+
+fn list_kinds() {
+    println!("Every scenario has `ident`, `interval` and optionally `node` and `secret`.");
+    println!("Specific parameters (within `scenario`) of every kind:");
+    for kind in scenario_registry().kinds() {
+        println!("  {}", kind.name());
+        if kind.parameters().is_empty() {
+            println!("    (none)");
+        }
+        for parameter in kind.parameters() {
+            println!("    {}", parameter);
+        }
+    }
+}
+
+/// Runs the scenario `ident` once and exits with its result.
+async fn once(
+    config: &Config,
+    ident: &str,
+    environment: Option<&str>,
+    logger: Logger,
+) -> Result<()> {
+    let candidates = match environment {
+        Some(name) => config
+            .environment(name)
+            .into_iter()
+            .filter(|environment| environment.has_scenario(ident))
+            .collect::<Vec<_>>(),
+        None => config
+            .environments()
+            .iter()
+            .filter(|environment| environment.has_scenario(ident))
+            .collect(),
+    };
+    let environment = match candidates.as_slice() {
+        [environment] => environment.only(ident),
+        [] => {
+            eprintln!("There is no scenario `{}`", ident);
+            process::exit(2);
+        }
+        _ => {
+            eprintln!(
+                "Scenario `{}` is defined in many environments. Choose one with `--environment`",
+                ident
+            );
+            process::exit(2);
+        }
+    };
+
+    let (_, _, mut scenarios) = prepare(&[&environment], config.account_pools())
+        .await
+        .remove(0);
+    let scenario = scenarios.remove(0);
+    let succeeded = run_once(scenario, logger).await;
+    process::exit(if succeeded { 0 } else { 1 })
+}
+
+type SharedStats = Arc<Mutex<Stats>>;
+
+/// Launches all the environments (sharing `logger`) and serves their stats. After Ctrl+C, waits
+/// until all the scenarios are torn down.
+async fn run(config: Config, logger: Logger) -> Result<()> {
+    let environments = config.environments().iter().collect::<Vec<_>>();
+    let prepared = prepare(&environments, config.account_pools()).await;
 
-    let stats = Arc::new(Mutex::new(Stats::new()));
-    let stats_for_backend = stats.clone();
+    let mut exporters = vec![];
+    let mut schedules = vec![];
+    for (environment, pool, scenarios) in prepared {
+        pool.supervise();
 
-    let scenarios = config.construct_scenarios();
-    tokio::spawn(async move {
-        run_schedule(scenarios, stats_for_backend).await;
-    });
+        let stats = Arc::new(Mutex::new(Stats::new(pool.clone())));
+        exporters.push((environment.name().to_string(), stats.clone()));
+
+        let logger = logger.clone();
+        schedules.push(tokio::spawn(async move {
+            run_schedule(scenarios, stats, logger).await;
+        }));
+    }
+    let environments = web::Data::new(Environments::new(exporters));
 
     HttpServer::new(move || {
         App::new()
@@ -55,17 +244,63 @@ async fn main() -> Result<()> {
                     .allow_any_origin()
                     .allowed_methods(vec!["GET"]),
             )
-            .app_data(web::Data::new(stats.clone()))
+            .app_data(environments.clone())
             .service(
                 web::scope("")
-                    .route("details", web::get().to(serve_details::<Arc<Mutex<Stats>>>))
                     .route(
-                        "logs/{scenario_ident}",
-                        web::get().to(serve_logs::<Arc<Mutex<Stats>>>),
+                        "environments",
+                        web::get().to(serve_environments::<SharedStats>),
+                    )
+                    .route(
+                        "{environment}/details",
+                        web::get().to(serve_details::<SharedStats>),
+                    )
+                    .route(
+                        "{environment}/logs/{scenario_ident}",
+                        web::get().to(serve_logs::<SharedStats>),
+                    )
+                    .route(
+                        "{environment}/latency",
+                        web::get().to(serve_latency::<SharedStats>),
+                    )
+                    .route(
+                        "{environment}/health",
+                        web::get().to(serve_health::<SharedStats>),
                     ),
             )
     })
     .bind(config.get_expose_host())?
     .run()
-    .await
+    .await?;
+
+    for schedule in schedules {
+        let _ = schedule.await;
+    }
+    Ok(())
+}
+
+#[actix_web::main]
+async fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Run(args) => run(parse_config(&args.timetable), setup_logging()).await,
+        Command::Validate(args) => {
+            let config = parse_config(&args.timetable);
+            let environments = config.environments().iter().collect::<Vec<_>>();
+            prepare(&environments, config.account_pools()).await;
+            Ok(())
+        }
+        Command::ListKinds => {
+            list_kinds();
+            Ok(())
+        }
+        Command::Once {
+            ident,
+            environment,
+            timetable,
+        } => {
+            let config = parse_config(&timetable.timetable);
+            once(&config, &ident, environment.as_deref(), setup_logging()).await
+        }
+        Command::Report { host, environment } => report(&host, environment.as_deref()).await,
+    }
 }
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, io::Result, process};
+
+use anyhow::{anyhow, Result as AnyResult};
+use awc::Client;
+use serde::de::DeserializeOwned;
+
+use common::{Ident, NodeHealth, ScenarioDetails};
+
+/// Fetches `path` from `host` and deserializes it from JSON.
+async fn fetch<T: DeserializeOwned>(client: &Client, host: &str, path: &str) -> AnyResult<T> {
+    let url = format!("{}/{}", host.trim_end_matches('/'), path);
+    let mut response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Cannot query `{}`: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("`{}` responded with {}", url, response.status()));
+    }
+    let body = response
+        .body()
+        .await
+        .map_err(|e| anyhow!("Cannot read response from `{}`: {}", url, e))?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+fn print_health(nodes: &[NodeHealth]) {
+    println!("  Nodes:");
+    for node in nodes {
+        let known = |value: Option<u32>| value.map_or("?".to_string(), |v| v.to_string());
+        println!(
+            "    {} ({}): {}, best #{}, finalized #{}, {} peers",
+            node.name,
+            node.address,
+            if node.connected { "up" } else { "down" },
+            known(node.best_block),
+            known(node.finalized_block),
+            known(node.peers)
+        );
+    }
+}
+
+fn print_details(details: HashMap<Ident, ScenarioDetails>) {
+    let mut details = details.into_values().collect::<Vec<_>>();
+    details.sort_by(|a, b| a.ident.0.cmp(&b.ident.0));
+
+    println!("  Scenarios:");
+    for scenario in details {
+        println!(
+            "    {}: {} runs, {} failures, last status: {:?}",
+            scenario.ident.0, scenario.runs, scenario.failures, scenario.last_status
+        );
+        if let Some(reason) = scenario.disabled_reason {
+            println!("      disabled: {}", reason);
+        }
+    }
+}
+
+async fn report_environment(client: &Client, host: &str, environment: &str) -> AnyResult<()> {
+    let health = fetch::<Vec<NodeHealth>>(client, host, &format!("{}/health", environment)).await?;
+    let details = fetch(client, host, &format!("{}/details", environment)).await?;
+
+    println!("Environment `{}`:", environment);
+    print_health(&health);
+    print_details(details);
+    Ok(())
+}
+
+/// Prints status of nodes and scenarios of `environment` (or all the environments) served by
+/// the instance at `host`. Exits with a non-zero status if the instance cannot be queried.
+pub async fn report(host: &str, environment: Option<&str>) -> Result<()> {
+    let client = Client::default();
+    let result = async {
+        let environments = match environment {
+            Some(environment) => vec![environment.to_string()],
+            None => fetch(&client, host, "environments").await?,
+        };
+        for environment in environments {
+            report_environment(&client, host, &environment).await?;
+        }
+        AnyResult::<()>::Ok(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -2,6 +2,7 @@ use std::collections::{hash_map::Entry, HashMap};
 
 use log::warn;
 
+use chain_support::NodePool;
 use common::{Ident, ScenarioDetails, ScenarioLogs, ScenarioStatus, ScheduledScenario};
 use traffic::EventListener;
 
@@ -9,18 +10,21 @@ use crate::data_export::DataExporter;
 
 /// A single struct realizing two important concepts: data exposure (`DataExporter` trait) and event
 /// registration (`EventListener` trait). It is the way in which the scheduler communicates with the
-/// outer world.
+/// outer world. There is a separate instance for every environment.
 #[derive(Clone)]
 pub struct Stats {
     details: HashMap<Ident, ScenarioDetails>,
     logs: HashMap<Ident, ScenarioLogs>,
+    /// Nodes used by scenarios; source of node health and extrinsic latencies.
+    pool: NodePool,
 }
 
 impl Stats {
-    pub fn new() -> Self {
+    pub fn new(pool: NodePool) -> Self {
         Stats {
             details: HashMap::new(),
             logs: HashMap::new(),
+            pool,
         }
     }
 
@@ -40,7 +44,10 @@ impl Stats {
     fn update_status(&mut self, scenario_ident: Ident, status: ScenarioStatus) {
         Self::update_storage(&mut self.details, scenario_ident, |details| {
             match status {
-                ScenarioStatus::Running | ScenarioStatus::NotLaunchedYet => {}
+                ScenarioStatus::Running
+                | ScenarioStatus::NotLaunchedYet
+                | ScenarioStatus::Skipped
+                | ScenarioStatus::Disabled => {}
                 ScenarioStatus::Success => details.runs += 1,
                 ScenarioStatus::Failure => {
                     details.runs += 1;
@@ -64,6 +71,15 @@ impl DataExporter for Stats {
             "".to_string()
         }
     }
+
+    fn export_latency(&self) -> String {
+        serde_json::to_string(&self.pool.latency_report())
+            .expect("Latency summaries should be serializable")
+    }
+
+    fn export_health(&self) -> String {
+        serde_json::to_string(&self.pool.health()).expect("Health should be serializable")
+    }
 }
 
 impl EventListener for Stats {
@@ -93,6 +109,17 @@ impl EventListener for Stats {
         self.update_status(scenario_ident, ScenarioStatus::Failure)
     }
 
+    fn report_skip(&mut self, scenario_ident: Ident) {
+        self.update_status(scenario_ident, ScenarioStatus::Skipped)
+    }
+
+    fn report_disabled(&mut self, scenario_ident: Ident, reason: String) {
+        Self::update_storage(&mut self.details, scenario_ident, |details| {
+            details.last_status = ScenarioStatus::Disabled;
+            details.disabled_reason = Some(reason);
+        })
+    }
+
     fn report_logs(&mut self, scenario_ident: Ident, log: String) {
         Self::update_storage(&mut self.logs, scenario_ident, |all_logs| {
             // TODO: make `content` a bounded container
@@ -0,0 +1,322 @@
+//! Declarative account pools.
+//!
+//! Every pool is a named family of accounts derived from a common seed prefix. Pools are declared
+//! once (in `Timetable.toml`): scenarios refer to them by name, and `set_up` endows them with
+//! their target balances.
+
+use std::collections::HashMap;
+
+use aleph_client::{account_from_keypair, substrate_api_client::AccountId, KeyPair};
+use anyhow::Result as AnyResult;
+use rand::{seq::index::sample, thread_rng, Rng};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{keypair_derived_from, real_amount, SecretSource};
+
+/// Gathers all possible errors from this module.
+#[derive(Debug, Error)]
+pub enum AccountsError {
+    #[error("👥❌ There is no account pool named `{0}`.")]
+    UnknownPool(String),
+    #[error("👥❌ Account pool `{0}` is declared more than once.")]
+    DuplicatedPool(String),
+    #[error("👥❌ Account pool `{0}` has {1} accounts, but at least {2} are needed.")]
+    PoolTooSmall(String, usize, usize),
+    #[error("👥❌ Account pool `{0}` has no accounts (its `size` is 0).")]
+    EmptyPool(String),
+}
+
+/// Declaration of an account pool.
+///
+/// If `size` is given, the pool consists of accounts `{seed}{i}` for `i` from 0 to `size`
+/// (exclusively). Otherwise, it is a single account `{seed}`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccountPool {
+    /// Name by which scenarios refer to the pool.
+    name: String,
+    /// Derivation path (prefix) of the accounts, like `//RandomTransfer`.
+    seed: String,
+    #[serde(default)]
+    size: Option<usize>,
+    /// How many tokens every account should have.
+    ///
+    /// As `toml` does not support deserializing `u128`, we need to operate on amounts scaled by
+    /// `DECIMALS`.
+    #[serde(default)]
+    balance: u64,
+    /// Where the base seed for this pool is kept. By default, the one of the environment (or
+    /// the scenario) is used.
+    #[serde(default)]
+    secret: Option<SecretSource>,
+}
+
+impl AccountPool {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Number of accounts in the pool.
+    pub fn size(&self) -> usize {
+        self.size.unwrap_or(1)
+    }
+
+    /// Target balance of every account (in the smallest units).
+    pub fn balance(&self) -> u128 {
+        real_amount(&self.balance)
+    }
+
+    pub fn secret(&self) -> Option<&SecretSource> {
+        self.secret.as_ref()
+    }
+
+    /// Derivation path of the account with index `idx`.
+    pub fn seed(&self, idx: usize) -> String {
+        match self.size {
+            Some(_) => format!("{}{}", self.seed, idx),
+            None => self.seed.clone(),
+        }
+    }
+
+    /// Derivation paths of all the accounts.
+    pub fn seeds(&self) -> Vec<String> {
+        (0..self.size()).map(|idx| self.seed(idx)).collect()
+    }
+}
+
+/// Account pool together with its base seed, ready to derive accounts.
+struct ResolvedPool {
+    pool: AccountPool,
+    /// `None` if the pool does not have its own secret.
+    base_seed: Option<String>,
+}
+
+/// All the declared account pools, by name.
+#[derive(Default)]
+pub struct AccountPools {
+    pools: HashMap<String, ResolvedPool>,
+}
+
+impl AccountPools {
+    /// Reads secrets of `pools` and checks that their names are unique and that none of them is
+    /// empty.
+    pub fn new(pools: &[AccountPool]) -> AnyResult<Self> {
+        let mut resolved = HashMap::new();
+        for pool in pools {
+            if pool.size() == 0 {
+                return Err(AccountsError::EmptyPool(pool.name.clone()).into());
+            }
+            let base_seed = pool.secret().map(SecretSource::read).transpose()?;
+            let previous = resolved.insert(
+                pool.name.clone(),
+                ResolvedPool {
+                    pool: pool.clone(),
+                    base_seed,
+                },
+            );
+            if previous.is_some() {
+                return Err(AccountsError::DuplicatedPool(pool.name.clone()).into());
+            }
+        }
+        Ok(AccountPools { pools: resolved })
+    }
+
+    /// Accounts of the pool `name`. They are derived from `base_seed`, unless the pool has its own
+    /// secret.
+    pub(crate) fn accounts<'a>(
+        &'a self,
+        name: &str,
+        base_seed: &'a str,
+    ) -> AnyResult<Accounts<'a>> {
+        let resolved = self
+            .pools
+            .get(name)
+            .ok_or_else(|| AccountsError::UnknownPool(name.to_string()))?;
+        Ok(Accounts {
+            pool: &resolved.pool,
+            base_seed: resolved.base_seed.as_deref().unwrap_or(base_seed),
+        })
+    }
+
+    /// Accounts of all the pools (ordered by pool name). They are derived from `base_seed`, unless
+    /// a pool has its own secret.
+    pub fn all<'a>(&'a self, base_seed: &'a str) -> Vec<Accounts<'a>> {
+        let mut names = self.pools.keys().collect::<Vec<_>>();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                self.accounts(name, base_seed)
+                    .expect("Pool names are taken from the map")
+            })
+            .collect()
+    }
+
+    /// Accounts of the pools that do not have their own secret (ordered by pool name), derived
+    /// from `base_seed`.
+    pub fn derived_from<'a>(&'a self, base_seed: &'a str) -> Vec<Accounts<'a>> {
+        let mut pools = self
+            .pools
+            .values()
+            .filter(|resolved| resolved.base_seed.is_none())
+            .map(|resolved| &resolved.pool)
+            .collect::<Vec<_>>();
+        pools.sort_by_key(|pool| pool.name());
+        pools
+            .into_iter()
+            .map(|pool| Accounts { pool, base_seed })
+            .collect()
+    }
+}
+
+/// Accounts of a single pool, as seen by a scenario (see `NodeConnection::accounts`).
+pub struct Accounts<'a> {
+    pool: &'a AccountPool,
+    base_seed: &'a str,
+}
+
+impl<'a> Accounts<'a> {
+    /// Name of the pool.
+    pub fn name(&self) -> &str {
+        self.pool.name()
+    }
+
+    /// Target balance of every account (in the smallest units).
+    pub fn balance(&self) -> u128 {
+        self.pool.balance()
+    }
+
+    /// Number of accounts in the pool.
+    pub fn size(&self) -> usize {
+        self.pool.size()
+    }
+
+    /// Fails if there are less than `needed` accounts in the pool.
+    pub fn ensure_size(&self, needed: usize) -> AnyResult<()> {
+        if self.size() < needed {
+            return Err(
+                AccountsError::PoolTooSmall(self.pool.name.clone(), self.size(), needed).into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Keypair of the account with index `idx`.
+    pub fn keypair(&self, idx: usize) -> KeyPair {
+        keypair_derived_from(self.base_seed, self.pool.seed(idx))
+    }
+
+    /// Address of the account with index `idx`.
+    pub fn account(&self, idx: usize) -> AccountId {
+        account_from_keypair(&self.keypair(idx))
+    }
+
+    /// Addresses of all the accounts.
+    pub fn accounts(&self) -> Vec<AccountId> {
+        (0..self.size()).map(|idx| self.account(idx)).collect()
+    }
+
+    /// Keypairs of all the accounts.
+    pub fn keypairs(&self) -> Vec<KeyPair> {
+        (0..self.size()).map(|idx| self.keypair(idx)).collect()
+    }
+
+    /// Keypair of a random account.
+    pub fn random_keypair(&self) -> KeyPair {
+        self.keypair(thread_rng().gen_range(0..self.size()))
+    }
+
+    /// Keypairs of `amount` distinct random accounts. Fails if the pool has fewer accounts.
+    pub fn random_keypairs(&self, amount: usize) -> AnyResult<Vec<KeyPair>> {
+        self.ensure_size(amount)?;
+        Ok(sample(&mut thread_rng(), self.size(), amount)
+            .iter()
+            .map(|idx| self.keypair(idx))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use aleph_client::account_from_keypair;
+
+    use crate::accounts::{AccountPool, AccountPools};
+
+    fn named_pool(name: &str, size: Option<usize>) -> AccountPool {
+        AccountPool {
+            name: name.to_string(),
+            seed: "//Pool".to_string(),
+            size,
+            balance: 10,
+            secret: None,
+        }
+    }
+
+    fn pool(size: Option<usize>) -> AccountPool {
+        named_pool("Pool", size)
+    }
+
+    #[test]
+    fn pool_with_size_has_indexed_seeds() {
+        assert_eq!(vec!["//Pool0", "//Pool1", "//Pool2"], pool(Some(3)).seeds());
+    }
+
+    #[test]
+    fn pool_without_size_is_a_single_account() {
+        assert_eq!(vec!["//Pool"], pool(None).seeds());
+    }
+
+    #[test]
+    fn random_keypairs_are_distinct_accounts_of_the_pool() {
+        let pools = AccountPools::new(&[pool(Some(5))]).unwrap();
+        let accounts = pools.accounts("Pool", "").unwrap();
+
+        let chosen = accounts
+            .random_keypairs(3)
+            .unwrap()
+            .iter()
+            .map(account_from_keypair)
+            .collect::<HashSet<_>>();
+
+        assert_eq!(3, chosen.len());
+        assert!(chosen.is_subset(&accounts.accounts().into_iter().collect()));
+    }
+
+    #[test]
+    fn random_keypairs_fail_when_pool_is_too_small() {
+        let pools = AccountPools::new(&[pool(Some(2))]).unwrap();
+        let accounts = pools.accounts("Pool", "").unwrap();
+
+        assert!(accounts.random_keypairs(2).is_ok());
+        assert!(accounts.random_keypairs(3).is_err());
+    }
+
+    #[test]
+    fn pools_are_looked_up_by_name() {
+        let pools =
+            AccountPools::new(&[named_pool("First", None), named_pool("Second", Some(2))]).unwrap();
+
+        assert_eq!(2, pools.accounts("Second", "").unwrap().size());
+        assert!(pools.accounts("Third", "").is_err());
+        assert_eq!(
+            vec!["First", "Second"],
+            pools
+                .all("")
+                .iter()
+                .map(|accounts| accounts.name())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn duplicated_pool_names_are_rejected() {
+        assert!(AccountPools::new(&[pool(None), pool(Some(2))]).is_err());
+    }
+
+    #[test]
+    fn empty_pools_are_rejected() {
+        assert!(AccountPools::new(&[pool(Some(0))]).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    fmt::Display,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use aleph_client::{
+    substrate_api_client::{rpc::ws_client::WsRpcClient, Hash},
+    AnyConnection, BlockNumber, Connection, KeyPair,
+};
+use anyhow::Result as AnyResult;
+use hex::FromHex;
+use jsonrpsee::{
+    core::client::ClientT,
+    rpc_params,
+    ws_client::{WsClient, WsClientBuilder},
+};
+use serde_json::Value;
+use sp_runtime::{generic::Header as GenericHeader, traits::BlakeTwo256};
+use thiserror::Error;
+use tokio::{
+    task::spawn_blocking,
+    time::{sleep, timeout},
+};
+
+use common::{Availability, LatencySummary, NodeHealth};
+
+use crate::{keypair_derived_from, latency::LatencyRegistry, AccountPools, Accounts, NonceManager};
+
+pub type Header = GenericHeader<BlockNumber, BlakeTwo256>;
+
+/// Gathers all possible errors from this module.
+#[derive(Debug, Error)]
+pub enum ConnectionError {
+    #[error("🔌❌ Could not connect to the node at `{0}`.")]
+    CannotConnect(String),
+    #[error("🔌❌ Could not read block header from the node.")]
+    NoHeader,
+    #[error("🔌❌ Could not read block #{0} from the node.")]
+    NoBlock(BlockNumber),
+    #[error("🔌❌ Block #{0} has not been reached in time.")]
+    BlockNotReached(BlockNumber),
+    #[error("🔌❌ WebSocket connection to the node has been closed.")]
+    Disconnected,
+}
+
+/// Prepends `ws://` to `address` unless it already specifies a protocol.
+fn ensure_protocol(address: &str) -> String {
+    if address.starts_with("ws://") || address.starts_with("wss://") {
+        address.to_string()
+    } else {
+        format!("ws://{}", address)
+    }
+}
+
+/// Creates `Connection` (which fetches metadata and runtime version) without blocking.
+async fn create_api(address: &str) -> AnyResult<Connection> {
+    let owned_address = address.to_string();
+    spawn_blocking(move || Connection::new(WsRpcClient::new(&owned_address)))
+        .await?
+        .map_err(|_| ConnectionError::CannotConnect(address.to_string()).into())
+}
+
+/// Creates both clients talking to the node at `address`.
+///
+/// The asynchronous client goes first, so that an unreachable node is reported before the
+/// blocking one even tries.
+async fn connect(address: &str) -> AnyResult<Clients> {
+    let rpc = WsClientBuilder::default()
+        .build(address)
+        .await
+        .map_err(|_| ConnectionError::CannotConnect(address.to_string()))?;
+    let connection = create_api(address).await?;
+
+    Ok(Clients {
+        connection,
+        rpc: Arc::new(rpc),
+    })
+}
+
+/// Clients that are replaced after reconnection.
+struct Clients {
+    connection: Connection,
+    rpc: Arc<WsClient>,
+}
+
+/// Connection to a single node.
+///
+/// Apart from being a regular `AnyConnection` (so that it can be used for composing and signing
+/// extrinsics or reading storage), it keeps an asynchronous RPC client. The latter is used for
+/// submitting extrinsics and watching their status without blocking any thread (see
+/// `NodeConnection::submit_and_watch`).
+///
+/// Apart from that, it knows the base seed and the account pools of the environment the node
+/// belongs to, so that scenarios derive their accounts with `NodeConnection::accounts` (or
+/// `NodeConnection::derive_keypair`).
+///
+/// Clones share the clients, the nonce manager, the latency registry and the node health. When
+/// the node goes down, the supervisor (see `NodeConnection::supervise`) replaces the clients for
+/// all the clones.
+#[derive(Clone)]
+pub struct NodeConnection {
+    name: String,
+    address: String,
+    base_seed: Arc<String>,
+    pools: Arc<AccountPools>,
+    clients: Arc<RwLock<Clients>>,
+    nonces: Arc<NonceManager>,
+    latency: Arc<LatencyRegistry>,
+    health: Arc<RwLock<NodeHealth>>,
+}
+
+impl NodeConnection {
+    /// Connects to the node at `address` (the protocol prefix is optional). `name` is used only
+    /// for identifying the node in stats. Accounts will be derived from `base_seed` (unless their
+    /// pool has its own secret).
+    ///
+    /// `nonces` should be shared by all the nodes of the same chain, since an account has a single
+    /// nonce sequence no matter which node its extrinsics go through.
+    pub async fn new(
+        name: &str,
+        address: &str,
+        base_seed: &str,
+        pools: Arc<AccountPools>,
+        nonces: Arc<NonceManager>,
+    ) -> AnyResult<Self> {
+        let address = ensure_protocol(address);
+        let clients = connect(&address).await?;
+
+        let mut health = NodeHealth::new(name.to_string(), address.clone());
+        health.connected = true;
+
+        Ok(NodeConnection {
+            name: name.to_string(),
+            address,
+            base_seed: Arc::new(base_seed.to_string()),
+            pools,
+            clients: Arc::new(RwLock::new(clients)),
+            nonces,
+            latency: Arc::new(LatencyRegistry::default()),
+            health: Arc::new(RwLock::new(health)),
+        })
+    }
+
+    /// Returns a connection to the same node (sharing everything with `self`), which derives
+    /// accounts from `base_seed` instead.
+    pub fn with_base_seed(&self, base_seed: &str) -> Self {
+        NodeConnection {
+            base_seed: Arc::new(base_seed.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Name of the node.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// WS address of the node.
+    pub fn address(&self) -> &str {
+        self.address.as_str()
+    }
+
+    pub(crate) fn rpc(&self) -> Arc<WsClient> {
+        self.clients
+            .read()
+            .expect("Should acquire lock")
+            .rpc
+            .clone()
+    }
+
+    pub(crate) fn nonces(&self) -> &NonceManager {
+        &self.nonces
+    }
+
+    pub(crate) fn latency(&self) -> &LatencyRegistry {
+        &self.latency
+    }
+
+    /// Latency statistics of all the extrinsics tracked through this node.
+    pub fn latency_report(&self) -> Vec<LatencySummary> {
+        self.latency.report(&self.name)
+    }
+
+    /// Accounts of the pool `pool` (see `AccountPools`).
+    pub fn accounts(&self, pool: &str) -> AnyResult<Accounts<'_>> {
+        self.pools.accounts(pool, &self.base_seed)
+    }
+
+    /// Accounts of all the pools (see `AccountPools::all`).
+    pub fn all_accounts(&self) -> Vec<Accounts<'_>> {
+        self.pools.all(&self.base_seed)
+    }
+
+    /// Creates a `KeyPair` derived from the base seed of this node's environment with `seed` as
+    /// a derivation path (see `keypair_derived_from`).
+    pub fn derive_keypair<S: AsRef<str> + Display>(&self, seed: S) -> KeyPair {
+        keypair_derived_from(&self.base_seed, seed)
+    }
+
+    /// Runs `read` (a blocking query through `Connection`, e.g. a storage read) on a thread where
+    /// blocking is allowed.
+    pub async fn read_blocking<T, F>(&self, read: F) -> AnyResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> AnyResult<T> + Send + 'static,
+    {
+        let connection = self.as_connection();
+        spawn_blocking(move || read(&connection)).await?
+    }
+
+    /// Checks whether the runtime metadata contains `pallet` with `call`.
+    pub fn has_call(&self, pallet: &str, call: &str) -> bool {
+        self.as_connection()
+            .metadata
+            .pallet(pallet)
+            .map_or(false, |pallet| pallet.calls.contains_key(call))
+    }
+
+    /// Most recent observations of the node.
+    pub fn health(&self) -> NodeHealth {
+        self.health.read().expect("Should acquire lock").clone()
+    }
+
+    pub(crate) fn update_health<F: FnOnce(&mut NodeHealth)>(&self, update: F) {
+        update(&mut self.health.write().expect("Should acquire lock"))
+    }
+
+    /// Replaces both clients with new ones.
+    ///
+    /// Cached nonces are dropped as well: extrinsics that were waiting in the pool of the node
+    /// might have been lost together with it.
+    pub(crate) async fn reconnect(&self) -> AnyResult<()> {
+        let clients = connect(&self.address).await?;
+        *self.clients.write().expect("Should acquire lock") = clients;
+        self.nonces.clear().await;
+        Ok(())
+    }
+
+    /// Replaces `Connection` with a new one, so that it uses the current metadata.
+    pub(crate) async fn refresh_metadata(&self) -> AnyResult<()> {
+        let connection = create_api(&self.address).await?;
+        self.clients
+            .write()
+            .expect("Should acquire lock")
+            .connection = connection;
+        Ok(())
+    }
+
+    /// Reads header of the block `hash`. If `hash` is `None`, the best block is taken.
+    pub async fn header(&self, hash: Option<Hash>) -> AnyResult<Header> {
+        self.rpc()
+            .request::<Option<Header>>("chain_getHeader", rpc_params![hash])
+            .await?
+            .ok_or_else(|| ConnectionError::NoHeader.into())
+    }
+
+    /// Number of the best block.
+    pub async fn best_block_number(&self) -> AnyResult<BlockNumber> {
+        Ok(self.header(None).await?.number)
+    }
+
+    /// Number of the last finalized block.
+    pub async fn finalized_block_number(&self) -> AnyResult<BlockNumber> {
+        let finalized = self
+            .rpc()
+            .request::<Hash>("chain_getFinalizedHead", None)
+            .await?;
+        Ok(self.header(Some(finalized)).await?.number)
+    }
+
+    /// Waits until the best block is at least `number`, giving up if it takes more than
+    /// `block_time_limit` per block on average.
+    pub async fn wait_for_block(
+        &self,
+        number: BlockNumber,
+        block_time_limit: Duration,
+    ) -> AnyResult<()> {
+        let blocks = number.saturating_sub(self.best_block_number().await?);
+        let waiting = async {
+            while self.best_block_number().await? < number {
+                sleep(Duration::from_millis(500)).await;
+            }
+            AnyResult::<()>::Ok(())
+        };
+        timeout(block_time_limit * (blocks + 1), waiting)
+            .await
+            .map_err(|_| ConnectionError::BlockNotReached(number))?
+    }
+
+    /// Hash of the block `number` (in the best chain).
+    pub(crate) async fn block_hash(&self, number: BlockNumber) -> AnyResult<Hash> {
+        self.rpc()
+            .request::<Option<Hash>>("chain_getBlockHash", rpc_params![number])
+            .await?
+            .ok_or_else(|| ConnectionError::NoBlock(number).into())
+    }
+
+    /// Position of `xt` (hex-encoded, as submitted) among the extrinsics of the block `number`, if
+    /// it is there.
+    pub async fn extrinsic_index(&self, number: BlockNumber, xt: &str) -> AnyResult<Option<u32>> {
+        let hash = self.block_hash(number).await?;
+        let block = self
+            .rpc()
+            .request::<Value>("chain_getBlock", rpc_params![hash])
+            .await?;
+        let extrinsics = block["block"]["extrinsics"]
+            .as_array()
+            .ok_or(ConnectionError::NoBlock(number))?;
+        Ok(extrinsics
+            .iter()
+            .position(|extrinsic| {
+                extrinsic
+                    .as_str()
+                    .map_or(false, |extrinsic| extrinsic.eq_ignore_ascii_case(xt))
+            })
+            .map(|index| index as u32))
+    }
+
+    /// Generates new session keys in the keystore of the node and returns their public parts
+    /// (SCALE-encoded, just as `Session::set_keys` expects them).
+    ///
+    /// `author_rotateKeys` is an unsafe RPC method, so the node must be run with
+    /// `--rpc-methods Unsafe`.
+    pub async fn rotate_keys(&self) -> AnyResult<Vec<u8>> {
+        let keys = self
+            .rpc()
+            .request::<String>("author_rotateKeys", None)
+            .await?;
+        Ok(Vec::from_hex(keys.trim_start_matches("0x"))?)
+    }
+
+    /// Dry-runs a contract call (`contracts_call`) described by `request` (a JSON version of
+    /// `pallet_contracts_rpc::CallRequest`) on top of the best block. Returns the raw
+    /// `ContractResult`.
+    pub async fn contracts_call(&self, request: Value) -> AnyResult<Value> {
+        Ok(self
+            .rpc()
+            .request::<Value>("contracts_call", rpc_params![request])
+            .await?)
+    }
+
+    /// Like `NodeConnection::contracts_call`, but for an instantiation (`contracts_instantiate`).
+    pub async fn contracts_instantiate(&self, request: Value) -> AnyResult<Value> {
+        Ok(self
+            .rpc()
+            .request::<Value>("contracts_instantiate", rpc_params![request])
+            .await?)
+    }
+}
+
+impl AnyConnection for NodeConnection {
+    fn as_connection(&self) -> Connection {
+        self.clients
+            .read()
+            .expect("Should acquire lock")
+            .connection
+            .clone()
+    }
+}
+
+impl Availability for NodeConnection {
+    fn is_available(&self) -> bool {
+        self.health.read().expect("Should acquire lock").connected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::ensure_protocol;
+
+    #[test]
+    fn adds_missing_protocol() {
+        assert_eq!("ws://127.0.0.1:9944", ensure_protocol("127.0.0.1:9944"));
+    }
+
+    #[test]
+    fn keeps_existing_protocol() {
+        assert_eq!(
+            "ws://127.0.0.1:9944",
+            ensure_protocol("ws://127.0.0.1:9944")
+        );
+        assert_eq!(
+            "wss://ws.test.azero.dev",
+            ensure_protocol("wss://ws.test.azero.dev")
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Endowing bot accounts.
+//!
+//! `set_up` uses it to give the accounts of every pool their target balance before scenarios are
+//! launched. The faucet (in `bin`) uses it to top up the accounts that have run low in the
+//! meantime (scenarios keep paying fees and transferring tokens away).
+
+use aleph_client::{
+    send_xt,
+    substrate_api_client::{
+        compose_call, compose_extrinsic, AccountId, Balance, GenericAddress, XtStatus,
+    },
+    AnyConnection, KeyPair, RootConnection,
+};
+use anyhow::Result as AnyResult;
+use codec::Compact;
+use futures::future::try_join_all;
+
+use common::LatencySample;
+
+use crate::{send_managed_xt, NodeConnection, WaitFor};
+
+/// Sets free balance of every account in `accounts` to `amount` (with a single `Utility::batch_all`
+/// of sudo calls, so that either all of the accounts are endowed or none is).
+pub fn set_balances(connection: &RootConnection, accounts: Vec<AccountId>, amount: Balance) {
+    let metadata = connection.as_connection().metadata;
+    let xts = accounts
+        .iter()
+        .map(|account| {
+            let endowment_call = compose_call!(
+                metadata,
+                "Balances",
+                "set_balance",
+                GenericAddress::Id(account.clone()),
+                Compact(amount), // free balance
+                Compact(0u128)   // reserved balance
+            );
+            compose_call!(metadata, "Sudo", "sudo", endowment_call)
+        })
+        .collect::<Vec<_>>();
+    let xt = compose_extrinsic!(connection.as_connection(), "Utility", "batch_all", xts);
+    send_xt(connection, xt, Some("Set endowment"), XtStatus::Finalized);
+}
+
+/// Reads free balance of `account`. An account that does not exist has nothing.
+pub async fn free_balance(connection: &NodeConnection, account: &AccountId) -> AnyResult<Balance> {
+    let account = account.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_account_data(&account)?.map_or(0, |data| data.free)))
+        .await
+}
+
+/// Reads free balances of all `accounts` (concurrently), in the same order.
+pub async fn free_balances(
+    connection: &NodeConnection,
+    accounts: &[AccountId],
+) -> AnyResult<Vec<Balance>> {
+    try_join_all(
+        accounts
+            .iter()
+            .map(|account| free_balance(connection, account)),
+    )
+    .await
+}
+
+/// For every account whose balance has fallen below `threshold`, returns how much it lacks to
+/// `target`.
+pub fn top_ups(
+    balances: Vec<(AccountId, Balance)>,
+    target: Balance,
+    threshold: Balance,
+) -> Vec<(AccountId, Balance)> {
+    balances
+        .into_iter()
+        .filter(|(_, balance)| *balance < threshold)
+        .map(|(account, balance)| (account, target.saturating_sub(balance)))
+        .filter(|(_, amount)| *amount > 0)
+        .collect()
+}
+
+/// Sends all `transfers` (pairs of receiver and amount) from `sender` in a single
+/// `Utility::batch_all`, so that a failing transfer reverts the others instead of being skipped
+/// silently.
+pub async fn transfer_batch(
+    connection: &NodeConnection,
+    sender: &KeyPair,
+    transfers: &[(AccountId, Balance)],
+    wait_for: WaitFor,
+) -> AnyResult<LatencySample> {
+    let metadata = connection.as_connection().metadata;
+    let calls = transfers
+        .iter()
+        .map(|(receiver, amount)| {
+            compose_call!(
+                metadata,
+                "Balances",
+                "transfer",
+                GenericAddress::Id(receiver.clone()),
+                Compact(*amount)
+            )
+        })
+        .collect::<Vec<_>>();
+    let call = compose_call!(metadata, "Utility", "batch_all", calls);
+    send_managed_xt(
+        connection,
+        sender,
+        call,
+        "Utility::batch_all",
+        wait_for.into(),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use aleph_client::substrate_api_client::AccountId;
+
+    use crate::endowment::top_ups;
+
+    #[test]
+    fn tops_up_only_accounts_below_threshold() {
+        let poor = AccountId::new([1; 32]);
+        let rich = AccountId::new([2; 32]);
+        let balances = vec![(poor.clone(), 30), (rich, 60)];
+
+        assert_eq!(vec![(poor, 70)], top_ups(balances, 100, 50));
+    }
+}
@@ -0,0 +1,109 @@
+//! Reading the events emitted by a particular extrinsic.
+//!
+//! A listener (see `SingleEventListener`) sees the events of all the extrinsics. Many events carry
+//! nothing that would tell whose extrinsic has emitted them (e.g. `Utility::BatchCompleted` or
+//! `Sudo::Sudid`), so they could come from another bot. Here, we read the events of the block
+//! instead and keep only the ones emitted by the extrinsic at the given position.
+
+use ac_node_api::events::{EventsDecoder, Raw};
+use aleph_client::{AnyConnection, BlockNumber};
+use anyhow::Result as AnyResult;
+use codec::Encode;
+use hex::FromHex;
+use jsonrpsee::{core::client::ClientT, rpc_params};
+
+use crate::{
+    event_listening::{Event, ListeningError},
+    NodeConnection,
+};
+
+/// Storage key of `System::Events` (`twox128("System") ++ twox128("Events")`).
+const EVENTS_KEY: &str = "0x26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7";
+
+/// Where an extrinsic has landed: the block and the position among its extrinsics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XtLocation {
+    pub block: BlockNumber,
+    pub index: u32,
+}
+
+/// Encoded `Phase::ApplyExtrinsic(index)`. Comparing encodings spares us a dependency on
+/// `frame_system` just for the type.
+fn apply_extrinsic_phase(index: u32) -> Vec<u8> {
+    (0u8, index).encode()
+}
+
+impl NodeConnection {
+    /// Finds `xt` (hex-encoded, as submitted) among the extrinsics of the block `number`.
+    pub async fn locate(&self, number: BlockNumber, xt: &str) -> AnyResult<XtLocation> {
+        let index = self
+            .extrinsic_index(number, xt)
+            .await?
+            .ok_or(ListeningError::NotInBlock(number))?;
+        Ok(XtLocation {
+            block: number,
+            index,
+        })
+    }
+
+    /// Looks for any of `xts` (hex-encoded, as submitted) in the blocks of the best chain, starting
+    /// from the block `since`. Useful for checking whether an extrinsic, whose watching has failed,
+    /// has made it after all.
+    pub async fn find_extrinsic(
+        &self,
+        since: BlockNumber,
+        xts: &[String],
+    ) -> AnyResult<Option<XtLocation>> {
+        if xts.is_empty() {
+            return Ok(None);
+        }
+        for block in since..=self.best_block_number().await? {
+            for xt in xts {
+                if let Some(index) = self.extrinsic_index(block, xt).await? {
+                    return Ok(Some(XtLocation { block, index }));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// All the events of kind `E` emitted by the extrinsic at `location`, in the order of
+    /// emission.
+    pub async fn extrinsic_events<E: Event>(&self, location: XtLocation) -> AnyResult<Vec<E>> {
+        let hash = self.block_hash(location.block).await?;
+        let encoded = self
+            .rpc()
+            .request::<Option<String>>("state_getStorage", rpc_params![EVENTS_KEY, hash])
+            .await?
+            .unwrap_or_default();
+        let encoded = Vec::from_hex(encoded.trim_start_matches("0x"))?;
+
+        let decoder = EventsDecoder::new(self.as_connection().metadata);
+        let phase = apply_extrinsic_phase(location.index);
+        Ok(decoder
+            .decode_events(&mut encoded.as_slice())?
+            .into_iter()
+            .filter(|(event_phase, _)| event_phase.encode() == phase)
+            .filter_map(|(_, event)| match event {
+                Raw::Event(event) if (&*event.pallet, &*event.variant) == E::kind() => {
+                    E::decode(&mut &event.data[..]).ok()
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// The first event of kind `E` emitted by the extrinsic at `location` that satisfies
+    /// `matcher`. Fails with `ListeningError::NoEventSpotted` if there is none.
+    pub async fn extrinsic_event<E: Event, M: Fn(&E) -> bool>(
+        &self,
+        location: XtLocation,
+        matcher: M,
+    ) -> AnyResult<E> {
+        self.extrinsic_events::<E>(location)
+            .await?
+            .into_iter()
+            .find(|event| matcher(event))
+            .ok_or_else(|| ListeningError::NoEventSpotted.into())
+    }
+}
@@ -1,17 +1,24 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
-use aleph_client::substrate_api_client;
+use aleph_client::{substrate_api_client, BlockNumber};
 use codec::Decode;
 use substrate_api_client::AccountId;
 use thiserror::Error;
 
 pub use event_derive::Event;
-pub use single_event::{with_event_listening, with_event_matching, SingleEventListener};
+pub use extrinsic_events::XtLocation;
+pub use single_event::{
+    send_with_event, with_event_listening, with_event_matching, SingleEventListener,
+};
 
+mod extrinsic_events;
 #[cfg(test)]
 mod macro_tests;
 mod single_event;
 
+/// How long we wait for an event after the extrinsic has been finalized.
+pub const EVENT_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Gathers all possible errors from this module.
 #[derive(Debug, Error)]
 pub enum ListeningError {
@@ -19,6 +26,10 @@ pub enum ListeningError {
     CannotSubscribe,
     #[error("⏳❌ Expected event has not been emitted.")]
     NoEventSpotted,
+    #[error("⏳❌ Extrinsic has not been reported as included in any block.")]
+    NotIncluded,
+    #[error("⏳❌ Extrinsic cannot be found in block #{0}.")]
+    NotInBlock(BlockNumber),
 }
 
 /// Every event is identified by two coordinates: pallet name and event name,
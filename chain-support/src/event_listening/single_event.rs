@@ -1,18 +1,21 @@
 use std::{fmt::Debug, future::Future, time::Duration};
 
 use ac_node_api::events::{EventsDecoder, Raw};
-use aleph_client::AnyConnection;
+use aleph_client::{substrate_api_client::XtStatus, AnyConnection, KeyPair};
 use anyhow::Result as AnyResult;
+use codec::Encode;
 use hex::FromHex;
 use tokio::{
     sync::oneshot::{channel, Receiver, Sender},
-    task::JoinHandle,
+    task::{spawn_blocking, JoinHandle},
     time::{sleep, timeout},
 };
 
+use common::LatencySample;
+
 use crate::{
-    do_async,
-    event_listening::{Event, ListeningError},
+    event_listening::{Event, ListeningError, EVENT_TIMEOUT},
+    send_managed_xt, NodeConnection,
 };
 
 type EventsOut = std::sync::mpsc::Receiver<String>;
@@ -34,8 +37,7 @@ pub struct SingleEventListener<E: Event> {
 ///
 /// The channel itself is blocking, i.e. not awaitable (API requirement).
 ///
-/// The only blocking call in this module. It is implemented as an outside function to enable
-/// calling it with `do_async` macro.
+/// The only blocking call in this module. It should be run with `spawn_blocking`.
 fn subscribe_for_events<C: AnyConnection>(connection: &C) -> AnyResult<EventsOut> {
     let (events_in, events_out) = std::sync::mpsc::channel();
     connection
@@ -124,7 +126,10 @@ impl<E: Event> SingleEventListener<E> {
         let (event_tx, event_rx) = channel::<E>();
         let (cancel_tx, cancel_rx) = channel();
 
-        let events_out = do_async!(subscribe_for_events, &connection)??;
+        let events_out = {
+            let connection = connection.as_connection();
+            spawn_blocking(move || subscribe_for_events(&connection)).await??
+        };
         let decoder = EventsDecoder::new(connection.as_connection().metadata);
         let listening_handle = tokio::spawn(Self::listen_for_event(
             matcher, event_tx, cancel_rx, events_out, decoder,
@@ -224,3 +229,22 @@ pub async fn with_event_matching<
         }
     }
 }
+
+/// Sends `call` signed by `signer` (see `send_managed_xt`; tracked as `label`, e.g.
+/// `"Staking::bond"`) and waits until it is finalized. Returns the latency sample together with
+/// the event matching `matcher`, which should have been emitted by the call.
+pub async fn send_with_event<Call: Encode + Clone, E: Event, M: Fn(&E) -> bool + Send + 'static>(
+    connection: &NodeConnection,
+    signer: &KeyPair,
+    call: Call,
+    label: &'static str,
+    matcher: M,
+) -> AnyResult<(LatencySample, E)> {
+    with_event_matching(
+        connection,
+        matcher,
+        EVENT_TIMEOUT,
+        send_managed_xt(connection, signer, call, label, XtStatus::Finalized),
+    )
+    .await
+}
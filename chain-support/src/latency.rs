@@ -0,0 +1,166 @@
+//! Instrumentation of extrinsic submission.
+//!
+//! Every tracked extrinsic yields a single `LatencySample`. Samples are aggregated in a registry kept
+//! by every node connection, per scenario (read from `common::current_scenario()`) and per call
+//! type (e.g. `Balances::transfer`). Extrinsics that do not reach the expected status are counted
+//! as failures.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use aleph_client::substrate_api_client::{UncheckedExtrinsicV4, XtStatus};
+use anyhow::Result as AnyResult;
+use codec::Encode;
+use futures::StreamExt;
+use thiserror::Error;
+use tokio::time::timeout;
+
+use common::{current_scenario, Ident, LatencySample, LatencySummary};
+
+use crate::{NodeConnection, SubmissionError, XtStatusUpdate};
+
+/// How many recent samples are kept for every (scenario, call) pair.
+const SAMPLES_KEPT: usize = 1000;
+
+/// For how long we are willing to wait for an extrinsic to reach the expected status.
+const TRACKING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Gathers all possible errors from this module.
+#[derive(Debug, Error)]
+pub enum TrackingError {
+    #[error("⏱️❌ Extrinsic has not reached the expected status within {0:?}.")]
+    Timeout(Duration),
+}
+
+/// Observations for a single (scenario, call) pair.
+#[derive(Default)]
+struct Observations {
+    samples: VecDeque<LatencySample>,
+    failures: usize,
+}
+
+/// Observations of all the extrinsics sent through a single node.
+#[derive(Default)]
+pub(crate) struct LatencyRegistry {
+    observations: Mutex<HashMap<(Option<Ident>, &'static str), Observations>>,
+}
+
+impl LatencyRegistry {
+    /// Applies `update` to the observations of `call`. They are attributed to the scenario being
+    /// played within the current task.
+    fn observe<F: FnOnce(&mut Observations)>(&self, call: &'static str, update: F) {
+        let mut observations = self.observations.lock().expect("Should acquire lock");
+        update(observations.entry((current_scenario(), call)).or_default())
+    }
+
+    /// Returns percentiles for all (scenario, call) pairs observed so far. `node` is the name of
+    /// the node owning this registry.
+    pub(crate) fn report(&self, node: &str) -> Vec<LatencySummary> {
+        self.observations
+            .lock()
+            .expect("Should acquire lock")
+            .iter()
+            .map(|((scenario, call), observations)| {
+                LatencySummary::new(
+                    scenario.clone(),
+                    node.to_string(),
+                    call.to_string(),
+                    &observations.samples,
+                    observations.failures,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Saves `sample` for `call` sent through `connection`.
+pub fn record_latency(connection: &NodeConnection, call: &'static str, sample: LatencySample) {
+    connection.latency().observe(call, |observations| {
+        if observations.samples.len() == SAMPLES_KEPT {
+            observations.samples.pop_front();
+        }
+        observations.samples.push_back(sample);
+    })
+}
+
+/// Counts a failed attempt of sending `call` through `connection`.
+pub fn record_failure(connection: &NodeConnection, call: &'static str) {
+    connection
+        .latency()
+        .observe(call, |observations| observations.failures += 1)
+}
+
+/// Submits `xt` and waits until it reaches `until` status. Timestamps of all the intermediate
+/// stages (together with the block numbers) are recorded by `connection` under `call` (e.g.
+/// `"Balances::transfer"`).
+///
+/// Statuses other than `Ready`, `InBlock` and `Finalized` are treated like `Finalized`.
+pub async fn send_tracked_xt<Call: Encode>(
+    connection: &NodeConnection,
+    xt: UncheckedExtrinsicV4<Call>,
+    call: &'static str,
+    until: XtStatus,
+) -> AnyResult<LatencySample> {
+    let mut sample = LatencySample::default();
+
+    let submitted = Instant::now();
+    let updates = match connection.submit_and_watch(xt).await {
+        Ok(updates) => updates,
+        Err(e) => {
+            record_failure(connection, call);
+            return Err(e);
+        }
+    };
+    futures::pin_mut!(updates);
+
+    let result: Result<AnyResult<()>, _> = timeout(TRACKING_TIMEOUT, async {
+        while let Some(update) = updates.next().await {
+            let update = update?;
+            match &update {
+                XtStatusUpdate::Ready => sample.to_ready = Some(submitted.elapsed()),
+                XtStatusUpdate::InBlock(block) => {
+                    sample.to_in_block = Some(submitted.elapsed());
+                    sample.in_block_number = Some(connection.header(Some(*block)).await?.number);
+                }
+                // It would wait there until the gap is filled, which might never happen. Giving
+                // up right away lets the nonce manager resync.
+                XtStatusUpdate::Future => return Err(SubmissionError::NonceGap.into()),
+                XtStatusUpdate::Retracted(_) => {
+                    sample.to_in_block = None;
+                    sample.in_block_number = None;
+                }
+                XtStatusUpdate::Finalized(_) => {
+                    sample.to_finalized = Some(submitted.elapsed());
+                    sample.finalized_number = Some(connection.finalized_block_number().await?);
+                }
+                _ if update.is_rejection() => {
+                    return Err(SubmissionError::Rejected(update.clone()).into())
+                }
+                _ => {}
+            }
+            if update.reaches(until) {
+                return Ok(());
+            }
+        }
+        Err(SubmissionError::SubscriptionClosed.into())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            record_latency(connection, call, sample.clone());
+            Ok(sample)
+        }
+        Ok(Err(e)) => {
+            record_failure(connection, call);
+            Err(e)
+        }
+        Err(_) => {
+            record_failure(connection, call);
+            Err(TrackingError::Timeout(TRACKING_TIMEOUT).into())
+        }
+    }
+}
@@ -1,31 +1,70 @@
-// Needed for `do_async!`.
-#![feature(fn_traits)]
-
-use std::{env, fmt::Display};
+use std::fmt::Display;
 
 pub use aleph_client::{
-    create_connection, keypair_from_string, send_xt, try_send_xt, AnyConnection, Connection,
-    KeyPair, RootConnection, SignedConnection,
+    account_from_keypair, create_connection, keypair_from_string, send_xt, try_send_xt,
+    AnyConnection, Connection, KeyPair, RootConnection, SignedConnection,
 };
 
+pub use accounts::{AccountPool, AccountPools, Accounts, AccountsError};
+pub use connection::{ConnectionError, Header, NodeConnection};
+pub use endowment::{free_balance, free_balances, set_balances, top_ups, transfer_batch};
 pub use event_listening::{
-    with_event_listening, with_event_matching, Event, EventKind, ListeningError,
-    SingleEventListener, Transfer as TransferEvent,
+    send_with_event, with_event_listening, with_event_matching, Event, EventKind, ListeningError,
+    SingleEventListener, Transfer as TransferEvent, XtLocation, EVENT_TIMEOUT,
 };
+pub use latency::{record_failure, record_latency, send_tracked_xt, TrackingError};
+pub use nodes::{NodePool, NodeSelection, NodeSelector, NodesError};
+pub use nonce::{send_located_xt, send_managed_xt, send_xt_with_nonce, NonceManager};
+pub use preflight::{check_requirements, Finding, Preflight, Requirements, Severity};
+pub use registry::{register_combinators, ChainScenario, ChainScenarioKind, ChainScenarioRegistry};
+pub use secret::{SecretError, SecretSource};
+pub use submission::{SubmissionError, WaitFor, XtStatusUpdate};
 
+mod accounts;
+mod connection;
+mod endowment;
 mod event_listening;
-mod macros;
+mod latency;
+mod nodes;
+mod nonce;
+mod preflight;
+mod registry;
+mod secret;
+mod submission;
+mod supervisor;
+
+/// Env variable with the base seed, used when no other secret source is configured.
+pub const SECRET_PHRASE_SEED: &str = "SECRET_PHRASE_SEED";
 
-/// Creates a new derived `KeyPair` from provided `seed` as a derivation path.
+/// Creates a new `KeyPair` derived from `base_seed` (see `SecretSource`) with `seed` as
+/// a derivation path.
 ///
-/// The base seed is empty by default, but can be overridden with env `SECRET_PHRASE_SEED`.
 /// Assumes that `seed` is already prefixed with a derivation delimiter (either `/` or `//`).
-pub fn keypair_derived_from_seed<S: AsRef<str> + Display>(seed: S) -> KeyPair {
-    let base_seed = env::var("SECRET_PHRASE_SEED").unwrap_or_default();
+pub fn keypair_derived_from<S: AsRef<str> + Display>(base_seed: &str, seed: S) -> KeyPair {
     let full_seed = format!("{}{}", base_seed, seed);
     keypair_from_string(&*full_seed)
 }
 
+/// Defines a function returning the name of the account pool that a scenario uses by default, to
+/// be referred to by `#[serde(default = "...")]`. Unless given explicitly, the function is named
+/// `default_pool`.
+///
+/// ```ignore
+/// default_pool!("Identity");
+/// default_pool!(fn default_registrar_pool = "IdentityRegistrar");
+/// ```
+#[macro_export]
+macro_rules! default_pool {
+    ($pool:literal) => {
+        $crate::default_pool!(fn default_pool = $pool);
+    };
+    ($vis:vis fn $function:ident = $pool:literal) => {
+        $vis fn $function() -> String {
+            $pool.to_string()
+        }
+    };
+}
+
 /// A single token is 10^12 rappens. This value corresponds to the constants defined in
 /// `aleph-node::primitives` (`TOKEN_DECIMALS` and `TOKEN`).
 pub const DECIMALS: u128 = 1_000_000_000_000;
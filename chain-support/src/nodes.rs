@@ -0,0 +1,222 @@
+//! Working with several nodes at once.
+//!
+//! `NodePool` keeps connections to all the configured nodes. Every scheduled scenario gets its
+//! own `NodeSelector`, which picks a node for every run according to `NodeSelection`.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use anyhow::Result as AnyResult;
+use rand::{seq::IteratorRandom, thread_rng};
+use serde::Deserialize;
+use thiserror::Error;
+
+use common::{Availability, ConnectionProvider, LatencySummary, NodeHealth};
+
+use crate::{AccountPools, NodeConnection, NonceManager};
+
+/// Gathers all possible errors from this module.
+#[derive(Debug, Error)]
+pub enum NodesError {
+    #[error("🛰️❌ There is no node named `{0}`.")]
+    UnknownNode(String),
+    #[error("🛰️❌ At least one node has to be configured.")]
+    NoNodes,
+}
+
+/// How a scenario chooses a node for its runs.
+///
+/// Use:
+/// - `Pinned(name)` for always using the node `name`; runs are skipped while it is down
+/// - `RoundRobin` for taking consecutive available nodes in turns
+/// - `Random` for taking a random available node every time
+#[derive(Clone, Debug, Deserialize)]
+pub enum NodeSelection {
+    Pinned(String),
+    RoundRobin,
+    Random,
+}
+
+impl Default for NodeSelection {
+    fn default() -> Self {
+        NodeSelection::RoundRobin
+    }
+}
+
+/// Connections to all the nodes of a single environment, shared by all its scenarios.
+#[derive(Clone)]
+pub struct NodePool {
+    nodes: Arc<Vec<NodeConnection>>,
+}
+
+impl NodePool {
+    /// Connects to every node from `endpoints` (pairs of name and address). Accounts from `pools`
+    /// will be derived from `base_seed` (unless a pool has its own secret). All the nodes share a
+    /// single `NonceManager`.
+    pub async fn connect(
+        endpoints: &[(String, String)],
+        base_seed: &str,
+        pools: Arc<AccountPools>,
+    ) -> AnyResult<Self> {
+        if endpoints.is_empty() {
+            return Err(NodesError::NoNodes.into());
+        }
+
+        let nonces = Arc::new(NonceManager::default());
+        let mut nodes = Vec::with_capacity(endpoints.len());
+        for (name, address) in endpoints {
+            nodes.push(
+                NodeConnection::new(name, address, base_seed, pools.clone(), nonces.clone())
+                    .await?,
+            );
+        }
+        Ok(NodePool {
+            nodes: Arc::new(nodes),
+        })
+    }
+
+    /// Returns a pool of the same nodes (see `NodeConnection::with_base_seed`), which derives
+    /// accounts from `base_seed` instead.
+    pub fn with_base_seed(&self, base_seed: &str) -> Self {
+        NodePool {
+            nodes: Arc::new(
+                self.nodes
+                    .iter()
+                    .map(|node| node.with_base_seed(base_seed))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Spawns supervisors for all the nodes (see `NodeConnection::supervise`).
+    pub fn supervise(&self) {
+        for node in self.nodes.iter() {
+            node.supervise();
+        }
+    }
+
+    /// All the nodes, in the configuration order.
+    pub fn nodes(&self) -> &[NodeConnection] {
+        &self.nodes
+    }
+
+    /// The first configured node.
+    pub fn first(&self) -> &NodeConnection {
+        &self.nodes[0]
+    }
+
+    /// Most recent observations of all the nodes.
+    pub fn health(&self) -> Vec<NodeHealth> {
+        self.nodes.iter().map(NodeConnection::health).collect()
+    }
+
+    /// Latency statistics gathered by all the nodes.
+    pub fn latency_report(&self) -> Vec<LatencySummary> {
+        self.nodes
+            .iter()
+            .flat_map(NodeConnection::latency_report)
+            .collect()
+    }
+
+    /// Creates a selector following `selection` within this pool.
+    pub fn selector(&self, selection: NodeSelection) -> AnyResult<NodeSelector> {
+        if let NodeSelection::Pinned(name) = &selection {
+            if !self.nodes.iter().any(|node| node.name() == name) {
+                return Err(NodesError::UnknownNode(name.clone()).into());
+            }
+        }
+        Ok(NodeSelector {
+            pool: self.clone(),
+            selection,
+            next: AtomicUsize::new(0),
+        })
+    }
+}
+
+/// Picks a node for every run of a single scenario.
+pub struct NodeSelector {
+    pool: NodePool,
+    selection: NodeSelection,
+    /// Where the next round-robin search starts.
+    next: AtomicUsize,
+}
+
+impl NodeSelector {
+    /// Returns index of the node to be used, given names and availability of all the nodes.
+    fn pick(&self, nodes: &[(&str, bool)]) -> Option<usize> {
+        let mut available = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, available))| *available);
+
+        match &self.selection {
+            NodeSelection::Pinned(pinned) => available
+                .find(|(_, (name, _))| *name == pinned.as_str())
+                .map(|(idx, _)| idx),
+            NodeSelection::RoundRobin => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % nodes.len();
+                (0..nodes.len())
+                    .map(|offset| (start + offset) % nodes.len())
+                    .find(|idx| nodes[*idx].1)
+            }
+            NodeSelection::Random => available.choose(&mut thread_rng()).map(|(idx, _)| idx),
+        }
+    }
+}
+
+impl ConnectionProvider<NodeConnection> for NodeSelector {
+    fn next_connection(&self) -> Option<NodeConnection> {
+        let nodes = self.pool.nodes();
+        let state = nodes
+            .iter()
+            .map(|node| (node.name(), node.is_available()))
+            .collect::<Vec<_>>();
+        self.pick(&state).map(|idx| nodes[idx].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicUsize, Arc};
+
+    use crate::nodes::{NodePool, NodeSelection, NodeSelector};
+
+    fn selector(selection: NodeSelection) -> NodeSelector {
+        NodeSelector {
+            pool: NodePool {
+                nodes: Arc::new(vec![]),
+            },
+            selection,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    #[test]
+    fn pinned_node_is_taken_only_when_available() {
+        let selector = selector(NodeSelection::Pinned("b".to_string()));
+
+        assert_eq!(Some(1), selector.pick(&[("a", true), ("b", true)]));
+        assert_eq!(None, selector.pick(&[("a", true), ("b", false)]));
+    }
+
+    #[test]
+    fn round_robin_skips_unavailable_nodes() {
+        let selector = selector(NodeSelection::RoundRobin);
+        let nodes = [("a", true), ("b", false), ("c", true)];
+
+        assert_eq!(Some(0), selector.pick(&nodes));
+        assert_eq!(Some(2), selector.pick(&nodes));
+        assert_eq!(Some(2), selector.pick(&nodes));
+        assert_eq!(Some(0), selector.pick(&nodes));
+    }
+
+    #[test]
+    fn random_selection_fails_over() {
+        let selector = selector(NodeSelection::Random);
+
+        assert_eq!(Some(1), selector.pick(&[("a", false), ("b", true)]));
+        assert_eq!(None, selector.pick(&[("a", false), ("b", false)]));
+    }
+}
@@ -0,0 +1,228 @@
+//! Client-side nonce management.
+//!
+//! `compose_extrinsic!` reads the nonce from the chain every time, so an account cannot have more
+//! than one extrinsic in flight: the next one would get the same nonce. `NonceManager` hands out
+//! consecutive nonces instead and consults the chain only when it knows nothing about the account
+//! (or after being told to resync).
+
+use std::{collections::HashMap, future::Future};
+
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{
+        compose_extrinsic_offline, AccountId, Pair, UncheckedExtrinsicV4, XtStatus,
+    },
+    KeyPair,
+};
+use anyhow::Result as AnyResult;
+use codec::Encode;
+use jsonrpsee::{core::client::ClientT, rpc_params};
+use sp_runtime::generic::Era;
+use tokio::sync::Mutex;
+
+use common::LatencySample;
+
+use crate::{send_tracked_xt, ListeningError, NodeConnection, XtLocation};
+
+/// Nonce cache shared by all the tasks sending extrinsics through the nodes of the same `NodePool`.
+#[derive(Default)]
+pub struct NonceManager {
+    next: Mutex<HashMap<AccountId, u32>>,
+}
+
+impl NonceManager {
+    /// Returns a nonce for the next extrinsic of `account`. If the account is not known yet, its
+    /// current nonce is obtained with `fetch`.
+    ///
+    /// The lock is held while fetching, so that concurrent callers do not read the same value.
+    pub async fn next<F, Fut>(&self, account: &AccountId, fetch: F) -> AnyResult<u32>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = AnyResult<u32>>,
+    {
+        let mut next = self.next.lock().await;
+        let nonce = match next.get(account) {
+            Some(nonce) => *nonce,
+            None => fetch().await?,
+        };
+        next.insert(account.clone(), nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Forgets everything about `account`. The next call to `Self::next` will read its nonce from
+    /// the chain again.
+    ///
+    /// Should be called whenever an extrinsic of `account` has been rejected: either its nonce
+    /// was already used (stale) or it left a gap (and the following extrinsics are stuck in the
+    /// future queue).
+    pub async fn resync(&self, account: &AccountId) {
+        self.next.lock().await.remove(account);
+    }
+
+    /// Forgets everything about all the accounts (like `Self::resync` for each of them).
+    pub async fn clear(&self) {
+        self.next.lock().await.clear();
+    }
+}
+
+impl NodeConnection {
+    /// Reads the next nonce of `account`, taking into account extrinsics waiting in the pool.
+    async fn account_next_index(&self, account: &AccountId) -> AnyResult<u32> {
+        Ok(self
+            .rpc()
+            .request::<u32>("system_accountNextIndex", rpc_params![account])
+            .await?)
+    }
+
+    /// Returns a nonce for the next extrinsic of `account` (see `NonceManager::next`).
+    pub async fn next_nonce(&self, account: &AccountId) -> AnyResult<u32> {
+        self.nonces()
+            .next(account, || self.account_next_index(account))
+            .await
+    }
+
+    /// Drops cached nonce of `account` (see `NonceManager::resync`).
+    pub async fn resync_nonce(&self, account: &AccountId) {
+        self.nonces().resync(account).await
+    }
+
+    /// Signs `call` by `signer` with explicitly provided `nonce`.
+    pub fn sign_with_nonce<Call: Encode + Clone>(
+        &self,
+        signer: &KeyPair,
+        call: Call,
+        nonce: u32,
+    ) -> UncheckedExtrinsicV4<Call> {
+        let api = self.as_connection();
+        compose_extrinsic_offline!(
+            signer.clone(),
+            call,
+            nonce,
+            Era::Immortal,
+            api.genesis_hash,
+            api.genesis_hash,
+            api.runtime_version.spec_version,
+            api.runtime_version.transaction_version
+        )
+    }
+}
+
+/// Like `send_tracked_xt`, but `call` is signed by `signer` with a nonce coming from the nonce
+/// manager of `connection`. Therefore, many such extrinsics from a single account can be in flight
+/// at the same time.
+///
+/// Any failure makes the manager resync the nonce of `signer`.
+pub async fn send_managed_xt<Call: Encode + Clone>(
+    connection: &NodeConnection,
+    signer: &KeyPair,
+    call: Call,
+    label: &'static str,
+    until: XtStatus,
+) -> AnyResult<LatencySample> {
+    let nonce = connection.next_nonce(&account_from_keypair(signer)).await?;
+    send_xt_with_nonce(connection, signer, call, nonce, label, until).await
+}
+
+/// Like `send_managed_xt`, but with `nonce` already taken from the nonce manager (with
+/// `NodeConnection::next_nonce`).
+pub async fn send_xt_with_nonce<Call: Encode + Clone>(
+    connection: &NodeConnection,
+    signer: &KeyPair,
+    call: Call,
+    nonce: u32,
+    label: &'static str,
+    until: XtStatus,
+) -> AnyResult<LatencySample> {
+    let account = account_from_keypair(signer);
+    let xt = connection.sign_with_nonce(signer, call, nonce);
+
+    let result = send_tracked_xt(connection, xt, label, until).await;
+    if result.is_err() {
+        connection.resync_nonce(&account).await;
+    }
+    result
+}
+
+/// Like `send_managed_xt` (waiting for finalization), but also finds the extrinsic in the block
+/// that included it. Its events can then be read with `NodeConnection::extrinsic_events`.
+pub async fn send_located_xt<Call: Encode + Clone>(
+    connection: &NodeConnection,
+    signer: &KeyPair,
+    call: Call,
+    label: &'static str,
+) -> AnyResult<(LatencySample, XtLocation)> {
+    let account = account_from_keypair(signer);
+    let nonce = connection.next_nonce(&account).await?;
+    let xt = connection.sign_with_nonce(signer, call, nonce);
+    let encoded = xt.hex_encode();
+
+    let sample = match send_tracked_xt(connection, xt, label, XtStatus::Finalized).await {
+        Ok(sample) => sample,
+        Err(e) => {
+            connection.resync_nonce(&account).await;
+            return Err(e);
+        }
+    };
+    let block = sample.in_block_number.ok_or(ListeningError::NotIncluded)?;
+    let location = connection.locate(block, &encoded).await?;
+    Ok((sample, location))
+}
+
+#[cfg(test)]
+mod tests {
+    use aleph_client::substrate_api_client::AccountId;
+    use anyhow::Result as AnyResult;
+    use futures::executor::block_on;
+
+    use crate::nonce::NonceManager;
+
+    async fn fetch(nonce: u32) -> AnyResult<u32> {
+        Ok(nonce)
+    }
+
+    #[test]
+    fn hands_out_consecutive_nonces() {
+        let manager = NonceManager::default();
+        let account = AccountId::new([1; 32]);
+
+        block_on(async {
+            assert_eq!(7, manager.next(&account, || fetch(7)).await.unwrap());
+            assert_eq!(8, manager.next(&account, || fetch(0)).await.unwrap());
+            assert_eq!(9, manager.next(&account, || fetch(0)).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn resync_reads_nonce_again() {
+        let manager = NonceManager::default();
+        let account = AccountId::new([1; 32]);
+        let other = AccountId::new([2; 32]);
+
+        block_on(async {
+            assert_eq!(3, manager.next(&account, || fetch(3)).await.unwrap());
+            assert_eq!(0, manager.next(&other, || fetch(0)).await.unwrap());
+
+            manager.resync(&account).await;
+
+            assert_eq!(3, manager.next(&account, || fetch(3)).await.unwrap());
+            assert_eq!(1, manager.next(&other, || fetch(10)).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn clear_reads_all_nonces_again() {
+        let manager = NonceManager::default();
+        let account = AccountId::new([1; 32]);
+        let other = AccountId::new([2; 32]);
+
+        block_on(async {
+            assert_eq!(3, manager.next(&account, || fetch(3)).await.unwrap());
+            assert_eq!(5, manager.next(&other, || fetch(5)).await.unwrap());
+
+            manager.clear().await;
+
+            assert_eq!(3, manager.next(&account, || fetch(3)).await.unwrap());
+            assert_eq!(5, manager.next(&other, || fetch(5)).await.unwrap());
+        });
+    }
+}
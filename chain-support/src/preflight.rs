@@ -0,0 +1,194 @@
+//! Pre-flight checks.
+//!
+//! Before scenarios are scheduled, every one of them declares what it needs (`Requirements`):
+//! calls that must exist in the runtime, account pools that must be large enough and funded, and
+//! consistency of its own parameters. All of them are checked against a live node, so that
+//! misconfigurations are reported at startup instead of as failures of every single run.
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
+
+use crate::{free_balances, NodeConnection};
+
+/// How serious a problem found during pre-flight checks is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// Scenarios can be launched, but some of their runs may fail.
+    Warning,
+    /// Scenarios must not be launched.
+    Error,
+}
+
+/// A single problem found during pre-flight checks.
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    /// What the problem concerns (usually a scenario identifier).
+    pub subject: String,
+    pub message: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mark = match self.severity {
+            Severity::Warning => "⚠️",
+            Severity::Error => "❌",
+        };
+        write!(f, "{} {}: {}", mark, self.subject, self.message)
+    }
+}
+
+/// Everything that a scenario needs in order to run successfully.
+#[derive(Clone, Debug, Default)]
+pub struct Requirements {
+    /// Calls (pallet and call name) that must be present in the runtime metadata.
+    calls: Vec<(&'static str, &'static str)>,
+    /// Account pools (with their minimal size), whose accounts must be funded.
+    pools: Vec<(String, usize)>,
+    /// Inconsistencies found in the scenario parameters.
+    problems: Vec<String>,
+}
+
+impl Requirements {
+    /// Requires `pallet` to have `call`.
+    pub fn call(mut self, pallet: &'static str, call: &'static str) -> Self {
+        self.calls.push((pallet, call));
+        self
+    }
+
+    /// Requires the account pool `pool` to be declared, to have at least `size` accounts and all
+    /// of them to be funded.
+    pub fn pool<S: Into<String>>(mut self, pool: S, size: usize) -> Self {
+        self.pools.push((pool.into(), size));
+        self
+    }
+
+    /// Records `problem` with the scenario parameters, unless `condition` holds.
+    pub fn ensure<S: Into<String>>(mut self, condition: bool, problem: S) -> Self {
+        if !condition {
+            self.problems.push(problem.into());
+        }
+        self
+    }
+
+    /// Requirements of both `self` and `other`.
+    pub fn merge(mut self, other: Requirements) -> Self {
+        self.calls.extend(other.calls);
+        self.pools.extend(other.pools);
+        self.problems.extend(other.problems);
+        self
+    }
+}
+
+/// Implemented by scenarios, which declare their requirements this way.
+pub trait Preflight {
+    fn requirements(&self) -> Requirements;
+}
+
+fn finding<S: Into<String>, M: Into<String>>(
+    severity: Severity,
+    subject: S,
+    message: M,
+) -> Finding {
+    Finding {
+        severity,
+        subject: subject.into(),
+        message: message.into(),
+    }
+}
+
+/// Checks funds of every account of `pool` (which must have at least `size` accounts).
+async fn check_pool(connection: &NodeConnection, pool: &str, size: usize) -> Vec<Finding> {
+    let subject = format!("pool `{}`", pool);
+    let accounts = match connection.accounts(pool) {
+        Ok(accounts) => accounts,
+        Err(e) => return vec![finding(Severity::Error, subject, e.to_string())],
+    };
+    if let Err(e) = accounts.ensure_size(size) {
+        return vec![finding(Severity::Error, subject, e.to_string())];
+    }
+
+    let balances = match free_balances(connection, &accounts.accounts()).await {
+        Ok(balances) => balances,
+        Err(e) => {
+            let message = format!("Cannot read balances: {}", e);
+            return vec![finding(Severity::Error, subject, message)];
+        }
+    };
+
+    let empty = balances.iter().filter(|balance| **balance == 0).count();
+    let low = balances
+        .iter()
+        .filter(|balance| **balance > 0 && **balance < accounts.balance() / 2)
+        .count();
+
+    let mut findings = vec![];
+    if empty > 0 {
+        let message = format!(
+            "{} of {} accounts have no funds (have they been endowed with `set_up`?)",
+            empty,
+            balances.len()
+        );
+        findings.push(finding(Severity::Error, subject.clone(), message));
+    }
+    if low > 0 {
+        let message = format!(
+            "{} of {} accounts have less than half of the target balance",
+            low,
+            balances.len()
+        );
+        findings.push(finding(Severity::Warning, subject, message));
+    }
+    findings
+}
+
+/// Checks `requirements` (of scenarios named by the first element) against `connection`.
+///
+/// Every pool is checked only once (with the largest size required), no matter how many scenarios
+/// use it.
+pub async fn check_requirements(
+    connection: &NodeConnection,
+    requirements: &[(String, Requirements)],
+) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut pools = HashMap::<&str, usize>::new();
+
+    for (subject, requirements) in requirements {
+        for (pallet, call) in &requirements.calls {
+            if !connection.has_call(pallet, call) {
+                let message = format!("Runtime does not provide `{}::{}`", pallet, call);
+                findings.push(finding(Severity::Error, subject, message));
+            }
+        }
+        for problem in &requirements.problems {
+            findings.push(finding(Severity::Error, subject, problem));
+        }
+        for (pool, size) in &requirements.pools {
+            let needed = pools.entry(pool.as_str()).or_default();
+            *needed = (*needed).max(*size);
+        }
+    }
+
+    let mut pools = pools.into_iter().collect::<Vec<_>>();
+    pools.sort_unstable();
+    for (pool, size) in pools {
+        findings.extend(check_pool(connection, pool, size).await);
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::preflight::Requirements;
+
+    #[test]
+    fn ensure_records_only_violated_conditions() {
+        let requirements = Requirements::default()
+            .ensure(true, "fine")
+            .ensure(false, "broken");
+
+        assert_eq!(vec!["broken".to_string()], requirements.problems);
+    }
+}
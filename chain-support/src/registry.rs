@@ -0,0 +1,150 @@
+//! Scenario kinds working on `NodeConnection`.
+//!
+//! Scenario crates register their kinds in a `ChainScenarioRegistry` (usually through their own
+//! `register` function). Constructed scenarios can be both played and asked for their requirements
+//! (see `Preflight`).
+//!
+//! Combinators from `common` (like `Sequence`) are registered by `register_combinators`. Their
+//! requirements are all the requirements of their children.
+
+use anyhow::Result as AnyResult;
+
+use common::{
+    Parallel, Repeat, Scenario, ScenarioKind, ScenarioLogging, ScenarioRegistry, Sequence,
+    WeightedChoice,
+};
+
+use crate::{NodeConnection, Preflight, Requirements};
+
+/// Scenario played against a chain, which declares its requirements.
+pub trait ChainScenario: Scenario<NodeConnection> + Preflight {}
+
+impl<S: Scenario<NodeConnection> + Preflight> ChainScenario for S {}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Box<dyn ChainScenario> {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        self.as_mut().play(connection, logger).await
+    }
+
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        self.as_mut().setup(connection, logger).await
+    }
+
+    async fn health_check(
+        &self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        self.as_ref().health_check(connection, logger).await
+    }
+
+    async fn teardown(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        self.as_mut().teardown(connection, logger).await
+    }
+}
+
+pub type ChainScenarioKind = ScenarioKind<NodeConnection, Box<dyn ChainScenario>>;
+
+pub type ChainScenarioRegistry = ScenarioRegistry<NodeConnection, Box<dyn ChainScenario>>;
+
+impl Preflight for Box<dyn ChainScenario> {
+    fn requirements(&self) -> Requirements {
+        self.as_ref().requirements()
+    }
+}
+
+/// Requirements of all `scenarios`.
+fn merged<'a, S: Preflight + 'a>(scenarios: impl IntoIterator<Item = &'a S>) -> Requirements {
+    scenarios
+        .into_iter()
+        .fold(Requirements::default(), |requirements, scenario| {
+            requirements.merge(scenario.requirements())
+        })
+}
+
+impl<S: Preflight> Preflight for Sequence<S> {
+    fn requirements(&self) -> Requirements {
+        merged(self.scenarios())
+    }
+}
+
+impl<S: Preflight> Preflight for Parallel<S> {
+    fn requirements(&self) -> Requirements {
+        merged(self.scenarios())
+    }
+}
+
+impl<S: Preflight> Preflight for WeightedChoice<S> {
+    fn requirements(&self) -> Requirements {
+        merged(self.scenarios())
+    }
+}
+
+impl<S: Preflight> Preflight for Repeat<S> {
+    fn requirements(&self) -> Requirements {
+        self.scenario().requirements()
+    }
+}
+
+/// Registers all the combinators (scenarios composed of other scenarios).
+pub fn register_combinators(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "Sequence",
+        &[
+            "scenarios: list of scenarios (tables with `kind` and parameters) played one after \
+            another, until the first failure",
+        ],
+        |config, connection, registry| {
+            Ok(Box::new(Sequence::from_config(
+                config, connection, registry,
+            )?))
+        },
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "Parallel",
+        &[
+            "scenarios: list of scenarios (tables with `kind` and parameters) played concurrently",
+            "copies (optional): how many instances of every scenario are played, by default 1",
+        ],
+        |config, connection, registry| {
+            Ok(Box::new(Parallel::from_config(
+                config, connection, registry,
+            )?))
+        },
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "WeightedChoice",
+        &[
+            "choices: list of `{ weight, scenario }`; every run plays one scenario, chosen \
+            randomly according to the weights",
+        ],
+        |config, connection, registry| {
+            Ok(Box::new(WeightedChoice::from_config(
+                config, connection, registry,
+            )?))
+        },
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "Repeat",
+        &[
+            "scenario: table with `kind` and parameters of the repeated scenario",
+            "times: how many times the scenario is played in every run",
+        ],
+        |config, connection, registry| {
+            Ok(Box::new(Repeat::from_config(config, connection, registry)?))
+        },
+    ))
+}
@@ -0,0 +1,157 @@
+//! Runtime sources of the base seed.
+//!
+//! All the bot accounts are derived from a base seed (see `keypair_derived_from`). The seed is
+//! read when the application starts, so changing it requires only a restart.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use aleph_client::{keypair_from_string, substrate_api_client::Pair};
+use anyhow::Result as AnyResult;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::SECRET_PHRASE_SEED;
+
+/// Key type (as in Substrate keystore) of the entry keeping the base seed.
+const ACCOUNT_KEY_TYPE: &[u8; 4] = b"acco";
+
+/// Gathers all possible errors from this module.
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("🔑❌ Cannot read secret from `{0}`: {1}.")]
+    Unreadable(PathBuf, String),
+    #[error("🔑❌ Secret file `{0}` is accessible by other users (mode {1:o}).")]
+    InsecurePermissions(PathBuf, u32),
+    #[error("🔑❌ Keystore `{0}` contains no account key.")]
+    NoKey(PathBuf),
+    #[error("🔑❌ Keystore `{0}` contains more than one account key.")]
+    AmbiguousKey(PathBuf),
+    #[error("🔑❌ Keystore entry `{0}` does not match the public key in its name.")]
+    KeyMismatch(PathBuf),
+}
+
+/// Where the base seed should be read from.
+///
+/// Use:
+/// - `Env(variable)` for reading it from an env variable; a missing variable means an empty seed
+/// - `File(path)` for reading the whole content of a file (without the trailing newline); the file
+///   must not be accessible by anyone but its owner
+/// - `Keystore(path)` for taking the phrase of the only account key (`acco`) from a Substrate-style
+///   keystore directory (like the one filled by `author_insertKey` or `subkey insert`)
+#[derive(Clone, Debug, Deserialize)]
+pub enum SecretSource {
+    Env(String),
+    File(PathBuf),
+    Keystore(PathBuf),
+}
+
+impl Default for SecretSource {
+    fn default() -> Self {
+        SecretSource::Env(SECRET_PHRASE_SEED.to_string())
+    }
+}
+
+impl SecretSource {
+    /// Reads the base seed.
+    pub fn read(&self) -> AnyResult<String> {
+        match self {
+            SecretSource::Env(variable) => Ok(env::var(variable).unwrap_or_default()),
+            SecretSource::File(path) => read_secret_file(path),
+            SecretSource::Keystore(path) => read_keystore(path),
+        }
+    }
+}
+
+/// Fails if `path` can be read or written by anyone but its owner.
+#[cfg(unix)]
+fn ensure_private(path: &Path) -> AnyResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)
+        .map_err(|e| SecretError::Unreadable(path.to_path_buf(), e.to_string()))?
+        .permissions()
+        .mode();
+    if mode & 0o077 != 0 {
+        return Err(SecretError::InsecurePermissions(path.to_path_buf(), mode & 0o777).into());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn ensure_private(_path: &Path) -> AnyResult<()> {
+    Ok(())
+}
+
+fn read_secret_file(path: &Path) -> AnyResult<String> {
+    ensure_private(path)?;
+    let content = fs::read_to_string(path)
+        .map_err(|e| SecretError::Unreadable(path.to_path_buf(), e.to_string()))?;
+    Ok(content.trim_end_matches(&['\n', '\r'][..]).to_string())
+}
+
+/// Keystore entries are named with hex-encoded key type followed by hex-encoded public key. They
+/// contain the secret phrase as a JSON string.
+fn read_keystore(path: &Path) -> AnyResult<String> {
+    let prefix = hex::encode(ACCOUNT_KEY_TYPE);
+    let entries = fs::read_dir(path)
+        .map_err(|e| SecretError::Unreadable(path.to_path_buf(), e.to_string()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|entry| {
+            entry
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.starts_with(&prefix))
+        })
+        .collect::<Vec<_>>();
+
+    let entry = match entries.as_slice() {
+        [] => return Err(SecretError::NoKey(path.to_path_buf()).into()),
+        [entry] => entry,
+        _ => return Err(SecretError::AmbiguousKey(path.to_path_buf()).into()),
+    };
+
+    let phrase: String = serde_json::from_str(&read_secret_file(entry)?)
+        .map_err(|e| SecretError::Unreadable(entry.clone(), e.to_string()))?;
+
+    let public = hex::encode(keypair_from_string(&phrase).public());
+    let expected = format!("{}{}", prefix, public);
+    if entry.file_name().and_then(|name| name.to_str()) != Some(expected.as_str()) {
+        return Err(SecretError::KeyMismatch(entry.clone()).into());
+    }
+    Ok(phrase)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf};
+
+    use crate::secret::{SecretError, SecretSource};
+
+    fn secret_file(name: &str, content: &str, mode: u32) -> PathBuf {
+        let path = env::temp_dir().join(format!("traffic-maker-{}-{}", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_private_file_without_trailing_newline() {
+        let path = secret_file("private", "base seed\n", 0o600);
+
+        assert_eq!("base seed", SecretSource::File(path).read().unwrap());
+    }
+
+    #[test]
+    fn rejects_file_readable_by_others() {
+        let path = secret_file("public", "base seed", 0o644);
+
+        let err = SecretSource::File(path).read().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<SecretError>(),
+            Some(SecretError::InsecurePermissions(_, 0o644))
+        ));
+    }
+}
@@ -0,0 +1,249 @@
+//! Asynchronous extrinsic submission.
+//!
+//! Instead of blocking a thread until an extrinsic is finalized (like `try_send_xt` does), we
+//! subscribe with `author_submitAndWatchExtrinsic` and consume status updates as a `Stream`.
+
+use aleph_client::substrate_api_client::{Hash, UncheckedExtrinsicV4, XtStatus};
+use anyhow::Result as AnyResult;
+use codec::Encode;
+use futures::{Stream, StreamExt};
+use jsonrpsee::{
+    core::{
+        client::{ClientT, SubscriptionClientT},
+        Error as RpcError,
+    },
+    rpc_params,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::NodeConnection;
+
+/// Gathers all possible errors from this module.
+#[derive(Debug, Error)]
+pub enum SubmissionError {
+    #[error("📨❌ Extrinsic has been rejected by the node: {0:?}.")]
+    Rejected(XtStatusUpdate),
+    #[error("📨❌ Extrinsic has been refused by the node: {0}.")]
+    Refused(String),
+    #[error("📨❌ Status subscription ended before the extrinsic reached the expected status.")]
+    SubscriptionClosed,
+    #[error("📨❌ Extrinsic has landed in the future queue (its nonce leaves a gap).")]
+    NonceGap,
+}
+
+/// Status of a watched extrinsic, as reported by the node.
+///
+/// Mirrors `sc_transaction_pool_api::TransactionStatus`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum XtStatusUpdate {
+    /// Extrinsic is part of the future queue (e.g. its nonce is too high).
+    Future,
+    /// Extrinsic is part of the ready queue.
+    Ready,
+    /// The extrinsic has been broadcast to the given peers.
+    Broadcast(Vec<String>),
+    /// Extrinsic has been included in the block with given hash.
+    InBlock(Hash),
+    /// The block this extrinsic was included in has been retracted.
+    Retracted(Hash),
+    /// Maximum number of finality watchers has been reached.
+    FinalityTimeout(Hash),
+    /// Extrinsic has been finalized in the block with given hash.
+    Finalized(Hash),
+    /// Extrinsic has been replaced in the pool by another one with the same tag.
+    Usurped(Hash),
+    /// Extrinsic has been dropped from the pool because of the limit.
+    Dropped,
+    /// Extrinsic is no longer valid in the current state.
+    Invalid,
+}
+
+/// Status of an extrinsic which a scenario waits for before moving on. Deserializable counterpart
+/// of `XtStatus`, limited to the statuses that `send_tracked_xt` can distinguish.
+///
+/// Waiting only for `Ready` or `InBlock` lets a scenario generate much more traffic, especially
+/// together with `send_managed_xt`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum WaitFor {
+    Ready,
+    InBlock,
+    Finalized,
+}
+
+impl Default for WaitFor {
+    fn default() -> Self {
+        WaitFor::Finalized
+    }
+}
+
+impl From<WaitFor> for XtStatus {
+    fn from(wait_for: WaitFor) -> Self {
+        match wait_for {
+            WaitFor::Ready => XtStatus::Ready,
+            WaitFor::InBlock => XtStatus::InBlock,
+            WaitFor::Finalized => XtStatus::Finalized,
+        }
+    }
+}
+
+impl XtStatusUpdate {
+    /// Whether the node will not report anything more about this extrinsic.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            XtStatusUpdate::Finalized(_)
+                | XtStatusUpdate::FinalityTimeout(_)
+                | XtStatusUpdate::Usurped(_)
+                | XtStatusUpdate::Dropped
+                | XtStatusUpdate::Invalid
+        )
+    }
+
+    /// Whether the extrinsic has been definitely rejected.
+    pub fn is_rejection(&self) -> bool {
+        matches!(
+            self,
+            XtStatusUpdate::Usurped(_) | XtStatusUpdate::Dropped | XtStatusUpdate::Invalid
+        )
+    }
+
+    /// Whether this update means that the extrinsic has reached (at least) `status`.
+    ///
+    /// Statuses other than `Ready`, `InBlock` and `Finalized` are treated like `Finalized`.
+    pub fn reaches(&self, status: XtStatus) -> bool {
+        match status {
+            XtStatus::Ready => matches!(
+                self,
+                XtStatusUpdate::Ready | XtStatusUpdate::InBlock(_) | XtStatusUpdate::Finalized(_)
+            ),
+            XtStatus::InBlock => {
+                matches!(
+                    self,
+                    XtStatusUpdate::InBlock(_) | XtStatusUpdate::Finalized(_)
+                )
+            }
+            _ => matches!(self, XtStatusUpdate::Finalized(_)),
+        }
+    }
+}
+
+impl NodeConnection {
+    /// Submits `xt` and returns a stream of its status updates.
+    ///
+    /// The stream ends after a terminal status (see `XtStatusUpdate::is_terminal`) or when the
+    /// node closes the subscription.
+    pub async fn submit_and_watch<Call: Encode>(
+        &self,
+        xt: UncheckedExtrinsicV4<Call>,
+    ) -> AnyResult<impl Stream<Item = AnyResult<XtStatusUpdate>>> {
+        let subscription = self
+            .rpc()
+            .subscribe::<XtStatusUpdate>(
+                "author_submitAndWatchExtrinsic",
+                rpc_params![xt.hex_encode()],
+                "author_unwatchExtrinsic",
+            )
+            .await
+            .map_err(|e| match e {
+                // Extrinsic did not even get into the pool (e.g. it is invalid or its nonce is
+                // stale).
+                RpcError::Call(e) => SubmissionError::Refused(e.to_string()).into(),
+                e => anyhow::Error::from(e),
+            })?;
+
+        let mut finished = false;
+        Ok(subscription
+            .take_while(move |update| {
+                let take = !finished;
+                finished = matches!(update, Ok(update) if update.is_terminal()) || update.is_err();
+                futures::future::ready(take)
+            })
+            .map(|update| update.map_err(|e| e.into())))
+    }
+
+    /// Returns the number of extrinsics waiting in the transaction pool of the node.
+    pub async fn pending_extrinsics(&self) -> AnyResult<usize> {
+        Ok(self
+            .rpc()
+            .request::<Vec<String>>("author_pendingExtrinsics", None)
+            .await?
+            .len())
+    }
+
+    /// Submits `xt` and waits until it reaches `until` status.
+    ///
+    /// Returns hash of the block in which the extrinsic was included (`None` if `until` is `Ready`).
+    pub async fn submit<Call: Encode>(
+        &self,
+        xt: UncheckedExtrinsicV4<Call>,
+        until: XtStatus,
+    ) -> AnyResult<Option<Hash>> {
+        let updates = self.submit_and_watch(xt).await?;
+        futures::pin_mut!(updates);
+
+        while let Some(update) = updates.next().await {
+            let update = update?;
+            if update.is_rejection() {
+                return Err(SubmissionError::Rejected(update).into());
+            }
+            if update.reaches(until) {
+                return Ok(match update {
+                    XtStatusUpdate::InBlock(hash) | XtStatusUpdate::Finalized(hash) => Some(hash),
+                    _ => None,
+                });
+            }
+        }
+        Err(SubmissionError::SubscriptionClosed.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aleph_client::substrate_api_client::{Hash, XtStatus};
+
+    use crate::submission::XtStatusUpdate;
+
+    fn parse(raw: &str) -> XtStatusUpdate {
+        serde_json::from_str(raw).expect("Should deserialize status")
+    }
+
+    #[test]
+    fn deserializes_unit_statuses() {
+        assert_eq!(XtStatusUpdate::Ready, parse("\"ready\""));
+        assert_eq!(XtStatusUpdate::Future, parse("\"future\""));
+        assert_eq!(XtStatusUpdate::Invalid, parse("\"invalid\""));
+    }
+
+    #[test]
+    fn deserializes_statuses_with_block_hash() {
+        let raw_hash = format!("0x{}", "ab".repeat(32));
+        let hash = Hash::from([0xab; 32]);
+
+        assert_eq!(
+            XtStatusUpdate::InBlock(hash),
+            parse(&format!("{{\"inBlock\": \"{}\"}}", raw_hash))
+        );
+        assert_eq!(
+            XtStatusUpdate::Finalized(hash),
+            parse(&format!("{{\"finalized\": \"{}\"}}", raw_hash))
+        );
+    }
+
+    #[test]
+    fn finalization_reaches_every_status() {
+        let finalized = XtStatusUpdate::Finalized(Hash::default());
+
+        assert!(finalized.reaches(XtStatus::Ready));
+        assert!(finalized.reaches(XtStatus::InBlock));
+        assert!(finalized.reaches(XtStatus::Finalized));
+    }
+
+    #[test]
+    fn readiness_does_not_reach_inclusion() {
+        assert!(XtStatusUpdate::Ready.reaches(XtStatus::Ready));
+        assert!(!XtStatusUpdate::Ready.reaches(XtStatus::InBlock));
+        assert!(!XtStatusUpdate::Ready.reaches(XtStatus::Finalized));
+    }
+}
@@ -0,0 +1,109 @@
+//! Keeping `NodeConnection` alive.
+//!
+//! The supervisor periodically checks the node. When the check fails (e.g. the node has been
+//! restarted and the WebSocket is dead), the connection is marked as unavailable (so that
+//! the scheduler skips runs) and the supervisor tries to reconnect with an exponential backoff.
+//! When the runtime version changes, metadata is refreshed.
+
+use std::{cmp::min, time::Duration};
+
+use aleph_client::AnyConnection;
+use anyhow::Result as AnyResult;
+use jsonrpsee::core::client::ClientT;
+use log::{info, warn};
+use serde::Deserialize;
+use tokio::{task::JoinHandle, time::sleep};
+
+use common::Availability;
+
+use crate::{ConnectionError, NodeConnection};
+
+/// How often the node is checked when everything is fine.
+const HEALTH_CHECK_PERIOD: Duration = Duration::from_secs(5);
+
+/// Backoff bounds for reconnection attempts.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Response of `system_health`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemHealth {
+    peers: u32,
+    is_syncing: bool,
+}
+
+/// Response of `state_getRuntimeVersion` (only what we need).
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuntimeVersion {
+    spec_version: u32,
+}
+
+impl NodeConnection {
+    /// Spawns a task supervising this connection (and all its clones).
+    pub fn supervise(&self) -> JoinHandle<()> {
+        let connection = self.clone();
+        tokio::spawn(async move { connection.supervision_loop().await })
+    }
+
+    async fn supervision_loop(&self) {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            match self.check_health().await {
+                Ok(()) => {
+                    backoff = MIN_BACKOFF;
+                    sleep(HEALTH_CHECK_PERIOD).await;
+                }
+                Err(e) => {
+                    if self.is_available() {
+                        warn!(target: "supervisor", "Node {} is unavailable: {}", self.address(), e);
+                    }
+                    self.update_health(|health| health.connected = false);
+
+                    sleep(backoff).await;
+                    backoff = min(2 * backoff, MAX_BACKOFF);
+
+                    match self.reconnect().await {
+                        Ok(()) => info!(target: "supervisor", "Reconnected to {}", self.address()),
+                        Err(e) => warn!(target: "supervisor", "Cannot reconnect: {}", e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Queries the node and saves the results. Refreshes metadata if the runtime has been
+    /// upgraded.
+    async fn check_health(&self) -> AnyResult<()> {
+        let rpc = self.rpc();
+        if !rpc.is_connected() {
+            return Err(ConnectionError::Disconnected.into());
+        }
+
+        let system = rpc.request::<SystemHealth>("system_health", None).await?;
+        let runtime = rpc
+            .request::<RuntimeVersion>("state_getRuntimeVersion", None)
+            .await?;
+        let best_block = self.best_block_number().await?;
+        let finalized_block = self.finalized_block_number().await?;
+
+        if runtime.spec_version != self.as_connection().runtime_version.spec_version {
+            info!(
+                target: "supervisor",
+                "Runtime upgraded to version {}. Refreshing metadata", runtime.spec_version
+            );
+            self.refresh_metadata().await?;
+        }
+
+        self.update_health(|health| {
+            health.connected = true;
+            health.best_block = Some(best_block);
+            health.finalized_block = Some(finalized_block);
+            health.peers = Some(system.peers);
+            health.is_syncing = Some(system.is_syncing);
+            health.spec_version = Some(runtime.spec_version);
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,480 @@
+//! Scenarios composed of other scenarios.
+//!
+//! Children are configured just like top-level scenarios (a table with `kind` and parameters)
+//! and are constructed through the same `ScenarioRegistry`, so combinators can be nested. Children
+//! log to the log of the composed scenario (every line is prefixed with the position of the child)
+//! and their outcomes are aggregated into a single one.
+//!
+//! Lifecycle hooks (`setup`, `health_check` and `teardown`) are passed to all the children.
+
+use anyhow::Result as AnyResult;
+use futures::future::join_all;
+use rand::{distributions::WeightedIndex, prelude::Distribution, thread_rng};
+use serde::Deserialize;
+use thiserror::Error;
+use toml::Value;
+
+use crate::{Scenario, ScenarioLogging, ScenarioRegistry};
+
+/// Gathers all possible errors from this module.
+#[derive(Debug, Error)]
+pub enum CombinatorError {
+    #[error("🧩❌ There are no scenarios to compose.")]
+    NoScenarios,
+    #[error("🧩❌ Scenario should be repeated at least once.")]
+    NoRepetitions,
+    #[error("🧩❌ At least one weight should be positive.")]
+    InvalidWeights,
+    #[error("🧩❌ Step {0} of {1} failed: {2}")]
+    StepFailed(usize, usize, String),
+    #[error("🧩❌ {0} of {1} runs failed.")]
+    RunsFailed(usize, usize),
+}
+
+fn default_copies() -> usize {
+    1
+}
+
+#[derive(Deserialize)]
+struct SequenceConfig {
+    scenarios: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct ParallelConfig {
+    scenarios: Vec<Value>,
+    #[serde(default = "default_copies")]
+    copies: usize,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    weight: u32,
+    scenario: Value,
+}
+
+#[derive(Deserialize)]
+struct ChoicesConfig {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct RepeatConfig {
+    times: usize,
+    scenario: Value,
+}
+
+/// Constructs every scenario of `configs` `copies` times.
+fn construct_all<C, S>(
+    configs: &[Value],
+    copies: usize,
+    connection: &C,
+    registry: &ScenarioRegistry<C, S>,
+) -> AnyResult<Vec<S>> {
+    let mut scenarios = vec![];
+    for config in configs {
+        for _ in 0..copies {
+            scenarios.push(registry.construct(config, connection)?);
+        }
+    }
+    if scenarios.is_empty() {
+        return Err(CombinatorError::NoScenarios.into());
+    }
+    Ok(scenarios)
+}
+
+fn child_logger(logger: &ScenarioLogging, idx: usize) -> ScenarioLogging {
+    logger.child(format!("#{}", idx + 1))
+}
+
+async fn setup_all<C: Send + Sync + 'static, S: Scenario<C>>(
+    scenarios: &mut [S],
+    connection: &C,
+    logger: &ScenarioLogging,
+) -> AnyResult<()> {
+    for (idx, scenario) in scenarios.iter_mut().enumerate() {
+        scenario
+            .setup(connection, &child_logger(logger, idx))
+            .await?;
+    }
+    Ok(())
+}
+
+async fn health_check_all<C: Send + Sync + 'static, S: Scenario<C>>(
+    scenarios: &[S],
+    connection: &C,
+    logger: &ScenarioLogging,
+) -> AnyResult<()> {
+    for (idx, scenario) in scenarios.iter().enumerate() {
+        scenario
+            .health_check(connection, &child_logger(logger, idx))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Tears down all the scenarios, even if some of them fail. Returns the first error.
+async fn teardown_all<C: Send + Sync + 'static, S: Scenario<C>>(
+    scenarios: &mut [S],
+    connection: &C,
+    logger: &ScenarioLogging,
+) -> AnyResult<()> {
+    let mut result = Ok(());
+    for (idx, scenario) in scenarios.iter_mut().enumerate() {
+        let teardown = scenario
+            .teardown(connection, &child_logger(logger, idx))
+            .await;
+        if result.is_ok() {
+            result = teardown;
+        }
+    }
+    result
+}
+
+/// Plays its scenarios one after another. A run stops at the first failure.
+pub struct Sequence<S> {
+    scenarios: Vec<S>,
+}
+
+impl<S> Sequence<S> {
+    pub fn new(scenarios: Vec<S>) -> AnyResult<Self> {
+        if scenarios.is_empty() {
+            return Err(CombinatorError::NoScenarios.into());
+        }
+        Ok(Sequence { scenarios })
+    }
+
+    /// Constructs children listed in `scenarios`.
+    pub fn from_config<C>(
+        config: Value,
+        connection: &C,
+        registry: &ScenarioRegistry<C, S>,
+    ) -> AnyResult<Self> {
+        let config = config.try_into::<SequenceConfig>()?;
+        Self::new(construct_all(&config.scenarios, 1, connection, registry)?)
+    }
+
+    pub fn scenarios(&self) -> &[S] {
+        &self.scenarios
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Send + Sync + 'static, S: Scenario<C>> Scenario<C> for Sequence<S> {
+    async fn play(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        let steps = self.scenarios.len();
+        for (idx, scenario) in self.scenarios.iter_mut().enumerate() {
+            if let Err(e) = scenario.play(connection, &child_logger(logger, idx)).await {
+                return logger.log_result(Err(CombinatorError::StepFailed(
+                    idx + 1,
+                    steps,
+                    e.to_string(),
+                )
+                .into()));
+            }
+        }
+        Ok(())
+    }
+
+    async fn setup(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        setup_all(&mut self.scenarios, connection, logger).await
+    }
+
+    async fn health_check(&self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        health_check_all(&self.scenarios, connection, logger).await
+    }
+
+    async fn teardown(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        teardown_all(&mut self.scenarios, connection, logger).await
+    }
+}
+
+/// Plays all its scenarios concurrently. A run fails if any of them fails.
+pub struct Parallel<S> {
+    scenarios: Vec<S>,
+}
+
+impl<S> Parallel<S> {
+    pub fn new(scenarios: Vec<S>) -> AnyResult<Self> {
+        if scenarios.is_empty() {
+            return Err(CombinatorError::NoScenarios.into());
+        }
+        Ok(Parallel { scenarios })
+    }
+
+    /// Constructs children listed in `scenarios`, each of them `copies` times (by default once).
+    pub fn from_config<C>(
+        config: Value,
+        connection: &C,
+        registry: &ScenarioRegistry<C, S>,
+    ) -> AnyResult<Self> {
+        let config = config.try_into::<ParallelConfig>()?;
+        let scenarios = construct_all(&config.scenarios, config.copies, connection, registry)?;
+        Self::new(scenarios)
+    }
+
+    pub fn scenarios(&self) -> &[S] {
+        &self.scenarios
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Send + Sync + 'static, S: Scenario<C>> Scenario<C> for Parallel<S> {
+    async fn play(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        let loggers = (0..self.scenarios.len())
+            .map(|idx| child_logger(logger, idx))
+            .collect::<Vec<_>>();
+        let results = join_all(
+            self.scenarios
+                .iter_mut()
+                .zip(&loggers)
+                .map(|(scenario, logger)| scenario.play(connection, logger)),
+        )
+        .await;
+
+        let failed = results.iter().filter(|result| result.is_err()).count();
+        if failed > 0 {
+            return logger.log_result(Err(
+                CombinatorError::RunsFailed(failed, results.len()).into()
+            ));
+        }
+        Ok(())
+    }
+
+    async fn setup(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        setup_all(&mut self.scenarios, connection, logger).await
+    }
+
+    async fn health_check(&self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        health_check_all(&self.scenarios, connection, logger).await
+    }
+
+    async fn teardown(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        teardown_all(&mut self.scenarios, connection, logger).await
+    }
+}
+
+/// Plays one of its scenarios, chosen randomly according to their weights.
+pub struct WeightedChoice<S> {
+    scenarios: Vec<S>,
+    weights: WeightedIndex<u32>,
+}
+
+impl<S> WeightedChoice<S> {
+    /// Pairs of scenarios and their weights.
+    pub fn new(choices: Vec<(S, u32)>) -> AnyResult<Self> {
+        if choices.is_empty() {
+            return Err(CombinatorError::NoScenarios.into());
+        }
+        let (scenarios, weights): (Vec<_>, Vec<_>) = choices.into_iter().unzip();
+        let weights = WeightedIndex::new(weights).map_err(|_| CombinatorError::InvalidWeights)?;
+        Ok(WeightedChoice { scenarios, weights })
+    }
+
+    /// Constructs children listed in `choices` (as `{ weight, scenario }`).
+    pub fn from_config<C>(
+        config: Value,
+        connection: &C,
+        registry: &ScenarioRegistry<C, S>,
+    ) -> AnyResult<Self> {
+        let config = config.try_into::<ChoicesConfig>()?;
+        let choices = config
+            .choices
+            .iter()
+            .map(|choice| {
+                Ok((
+                    registry.construct(&choice.scenario, connection)?,
+                    choice.weight,
+                ))
+            })
+            .collect::<AnyResult<Vec<_>>>()?;
+        Self::new(choices)
+    }
+
+    pub fn scenarios(&self) -> &[S] {
+        &self.scenarios
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Send + Sync + 'static, S: Scenario<C>> Scenario<C> for WeightedChoice<S> {
+    async fn play(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        let idx = self.weights.sample(&mut thread_rng());
+        self.scenarios[idx]
+            .play(connection, &child_logger(logger, idx))
+            .await
+    }
+
+    async fn setup(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        setup_all(&mut self.scenarios, connection, logger).await
+    }
+
+    async fn health_check(&self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        health_check_all(&self.scenarios, connection, logger).await
+    }
+
+    async fn teardown(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        teardown_all(&mut self.scenarios, connection, logger).await
+    }
+}
+
+/// Plays its scenario `times` times in a row. All the repetitions are played even if some of them
+/// fail. A run fails if any repetition fails.
+pub struct Repeat<S> {
+    scenario: S,
+    times: usize,
+}
+
+impl<S> Repeat<S> {
+    pub fn new(scenario: S, times: usize) -> AnyResult<Self> {
+        if times == 0 {
+            return Err(CombinatorError::NoRepetitions.into());
+        }
+        Ok(Repeat { scenario, times })
+    }
+
+    /// Constructs `scenario` (repeated `times` times).
+    pub fn from_config<C>(
+        config: Value,
+        connection: &C,
+        registry: &ScenarioRegistry<C, S>,
+    ) -> AnyResult<Self> {
+        let config = config.try_into::<RepeatConfig>()?;
+        Self::new(
+            registry.construct(&config.scenario, connection)?,
+            config.times,
+        )
+    }
+
+    pub fn scenario(&self) -> &S {
+        &self.scenario
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Send + Sync + 'static, S: Scenario<C>> Scenario<C> for Repeat<S> {
+    async fn play(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        let mut failed = 0;
+        for idx in 0..self.times {
+            if self
+                .scenario
+                .play(connection, &child_logger(logger, idx))
+                .await
+                .is_err()
+            {
+                failed += 1;
+            }
+        }
+        if failed > 0 {
+            return logger.log_result(Err(CombinatorError::RunsFailed(failed, self.times).into()));
+        }
+        Ok(())
+    }
+
+    async fn setup(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        self.scenario.setup(connection, logger).await
+    }
+
+    async fn health_check(&self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        self.scenario.health_check(connection, logger).await
+    }
+
+    async fn teardown(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        self.scenario.teardown(connection, logger).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use anyhow::{anyhow, Result as AnyResult};
+    use futures::executor::block_on;
+
+    use crate::{
+        combinators::{CombinatorError, Parallel, Repeat, Sequence},
+        log_target, Ident, Scenario, ScenarioLogging,
+    };
+
+    /// Counts its runs (in a counter shared by all the clones) and fails if told to.
+    #[derive(Clone)]
+    struct Counting {
+        runs: Arc<AtomicUsize>,
+        fails: bool,
+    }
+
+    impl Counting {
+        fn new(fails: bool) -> Self {
+            Counting {
+                runs: Arc::new(AtomicUsize::new(0)),
+                fails,
+            }
+        }
+
+        fn runs(&self) -> usize {
+            self.runs.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Scenario<()> for Counting {
+        async fn play(&mut self, _connection: &(), _logger: &ScenarioLogging) -> AnyResult<()> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            match self.fails {
+                true => Err(anyhow!("failed")),
+                false => Ok(()),
+            }
+        }
+    }
+
+    fn logger() -> ScenarioLogging {
+        ScenarioLogging::new(log_target("test", &Ident::from("test")))
+    }
+
+    #[test]
+    fn sequence_stops_at_first_failure() {
+        let (ok, failing, skipped) = (
+            Counting::new(false),
+            Counting::new(true),
+            Counting::new(false),
+        );
+        let mut sequence =
+            Sequence::new(vec![ok.clone(), failing.clone(), skipped.clone()]).unwrap();
+
+        let err = block_on(sequence.play(&(), &logger())).unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<CombinatorError>(),
+            Some(CombinatorError::StepFailed(2, 3, _))
+        ));
+        assert_eq!((1, 1, 0), (ok.runs(), failing.runs(), skipped.runs()));
+    }
+
+    #[test]
+    fn parallel_plays_all_and_counts_failures() {
+        let (ok, failing) = (Counting::new(false), Counting::new(true));
+        let mut parallel = Parallel::new(vec![ok.clone(), failing.clone(), ok.clone()]).unwrap();
+
+        let err = block_on(parallel.play(&(), &logger())).unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<CombinatorError>(),
+            Some(CombinatorError::RunsFailed(1, 3))
+        ));
+        assert_eq!((2, 1), (ok.runs(), failing.runs()));
+    }
+
+    #[test]
+    fn repeat_plays_given_number_of_times() {
+        let ok = Counting::new(false);
+        let mut repeat = Repeat::new(ok.clone(), 4).unwrap();
+
+        block_on(repeat.play(&(), &logger())).unwrap();
+
+        assert_eq!(4, ok.runs());
+        assert!(Repeat::new(ok, 0).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+/// Connection able to tell whether the node behind it is reachable at the moment.
+pub trait Availability {
+    fn is_available(&self) -> bool;
+}
+
+/// Provides a connection for every run of a scenario.
+///
+/// The scheduler does not launch a scenario when its provider has no connection to offer.
+pub trait ConnectionProvider<C>: Send + Sync + 'static {
+    /// Connection for the next run or `None` if no node is available now.
+    fn next_connection(&self) -> Option<C>;
+}
+
+/// A single connection is its own provider, as long as it is available.
+impl<C: Availability + Clone + Send + Sync + 'static> ConnectionProvider<C> for C {
+    fn next_connection(&self) -> Option<C> {
+        if self.is_available() {
+            Some(self.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Most recent observations of a node.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeHealth {
+    /// Name of the node (as in the configuration).
+    pub name: String,
+    /// WS address of the node.
+    pub address: String,
+    /// Whether the last health check succeeded.
+    pub connected: bool,
+    /// Last known number of the best block.
+    pub best_block: Option<u32>,
+    /// Last known number of the finalized block.
+    pub finalized_block: Option<u32>,
+    /// Last known number of peers of the node.
+    pub peers: Option<u32>,
+    /// Whether the node was syncing during the last check.
+    pub is_syncing: Option<bool>,
+    /// Last known runtime version.
+    pub spec_version: Option<u32>,
+}
+
+impl NodeHealth {
+    pub fn new(name: String, address: String) -> Self {
+        NodeHealth {
+            name,
+            address,
+            ..NodeHealth::default()
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::Ident;
+
+/// A single observation of an extrinsic going through the transaction pool and the chain.
+///
+/// Every duration is measured from the moment of submission. A stage that has not been reached
+/// (or was not awaited) is `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySample {
+    /// Time until the extrinsic was accepted to the transaction pool.
+    pub to_ready: Option<Duration>,
+    /// Time until the extrinsic was included in a (best) block.
+    pub to_in_block: Option<Duration>,
+    /// Time until the block with the extrinsic was finalized.
+    pub to_finalized: Option<Duration>,
+    /// Number of the block in which the extrinsic was included.
+    pub in_block_number: Option<u32>,
+    /// Number of the finalized block that was first observed to cover `in_block_number`.
+    pub finalized_number: Option<u32>,
+}
+
+impl LatencySample {
+    /// How many blocks passed between inclusion and the moment when finalization was observed.
+    pub fn finality_lag(&self) -> Option<u32> {
+        match (self.in_block_number, self.finalized_number) {
+            (Some(in_block), Some(finalized)) => Some(finalized.saturating_sub(in_block)),
+            _ => None,
+        }
+    }
+}
+
+/// Basic percentiles over a set of samples. Computed with nearest-rank method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Percentiles<T> {
+    pub p50: T,
+    pub p90: T,
+    pub p99: T,
+    pub max: T,
+}
+
+impl<T: Copy + Ord> Percentiles<T> {
+    /// Returns `None` if `samples` is empty.
+    pub fn from_samples(mut samples: Vec<T>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+
+        // Index of the smallest sample that is not less than `p` percent of all samples.
+        let rank = |p: usize| samples[(p * samples.len() - 1) / 100];
+
+        Some(Percentiles {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: samples[samples.len() - 1],
+        })
+    }
+}
+
+/// Aggregated latency statistics for a single call type sent by a single scenario through
+/// a single node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySummary {
+    /// Scenario which has sent extrinsics. `None` if they were sent outside any scenario.
+    pub scenario_ident: Option<Ident>,
+    /// Name of the node to which extrinsics were submitted.
+    pub node: String,
+    /// Call type, like `Balances::transfer`.
+    pub call: String,
+    /// Number of samples taken into account.
+    pub samples: usize,
+    /// Number of extrinsics that did not reach the expected status.
+    pub failures: usize,
+    pub to_ready: Option<Percentiles<Duration>>,
+    pub to_in_block: Option<Percentiles<Duration>>,
+    pub to_finalized: Option<Percentiles<Duration>>,
+    /// Distance (in blocks) between inclusion and observed finalization.
+    pub finality_lag: Option<Percentiles<u32>>,
+    /// The highest block in which some extrinsic of this kind was included.
+    pub last_in_block_number: Option<u32>,
+}
+
+impl LatencySummary {
+    pub fn new<'a, I: IntoIterator<Item = &'a LatencySample>>(
+        scenario_ident: Option<Ident>,
+        node: String,
+        call: String,
+        samples: I,
+        failures: usize,
+    ) -> Self {
+        let samples = samples.into_iter().collect::<Vec<_>>();
+        let collect = |f: fn(&LatencySample) -> Option<Duration>| {
+            Percentiles::from_samples(samples.iter().filter_map(|s| f(s)).collect())
+        };
+
+        LatencySummary {
+            scenario_ident,
+            node,
+            call,
+            samples: samples.len(),
+            failures,
+            to_ready: collect(|s| s.to_ready),
+            to_in_block: collect(|s| s.to_in_block),
+            to_finalized: collect(|s| s.to_finalized),
+            finality_lag: Percentiles::from_samples(
+                samples.iter().filter_map(|s| s.finality_lag()).collect(),
+            ),
+            last_in_block_number: samples.iter().filter_map(|s| s.in_block_number).max(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::latency::{LatencySample, LatencySummary, Percentiles};
+
+    #[test]
+    fn percentiles_of_empty_set_are_none() {
+        assert_eq!(None, Percentiles::<u32>::from_samples(vec![]));
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let percentiles = Percentiles::from_samples((1..=100u32).rev().collect()).unwrap();
+
+        assert_eq!(50, percentiles.p50);
+        assert_eq!(90, percentiles.p90);
+        assert_eq!(99, percentiles.p99);
+        assert_eq!(100, percentiles.max);
+    }
+
+    #[test]
+    fn percentiles_of_single_sample() {
+        let percentiles = Percentiles::from_samples(vec![7u32]).unwrap();
+        assert_eq!(
+            Percentiles {
+                p50: 7,
+                p90: 7,
+                p99: 7,
+                max: 7
+            },
+            percentiles
+        );
+    }
+
+    #[test]
+    fn summary_skips_missing_stages() {
+        let samples = vec![
+            LatencySample {
+                to_ready: Some(Duration::from_millis(10)),
+                to_in_block: Some(Duration::from_millis(900)),
+                to_finalized: Some(Duration::from_millis(3000)),
+                in_block_number: Some(5),
+                finalized_number: Some(7),
+            },
+            LatencySample {
+                to_ready: Some(Duration::from_millis(20)),
+                ..LatencySample::default()
+            },
+        ];
+
+        let summary = LatencySummary::new(
+            None,
+            "node".to_string(),
+            "Balances::transfer".to_string(),
+            &samples,
+            0,
+        );
+
+        assert_eq!(2, summary.samples);
+        assert_eq!(Duration::from_millis(20), summary.to_ready.unwrap().max);
+        assert_eq!(Duration::from_millis(900), summary.to_in_block.unwrap().p50);
+        assert_eq!(2, summary.finality_lag.unwrap().max);
+        assert_eq!(Some(5), summary.last_in_block_number);
+    }
+}
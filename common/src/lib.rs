@@ -2,11 +2,19 @@ use parse_duration::parse;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::time::Duration;
 
+pub use combinators::{CombinatorError, Parallel, Repeat, Sequence, WeightedChoice};
+pub use health::{Availability, ConnectionProvider, NodeHealth};
+pub use latency::{LatencySample, LatencySummary, Percentiles};
+pub use registry::{Constructor, RegistryError, ScenarioKind, ScenarioRegistry};
 pub use scenario::{
-    Scenario, ScenarioDetails, ScenarioError, ScenarioLogging, ScenarioLogs, ScenarioStatus,
-    ScheduledScenario,
+    current_scenario, log_target, split_log_target, Scenario, ScenarioDetails, ScenarioError,
+    ScenarioLogging, ScenarioLogs, ScenarioStatus, ScheduledScenario,
 };
 
+mod combinators;
+mod health;
+mod latency;
+mod registry;
 mod scenario;
 
 /// A wrapper type for scenario identification.
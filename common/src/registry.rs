@@ -0,0 +1,172 @@
+//! Registry of scenario kinds.
+//!
+//! Every scenario crate registers its kinds: a name (used as `kind` in the configuration),
+//! a description of the parameters and a constructor, which gets the rest of the configuration
+//! together with a connection and the registry itself. Thanks to the connection, construction can
+//! read anything it needs from the chain (and fail gracefully). Thanks to the registry, scenarios
+//! can be composed of other configured scenarios (see `combinators`).
+
+use std::collections::BTreeMap;
+
+use anyhow::Result as AnyResult;
+use thiserror::Error;
+use toml::Value;
+
+/// Gathers all possible errors from this module.
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("📋❌ Scenario configuration does not specify its `kind`.")]
+    MissingKind,
+    #[error("📋❌ There is no scenario kind `{0}`.")]
+    UnknownKind(String),
+    #[error("📋❌ Scenario kind `{0}` is registered more than once.")]
+    DuplicatedKind(&'static str),
+    #[error("📋❌ Invalid configuration of `{0}`: {1}.")]
+    InvalidConfig(String, String),
+}
+
+/// Builds a scenario out of its configuration (without `kind`), possibly consulting the chain
+/// through the connection and constructing nested scenarios through the registry.
+pub type Constructor<C, S> = fn(Value, &C, &ScenarioRegistry<C, S>) -> AnyResult<S>;
+
+/// A single kind of scenarios.
+pub struct ScenarioKind<C, S> {
+    name: &'static str,
+    /// Human-readable descriptions of the parameters, like `"transfers: how many transfers"`.
+    parameters: &'static [&'static str],
+    construct: Constructor<C, S>,
+}
+
+impl<C, S> ScenarioKind<C, S> {
+    pub fn new(
+        name: &'static str,
+        parameters: &'static [&'static str],
+        construct: Constructor<C, S>,
+    ) -> Self {
+        ScenarioKind {
+            name,
+            parameters,
+            construct,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn parameters(&self) -> &'static [&'static str] {
+        self.parameters
+    }
+}
+
+/// All the registered scenario kinds, by name. `S` is the type of constructed scenarios (usually
+/// a boxed trait object).
+pub struct ScenarioRegistry<C, S> {
+    kinds: BTreeMap<&'static str, ScenarioKind<C, S>>,
+}
+
+impl<C, S> Default for ScenarioRegistry<C, S> {
+    fn default() -> Self {
+        ScenarioRegistry {
+            kinds: BTreeMap::new(),
+        }
+    }
+}
+
+impl<C, S> ScenarioRegistry<C, S> {
+    /// Adds `kind`. Fails if there is already a kind with the same name.
+    pub fn register(&mut self, kind: ScenarioKind<C, S>) -> AnyResult<()> {
+        if self.kinds.contains_key(kind.name) {
+            return Err(RegistryError::DuplicatedKind(kind.name).into());
+        }
+        self.kinds.insert(kind.name, kind);
+        Ok(())
+    }
+
+    /// All the registered kinds, ordered by name.
+    pub fn kinds(&self) -> impl Iterator<Item = &ScenarioKind<C, S>> {
+        self.kinds.values()
+    }
+
+    /// Constructs a scenario out of `config`, which is a table with `kind` and parameters of the
+    /// scenario.
+    pub fn construct(&self, config: &Value, connection: &C) -> AnyResult<S> {
+        let mut config = config.clone();
+        let kind = match config.as_table_mut().and_then(|table| table.remove("kind")) {
+            Some(Value::String(kind)) => kind,
+            _ => return Err(RegistryError::MissingKind.into()),
+        };
+        let scenario_kind = self
+            .kinds
+            .get(kind.as_str())
+            .ok_or_else(|| RegistryError::UnknownKind(kind.clone()))?;
+        (scenario_kind.construct)(config, connection, self).map_err(|e| {
+            match e.downcast_ref::<toml::de::Error>() {
+                Some(e) => RegistryError::InvalidConfig(kind, e.to_string()).into(),
+                None => e,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use toml::Value;
+
+    use crate::registry::{RegistryError, ScenarioKind, ScenarioRegistry};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Transfers {
+        transfers: usize,
+    }
+
+    fn registry() -> ScenarioRegistry<(), Transfers> {
+        let mut registry = ScenarioRegistry::default();
+        registry
+            .register(ScenarioKind::new("Transfers", &[], |config, _, _| {
+                Ok(config.try_into()?)
+            }))
+            .unwrap();
+        registry
+    }
+
+    fn config(content: &str) -> Value {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn constructs_registered_kind() {
+        let scenario = registry()
+            .construct(&config("kind = \"Transfers\"\ntransfers = 3"), &())
+            .unwrap();
+
+        assert_eq!(Transfers { transfers: 3 }, scenario);
+    }
+
+    #[test]
+    fn rejects_unknown_kind() {
+        let err = registry()
+            .construct(&config("kind = \"Other\""), &())
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<RegistryError>(),
+            Some(RegistryError::UnknownKind(kind)) if kind == "Other"
+        ));
+    }
+
+    #[test]
+    fn rejects_duplicated_kind() {
+        let err = registry()
+            .register(ScenarioKind::new("Transfers", &[], |config, _, _| {
+                Ok(config.try_into()?)
+            }))
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<RegistryError>(),
+            Some(RegistryError::DuplicatedKind("Transfers"))
+        ));
+    }
+}
@@ -7,7 +7,20 @@ use std::{
 };
 use thiserror::Error;
 
-use crate::Ident;
+use crate::{ConnectionProvider, Ident};
+
+tokio::task_local! {
+    /// Identifier of the scenario which is being played within the current task.
+    static CURRENT_SCENARIO: Ident;
+}
+
+/// Returns identifier of the scenario which is being played within the current task (if any).
+///
+/// Useful for attributing some measurements to the scenario without passing its identifier all
+/// the way down.
+pub fn current_scenario() -> Option<Ident> {
+    CURRENT_SCENARIO.try_with(|ident| ident.clone()).ok()
+}
 
 #[derive(Debug, Error)]
 pub enum ScenarioError {
@@ -27,10 +40,29 @@ impl Display for ScenarioError {
 }
 
 /// Core trait that every bot should satisfy.
+///
+/// Apart from `play`, a scenario may hook into its lifecycle: `setup` is called once before the
+/// first run (a scenario that fails it is disabled and never played), `health_check` before every
+/// run (the run is skipped if it fails) and `teardown` once after the last run.
 #[async_trait::async_trait]
 pub trait Scenario<C>: Send + Sync + 'static {
     /// Runs the scenario and returns whether it succeeded.
     async fn play(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()>;
+
+    /// Prepares the scenario for running.
+    async fn setup(&mut self, _connection: &C, _logger: &ScenarioLogging) -> AnyResult<()> {
+        Ok(())
+    }
+
+    /// Checks whether the scenario can be played right now.
+    async fn health_check(&self, _connection: &C, _logger: &ScenarioLogging) -> AnyResult<()> {
+        Ok(())
+    }
+
+    /// Cleans up after the scenario.
+    async fn teardown(&mut self, _connection: &C, _logger: &ScenarioLogging) -> AnyResult<()> {
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -38,49 +70,121 @@ impl<C: Send + Sync + 'static> Scenario<C> for Box<dyn Scenario<C>> {
     async fn play(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
         self.as_mut().play(connection, logger).await
     }
+
+    async fn setup(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        self.as_mut().setup(connection, logger).await
+    }
+
+    async fn health_check(&self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        self.as_ref().health_check(connection, logger).await
+    }
+
+    async fn teardown(&mut self, connection: &C, logger: &ScenarioLogging) -> AnyResult<()> {
+        self.as_mut().teardown(connection, logger).await
+    }
+}
+
+/// Log target of the records of scenario `ident` scheduled in `environment`.
+///
+/// Scenarios of different environments may share identifiers, so the target carries both.
+pub fn log_target(environment: &str, ident: &Ident) -> String {
+    format!("{}/{}", environment, ident.0)
+}
+
+/// Environment and scenario identifier the records of `target` come from (if it has been built by
+/// `log_target`).
+pub fn split_log_target(target: &str) -> Option<(&str, Ident)> {
+    target
+        .split_once('/')
+        .map(|(environment, ident)| (environment, Ident::from(ident)))
 }
 
 pub struct ScheduledScenario<C> {
+    /// Name of the environment in which the scenario is scheduled.
+    environment: String,
     /// Identifier for this instance of the scenario.
     ident: Ident,
     /// How often should it be run.
     interval: Duration,
-    /// The connection to use for the scenario.
-    connection: C,
+    /// Source of connections for consecutive runs.
+    connections: Box<dyn ConnectionProvider<C>>,
     /// The actual scenario to perform.
     scenario: Box<dyn Scenario<C>>,
 }
 
 impl<C: Send + Sync + 'static> ScheduledScenario<C> {
     pub fn new(
+        environment: String,
         ident: Ident,
         interval: Duration,
-        connection: C,
+        connections: impl ConnectionProvider<C>,
         scenario: impl Scenario<C>,
     ) -> ScheduledScenario<C> {
         ScheduledScenario {
+            environment,
             ident,
             interval,
-            connection,
+            connections: Box::new(connections),
             scenario: Box::new(scenario),
         }
     }
 
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+
     pub fn ident(&self) -> Ident {
         self.ident.clone()
     }
 
+    /// Target of the log records of this scenario (see `log_target`).
+    pub fn log_target(&self) -> String {
+        log_target(&self.environment, &self.ident)
+    }
+
     pub fn interval(&self) -> Duration {
         self.interval
     }
 
-    pub async fn play(&mut self) -> AnyResult<()> {
-        self.scenario
-            .play(
-                &self.connection,
-                &ScenarioLogging {
-                    ident: self.ident.clone(),
-                },
+    /// Connection for the next run. `None` means that no node is available now.
+    pub fn next_connection(&self) -> Option<C> {
+        self.connections.next_connection()
+    }
+
+    fn logger(&self) -> ScenarioLogging {
+        ScenarioLogging::new(self.log_target())
+    }
+
+    pub async fn play(&mut self, connection: &C) -> AnyResult<()> {
+        let logger = self.logger();
+        CURRENT_SCENARIO
+            .scope(self.ident.clone(), self.scenario.play(connection, &logger))
+            .await
+    }
+
+    pub async fn setup(&mut self, connection: &C) -> AnyResult<()> {
+        let logger = self.logger();
+        CURRENT_SCENARIO
+            .scope(self.ident.clone(), self.scenario.setup(connection, &logger))
+            .await
+    }
+
+    pub async fn health_check(&self, connection: &C) -> AnyResult<()> {
+        let logger = self.logger();
+        CURRENT_SCENARIO
+            .scope(
+                self.ident.clone(),
+                self.scenario.health_check(connection, &logger),
+            )
+            .await
+    }
+
+    pub async fn teardown(&mut self, connection: &C) -> AnyResult<()> {
+        let logger = self.logger();
+        CURRENT_SCENARIO
+            .scope(
+                self.ident.clone(),
+                self.scenario.teardown(connection, &logger),
             )
             .await
     }
@@ -97,6 +201,12 @@ pub enum ScenarioStatus {
     Failure,
     /// The scenario is running now.
     Running,
+    /// Last run has been skipped, because no node was available or the health check failed.
+    /// The scenario is scheduled.
+    Skipped,
+    /// Setup of the scenario failed (see `ScenarioDetails::disabled_reason`). The scenario is not
+    /// scheduled anymore.
+    Disabled,
 }
 
 /// The struct representing a running bot.
@@ -113,6 +223,9 @@ pub struct ScenarioDetails {
     pub interval: Duration,
     /// Scenario status.
     pub last_status: ScenarioStatus,
+    /// Why the scenario has been disabled (if it has).
+    #[serde(default)]
+    pub disabled_reason: Option<String>,
 }
 
 impl ScenarioDetails {
@@ -123,6 +236,7 @@ impl ScenarioDetails {
             failures: 0,
             interval: scenario.interval(),
             last_status: ScenarioStatus::NotLaunchedYet,
+            disabled_reason: None,
         }
     }
 }
@@ -147,28 +261,46 @@ impl ScenarioLogs {
 }
 
 pub struct ScenarioLogging {
-    ident: Ident,
+    /// Log target of the scenario (see `log_target`).
+    target: String,
+    /// Prepended to every message (used for telling apart children of composed scenarios).
+    prefix: String,
 }
 
 impl ScenarioLogging {
+    pub(crate) fn new(target: String) -> Self {
+        ScenarioLogging {
+            target,
+            prefix: String::new(),
+        }
+    }
+
+    /// Logging for a part of the scenario: messages go to the same log, prefixed with `label`.
+    pub fn child<L: Display>(&self, label: L) -> ScenarioLogging {
+        ScenarioLogging {
+            target: self.target.clone(),
+            prefix: format!("{}[{}] ", self.prefix, label),
+        }
+    }
+
     pub fn trace<M: Debug>(&self, message: M) {
-        trace!(target: self.ident.0.as_str(), "{:?}", message)
+        trace!(target: self.target.as_str(), "{}{:?}", self.prefix, message)
     }
 
     pub fn debug<M: Debug>(&self, message: M) {
-        debug!(target: self.ident.0.as_str(), "{:?}", message)
+        debug!(target: self.target.as_str(), "{}{:?}", self.prefix, message)
     }
 
     pub fn info<M: Debug>(&self, message: M) {
-        info!(target: self.ident.0.as_str(), "{:?}", message)
+        info!(target: self.target.as_str(), "{}{:?}", self.prefix, message)
     }
 
     pub fn warn<M: Debug>(&self, message: M) {
-        warn!(target: self.ident.0.as_str(), "{:?}", message)
+        warn!(target: self.target.as_str(), "{}{:?}", self.prefix, message)
     }
 
     pub fn error<M: Debug>(&self, message: M) {
-        error!(target: self.ident.0.as_str(), "{:?}", message)
+        error!(target: self.target.as_str(), "{}{:?}", self.prefix, message)
     }
 
     pub fn log_result<R: Debug>(&self, result: AnyResult<R>) -> AnyResult<R> {
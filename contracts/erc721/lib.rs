@@ -0,0 +1,142 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+/// An ERC721-like (non-fungible token) contract. Tokens are minted by anyone who pays the price in
+/// a PSP22 token, which is charged with a cross-contract call to `PSP22::transfer_from`.
+#[ink::contract]
+mod erc721 {
+    use ink_env::{
+        call::{build_call, Call, ExecutionInput, Selector},
+        DefaultEnvironment,
+    };
+    use ink_prelude::{string::String, vec::Vec};
+    use ink_storage::{traits::SpreadAllocate, Mapping};
+
+    pub type TokenId = u32;
+
+    /// Selector of `PSP22::transfer_from`.
+    const PSP22_TRANSFER_FROM: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+
+    /// Mirror of `PSP22Error`, so that results of the token contract can be decoded.
+    #[derive(scale::Encode, scale::Decode)]
+    pub enum PSP22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        NotOwner,
+        TokenNotFound,
+        PaymentFailed,
+    }
+
+    #[ink(storage)]
+    #[derive(SpreadAllocate)]
+    pub struct Erc721 {
+        /// PSP22 token in which the price is paid.
+        token: AccountId,
+        price: Balance,
+        /// Who receives the payments (the instantiator).
+        beneficiary: AccountId,
+        next_id: TokenId,
+        owners: Mapping<TokenId, AccountId>,
+        balances: Mapping<AccountId, u32>,
+    }
+
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        #[ink(topic)]
+        id: TokenId,
+    }
+
+    impl Erc721 {
+        #[ink(constructor)]
+        pub fn new(token: AccountId, price: Balance) -> Self {
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.token = token;
+                contract.price = price;
+                contract.beneficiary = Self::env().caller();
+            })
+        }
+
+        /// Mints the next token to the caller, who pays the price with `PSP22::transfer_from`. So
+        /// this contract must be approved to spend the tokens of the caller first.
+        #[ink(message)]
+        pub fn mint(&mut self) -> Result<TokenId, Error> {
+            let caller = self.env().caller();
+            let payment = build_call::<DefaultEnvironment>()
+                .call_type(Call::new().callee(self.token).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM))
+                        .push_arg(caller)
+                        .push_arg(self.beneficiary)
+                        .push_arg(self.price)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<core::result::Result<(), PSP22Error>>()
+                .fire();
+            if !matches!(payment, Ok(Ok(()))) {
+                return Err(Error::PaymentFailed);
+            }
+
+            let id = self.next_id;
+            self.next_id += 1;
+            self.owners.insert(&id, &caller);
+            self.balances
+                .insert(&caller, &(self.balance_of(caller) + 1));
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(caller),
+                id,
+            });
+            Ok(id)
+        }
+
+        #[ink(message)]
+        pub fn transfer(&mut self, to: AccountId, id: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            match self.owners.get(&id) {
+                None => return Err(Error::TokenNotFound),
+                Some(owner) if owner != caller => return Err(Error::NotOwner),
+                _ => {}
+            }
+
+            self.owners.insert(&id, &to);
+            self.balances
+                .insert(&caller, &(self.balance_of(caller) - 1));
+            self.balances.insert(&to, &(self.balance_of(to) + 1));
+            self.env().emit_event(Transfer {
+                from: Some(caller),
+                to: Some(to),
+                id,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn owner_of(&self, id: TokenId) -> Option<AccountId> {
+            self.owners.get(&id)
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, owner: AccountId) -> u32 {
+            self.balances.get(&owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn price(&self) -> Balance {
+            self.price
+        }
+    }
+}
@@ -1,9 +1,9 @@
 use iced::{executor, Application, Command, Element};
 
-use common::{Ident, ScenarioDetails, ScenarioLogs};
+use common::{Ident, NodeHealth, ScenarioDetails, ScenarioLogs};
 
 use crate::{
-    data::{fetch_logs, fetch_scenarios},
+    data::{fetch_environments, fetch_health, fetch_logs, fetch_scenarios},
     message::Message,
     view::{LogsPage, OverviewPage},
 };
@@ -25,10 +25,16 @@ pub struct App {
     /// Base URL where stats from backend are exposed. Don't forget about protocol prefix
     /// (like `http://`), even if using localhost.
     stats_base_url: String,
+    /// Names of the environments run by the backend.
+    environments: Vec<String>,
+    /// Environment which stats are displayed.
+    environment: Option<String>,
     /// Fetched scenarios.
     scenarios: Option<Vec<ScenarioDetails>>,
     /// Fetched logs for a particular scenario.
     logs: Option<ScenarioLogs>,
+    /// Fetched health of all the nodes.
+    health: Option<Vec<NodeHealth>>,
 
     /// Overview page view.
     ///
@@ -46,6 +52,35 @@ pub struct Flags {
     pub stats_base_url: String,
 }
 
+/// Requests everything displayed on the overview page. `environment_url` is the base URL where
+/// stats of a single environment are exposed.
+fn fetch_overview(environment_url: String) -> Command<Message> {
+    Command::batch([
+        Command::perform(
+            fetch_scenarios(environment_url.clone()),
+            Message::FetchedScenarios,
+        ),
+        Command::perform(fetch_health(environment_url), Message::FetchedHealth),
+    ])
+}
+
+impl App {
+    /// Base URL where stats of the selected environment are exposed.
+    fn environment_url(&self) -> Option<String> {
+        self.environment
+            .as_ref()
+            .map(|environment| format!("{}/{}", self.stats_base_url, environment))
+    }
+
+    /// Requests the overview of the selected environment (if any).
+    fn refresh_overview(&self) -> Command<Message> {
+        match self.environment_url() {
+            Some(environment_url) => fetch_overview(environment_url),
+            None => Command::none(),
+        }
+    }
+}
+
 impl Application for App {
     type Executor = executor::Default;
     type Message = Message;
@@ -56,14 +91,17 @@ impl Application for App {
             App {
                 current_route: Route::Overview,
                 stats_base_url: flags.stats_base_url.clone(),
+                environments: vec![],
+                environment: None,
                 scenarios: None,
                 logs: None,
+                health: None,
                 overview_page: None,
                 logs_page: None,
             },
             Command::perform(
-                fetch_scenarios(flags.stats_base_url),
-                Message::FetchedScenarios,
+                fetch_environments(flags.stats_base_url),
+                Message::FetchedEnvironments,
             ),
         )
     }
@@ -76,17 +114,34 @@ impl Application for App {
         match message {
             Message::GoToOverview => {
                 self.current_route = Route::Overview;
-                Command::perform(
-                    fetch_scenarios(self.stats_base_url.clone()),
-                    Message::FetchedScenarios,
-                )
+                self.refresh_overview()
             }
             Message::GoToLogs(scenario) => {
                 self.current_route = Route::Logs(scenario.clone());
-                Command::perform(
-                    fetch_logs(scenario, self.stats_base_url.clone()),
-                    Message::FetchedLogs,
-                )
+                match self.environment_url() {
+                    Some(environment_url) => Command::perform(
+                        fetch_logs(scenario, environment_url),
+                        Message::FetchedLogs,
+                    ),
+                    None => Command::none(),
+                }
+            }
+            Message::SelectEnvironment(environment) => {
+                self.environment = Some(environment);
+                self.scenarios = None;
+                self.health = None;
+                self.current_route = Route::Overview;
+                self.refresh_overview()
+            }
+            Message::FetchedEnvironments(result) => {
+                self.environments = result.unwrap_or_default();
+                match self.environments.first() {
+                    Some(first) if self.environment.is_none() => {
+                        self.environment = Some(first.clone());
+                        self.refresh_overview()
+                    }
+                    _ => Command::none(),
+                }
             }
             Message::FetchedScenarios(result) => {
                 self.scenarios = result.ok();
@@ -96,13 +151,22 @@ impl Application for App {
                 self.logs = result.ok();
                 Command::none()
             }
+            Message::FetchedHealth(result) => {
+                self.health = result.ok();
+                Command::none()
+            }
         }
     }
 
     fn view(&mut self) -> Element<Message> {
         match self.current_route {
             Route::Overview => {
-                self.overview_page = Some(OverviewPage::new(self.scenarios.clone()));
+                self.overview_page = Some(OverviewPage::new(
+                    self.environments.clone(),
+                    self.environment.clone(),
+                    self.scenarios.clone(),
+                    self.health.clone(),
+                ));
                 self.overview_page.as_mut().unwrap().view()
             }
             Route::Logs(ref scenario) => {
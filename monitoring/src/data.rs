@@ -1,6 +1,19 @@
 use std::collections::HashMap;
 
-use common::{Ident, ScenarioDetails, ScenarioLogs};
+use common::{Ident, NodeHealth, ScenarioDetails, ScenarioLogs};
+
+pub async fn fetch_environments(base_url: String) -> Result<Vec<String>, String> {
+    inner_fetch_environments(base_url)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+async fn inner_fetch_environments(base_url: String) -> reqwest::Result<Vec<String>> {
+    reqwest::get(format!("{}/environments", base_url))
+        .await?
+        .json::<Vec<String>>()
+        .await
+}
 
 pub async fn fetch_scenarios(base_url: String) -> Result<Vec<ScenarioDetails>, String> {
     inner_fetch_scenarios(base_url)
@@ -35,3 +48,16 @@ async fn inner_fetch_logs(
             .await?,
     )
 }
+
+pub async fn fetch_health(base_url: String) -> Result<Vec<NodeHealth>, String> {
+    inner_fetch_health(base_url)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+async fn inner_fetch_health(base_url: String) -> reqwest::Result<Vec<NodeHealth>> {
+    reqwest::get(format!("{}/health", base_url))
+        .await?
+        .json::<Vec<NodeHealth>>()
+        .await
+}
@@ -1,13 +1,19 @@
-use common::{Ident, ScenarioDetails, ScenarioLogs};
+use common::{Ident, NodeHealth, ScenarioDetails, ScenarioLogs};
 
 /// Events driving the logic of the app.
 #[derive(Debug, Clone)]
 pub enum Message {
+    /// Names of the available environments are ready.
+    FetchedEnvironments(Result<Vec<String>, String>),
     /// Requested scenarios are ready.
     FetchedScenarios(Result<Vec<ScenarioDetails>, String>),
     /// Requested logs for a scenario are ready.
     FetchedLogs(Result<ScenarioLogs, String>),
+    /// Requested health of all the nodes is ready.
+    FetchedHealth(Result<Vec<NodeHealth>, String>),
 
+    /// Another environment has been selected.
+    SelectEnvironment(String),
     /// `Route::Overview` has been selected.
     GoToOverview,
     /// `Route::Logs` has been selected.
@@ -1,29 +1,52 @@
-use common::ScenarioDetails;
-use iced::{scrollable, Alignment, Column, Container, Element, Length, Rule, Scrollable, Text};
+use common::{NodeHealth, ScenarioDetails};
+use iced::{
+    button, scrollable, Alignment, Button, Column, Container, Element, Length, Row, Rule,
+    Scrollable, Text,
+};
 
 use crate::{
     message::Message,
     view::{
         scenario::ScenarioView,
-        style::{AlephTheme, FontSize, Spacing},
+        style::{AlephTheme, Color, FontSize, Spacing},
     },
 };
 
 pub struct OverviewPage {
+    /// Environment which stats are displayed.
+    environment: Option<String>,
+    /// Names of all the environments together with states of their buttons.
+    environment_buttons: Vec<(String, button::State)>,
+    health: Option<Vec<NodeHealth>>,
     scenario_views: Vec<ScenarioView>,
     scroll_state: scrollable::State,
 }
 
 impl OverviewPage {
-    pub fn new(scenarios: Option<Vec<ScenarioDetails>>) -> Self {
+    pub fn new(
+        environments: Vec<String>,
+        environment: Option<String>,
+        scenarios: Option<Vec<ScenarioDetails>>,
+        health: Option<Vec<NodeHealth>>,
+    ) -> Self {
+        let environment_buttons = environments
+            .into_iter()
+            .map(|name| (name, button::State::new()))
+            .collect();
         match scenarios {
             None => OverviewPage {
+                environment,
+                environment_buttons,
+                health,
                 scenario_views: vec![],
                 scroll_state: scrollable::State::new(),
             },
             Some(mut scenarios) => {
                 scenarios.sort_by_key(|s| s.ident.0.clone());
                 OverviewPage {
+                    environment,
+                    environment_buttons,
+                    health,
                     scenario_views: scenarios
                         .iter()
                         .map(|s| ScenarioView::new(s.clone()))
@@ -41,6 +64,13 @@ impl OverviewPage {
             Self::scenario_list(&mut self.scenario_views)
         };
         let scenario_list = Scrollable::new(&mut self.scroll_state)
+            .push(Self::environment_switcher(
+                &mut self.environment_buttons,
+                &self.environment,
+            ))
+            .push(Rule::horizontal(Spacing::NORMAL))
+            .push(Self::nodes_health(&self.health))
+            .push(Rule::horizontal(Spacing::NORMAL))
             .push(scenario_list)
             .align_items(Alignment::Center)
             .width(Length::Fill)
@@ -55,6 +85,94 @@ impl OverviewPage {
             .into()
     }
 
+    /// Row of buttons, one per environment. The selected environment cannot be pressed.
+    fn environment_switcher<'a>(
+        environment_buttons: &'a mut [(String, button::State)],
+        selected: &Option<String>,
+    ) -> Element<'a, Message> {
+        if environment_buttons.is_empty() {
+            return Text::new("No environments available")
+                .size(FontSize::H3)
+                .color(Color::GRAY)
+                .into();
+        }
+
+        environment_buttons
+            .iter_mut()
+            .fold(Row::new().spacing(Spacing::SMALL), |row, (name, state)| {
+                let is_selected = selected.as_deref() == Some(name.as_str());
+                let label = Text::new(format!(" {} ", name)).size(FontSize::H3);
+                let button = Button::new(
+                    state,
+                    if is_selected {
+                        label.color(Color::PRIMARY)
+                    } else {
+                        label
+                    },
+                )
+                .style(AlephTheme);
+                row.push(if is_selected {
+                    button
+                } else {
+                    button.on_press(Message::SelectEnvironment(name.clone()))
+                })
+            })
+            .into()
+    }
+
+    fn nodes_health<'a>(health: &Option<Vec<NodeHealth>>) -> Element<'a, Message> {
+        match health {
+            None => Self::health_entry(
+                Text::new("Nodes: unknown").color(Color::GRAY),
+                Text::new("Cannot fetch node health"),
+            ),
+            Some(health) => health
+                .iter()
+                .fold(
+                    Column::new()
+                        .spacing(Spacing::NORMAL)
+                        .align_items(Alignment::Center),
+                    |col, node| col.push(Self::node_health(node)),
+                )
+                .into(),
+        }
+    }
+
+    fn node_health<'a>(health: &NodeHealth) -> Element<'a, Message> {
+        let describe = |value: Option<u32>| match value {
+            Some(value) => value.to_string(),
+            None => "?".to_string(),
+        };
+
+        let status = if health.connected {
+            Text::new(format!("Node {} ({}): up", health.name, health.address)).color(Color::GREEN)
+        } else {
+            Text::new(format!("Node {} ({}): down", health.name, health.address)).color(Color::RED)
+        };
+        let details = Text::new(format!(
+            "best block: {}, finalized block: {}, peers: {}, {}",
+            describe(health.best_block),
+            describe(health.finalized_block),
+            describe(health.peers),
+            match health.is_syncing {
+                Some(true) => "syncing",
+                Some(false) => "synced",
+                None => "sync state unknown",
+            }
+        ));
+
+        Self::health_entry(status, details)
+    }
+
+    fn health_entry<'a>(status: Text, details: Text) -> Element<'a, Message> {
+        Column::new()
+            .spacing(Spacing::SMALL)
+            .align_items(Alignment::Center)
+            .push(status.size(FontSize::H3))
+            .push(details.size(FontSize::CONTENT))
+            .into()
+    }
+
     fn no_scenarios<'a>() -> Element<'a, Message> {
         Column::new()
             .push(Text::new("No scenarios available").size(FontSize::H2))
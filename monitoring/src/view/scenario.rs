@@ -45,11 +45,18 @@ impl ScenarioView {
         ))
         .size(FontSize::CONTENT);
 
-        let info_view = Column::new()
+        let mut info_view = Column::new()
             .spacing(Spacing::SMALL)
             .push(title)
             .push(fails)
             .push(interval);
+        if let Some(reason) = &self.scenario.disabled_reason {
+            info_view = info_view.push(
+                Text::new(format!("disabled: {}", reason))
+                    .size(FontSize::CONTENT)
+                    .color(Color::RED),
+            );
+        }
 
         let logs_button = Button::new(
             &mut self.logs_button,
@@ -76,6 +83,8 @@ impl ScenarioView {
                 Text::new("Status: not launched yet").color(Color::GRAY)
             }
             ScenarioStatus::Running => Text::new("Status: running").color(Color::GRAY),
+            ScenarioStatus::Skipped => Text::new("Status: skipped").color(Color::RED),
+            ScenarioStatus::Disabled => Text::new("Status: disabled").color(Color::RED),
         }
         .size(FontSize::H3)
         .vertical_alignment(Vertical::Center)
@@ -0,0 +1,402 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{compose_call, AccountId, Balance, GenericAddress, Hash, XtStatus},
+    AnyConnection, KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use codec::{Compact, Encode};
+use hex::FromHex;
+use rand::{thread_rng, Rng};
+use serde_json::{json, Value as Json};
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT};
+
+use chain_support::{send_managed_xt, with_event_matching, NodeConnection, EVENT_TIMEOUT};
+
+use crate::{
+    events::{CodeStored, ContractEmitted, Instantiated},
+    metadata::{ContractEvent, ContractMetadata, Value},
+    storage::code_exists,
+    ContractsError,
+};
+
+/// `ExecReturnValue::flags` bit set when the contract has reverted.
+const REVERT_FLAG: u64 = 1;
+
+/// Compiled contract together with its metadata, as copied to the artifacts directory by
+/// `make build-contracts`.
+#[derive(Clone, Debug)]
+pub struct Artifact {
+    pub name: String,
+    pub code: Vec<u8>,
+    pub code_hash: Hash,
+    pub metadata: Arc<ContractMetadata>,
+}
+
+impl Artifact {
+    /// Paths of the code (`<name>.wasm`) and the metadata (`<name>.json`) of contract `name`.
+    pub fn paths(dir: &Path, name: &str) -> (PathBuf, PathBuf) {
+        (
+            dir.join(format!("{}.wasm", name)),
+            dir.join(format!("{}.json", name)),
+        )
+    }
+
+    pub fn exists(dir: &Path, name: &str) -> bool {
+        let (code, metadata) = Self::paths(dir, name);
+        code.is_file() && metadata.is_file()
+    }
+
+    pub fn load(dir: &Path, name: &str) -> AnyResult<Self> {
+        let (code_path, metadata_path) = Self::paths(dir, name);
+        let code = fs::read(&code_path).map_err(|_| ContractsError::MissingArtifact(code_path))?;
+        let metadata = fs::read_to_string(&metadata_path)
+            .map_err(|_| ContractsError::MissingArtifact(metadata_path))?;
+        Ok(Artifact {
+            name: name.to_string(),
+            code_hash: BlakeTwo256::hash(&code),
+            code,
+            metadata: Arc::new(ContractMetadata::from_json(&metadata)?),
+        })
+    }
+}
+
+/// Outcome of a dry-run (`contracts_call` or `contracts_instantiate`).
+#[derive(Clone, Debug)]
+pub struct DryRun {
+    pub gas_consumed: u64,
+    pub gas_required: u64,
+    /// Storage deposit charged (positive) or refunded (negative).
+    pub storage_deposit: i128,
+    pub data: Vec<u8>,
+    /// Address of the new contract, in case of an instantiation.
+    pub account: Option<AccountId>,
+}
+
+impl DryRun {
+    fn parse(label: &str, response: Json) -> AnyResult<Self> {
+        let invalid = || ContractsError::InvalidResponse(response.to_string());
+        let gas = |key: &str| response[key].as_u64().ok_or_else(invalid);
+
+        let storage_deposit = match response["storageDeposit"].as_object() {
+            Some(deposit) if deposit.contains_key("charge") => {
+                balance(&deposit["charge"]).ok_or_else(invalid)? as i128
+            }
+            Some(deposit) if deposit.contains_key("refund") => {
+                -(balance(&deposit["refund"]).ok_or_else(invalid)? as i128)
+            }
+            _ => return Err(invalid().into()),
+        };
+
+        let ok = &response["result"]["Ok"];
+        if ok.is_null() {
+            return Err(ContractsError::DryRunFailed {
+                label: label.to_string(),
+                error: response["result"]["Err"].to_string(),
+            }
+            .into());
+        }
+        // Instantiation wraps the execution result together with the new address.
+        let (result, account) = match ok.get("accountId") {
+            Some(account) => (&ok["result"], Some(account.clone())),
+            None => (ok, None),
+        };
+
+        let flags = result["flags"]
+            .as_u64()
+            .or_else(|| result["flags"]["bits"].as_u64())
+            .ok_or_else(invalid)?;
+        let data = result["data"]
+            .as_str()
+            .and_then(|data| Vec::from_hex(data.trim_start_matches("0x")).ok())
+            .ok_or_else(invalid)?;
+        ensure!(
+            flags & REVERT_FLAG == 0,
+            ContractsError::Reverted {
+                label: label.to_string(),
+                data,
+            }
+        );
+
+        let account = match account {
+            Some(account) => Some(
+                account
+                    .as_str()
+                    .and_then(|account| account.parse::<AccountId>().ok())
+                    .ok_or_else(invalid)?,
+            ),
+            None => None,
+        };
+
+        Ok(DryRun {
+            gas_consumed: gas("gasConsumed")?,
+            gas_required: gas("gasRequired")?,
+            storage_deposit,
+            data,
+            account,
+        })
+    }
+
+    /// Makes sure that the gas estimation is consistent and fits within `max_gas`.
+    fn check_gas(&self, label: &str, max_gas: u64) -> AnyResult<()> {
+        ensure!(
+            self.gas_consumed <= self.gas_required && self.gas_required <= max_gas,
+            ContractsError::UnexpectedGas {
+                label: label.to_string(),
+                consumed: self.gas_consumed,
+                required: self.gas_required,
+                max: max_gas,
+            }
+        );
+        Ok(())
+    }
+}
+
+/// Balances are serialized either as numbers or as hex strings.
+fn balance(value: &Json) -> Option<Balance> {
+    match value {
+        Json::Number(number) => number.as_u64().map(Balance::from),
+        Json::String(hex) => Balance::from_str_radix(hex.trim_start_matches("0x"), 16).ok(),
+        _ => None,
+    }
+}
+
+/// An instantiated contract.
+#[derive(Clone, Debug)]
+pub struct Contract {
+    pub name: String,
+    pub address: AccountId,
+    pub metadata: Arc<ContractMetadata>,
+}
+
+impl Contract {
+    /// Dry-runs message `label` with `args`, sent by `origin`.
+    pub async fn dry_run<A: Encode>(
+        &self,
+        connection: &NodeConnection,
+        origin: &AccountId,
+        label: &str,
+        args: A,
+        max_gas: u64,
+    ) -> AnyResult<DryRun> {
+        let data = self.metadata.message_data(label, args)?;
+        let response = connection
+            .contracts_call(json!({
+                "origin": origin.to_string(),
+                "dest": self.address.to_string(),
+                "value": 0,
+                "gasLimit": max_gas,
+                "storageDepositLimit": null,
+                "inputData": format!("0x{}", hex::encode(data)),
+            }))
+            .await?;
+        DryRun::parse(label, response)
+    }
+
+    /// Reads (with a dry-run) what message `label` returns.
+    pub async fn query<A: Encode>(
+        &self,
+        connection: &NodeConnection,
+        label: &str,
+        args: A,
+        max_gas: u64,
+    ) -> AnyResult<Value> {
+        let dry_run = self
+            .dry_run(connection, &self.address, label, args, max_gas)
+            .await?;
+        self.metadata.decode_return(label, &dry_run.data)
+    }
+
+    /// Checks (with a dry-run) that message `label` returns `expected` now.
+    pub async fn expect<A: Encode>(
+        &self,
+        connection: &NodeConnection,
+        label: &str,
+        args: A,
+        expected: Value,
+        max_gas: u64,
+    ) -> AnyResult<()> {
+        let found = self.query(connection, label, args, max_gas).await?;
+        ensure!(
+            found == expected,
+            ContractsError::UnexpectedReturn {
+                label: label.to_string(),
+                found,
+                expected,
+            }
+        );
+        Ok(())
+    }
+
+    /// Sends message `label` with `args`, signed by `signer`, and waits until it is finalized.
+    ///
+    /// The call is dry-run first. It must return `expected`, its gas estimation must fit within
+    /// `max_gas` and exactly the required gas is used as the limit. Returns the dry-run.
+    pub async fn call<A: Encode>(
+        &self,
+        connection: &NodeConnection,
+        signer: &KeyPair,
+        label: &str,
+        args: A,
+        expected: Value,
+        max_gas: u64,
+    ) -> AnyResult<DryRun> {
+        let data = self.metadata.message_data(label, &args)?;
+        let dry_run = self
+            .dry_run(
+                connection,
+                &account_from_keypair(signer),
+                label,
+                &args,
+                max_gas,
+            )
+            .await?;
+        dry_run.check_gas(label, max_gas)?;
+        let found = self.metadata.decode_return(label, &dry_run.data)?;
+        ensure!(
+            found == expected,
+            ContractsError::UnexpectedReturn {
+                label: label.to_string(),
+                found,
+                expected,
+            }
+        );
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Contracts",
+            "call",
+            GenericAddress::Id(self.address.clone()),
+            Compact::<Balance>(0),
+            Compact(dry_run.gas_required),
+            None::<Compact<Balance>>,
+            data
+        );
+        send_managed_xt(
+            connection,
+            signer,
+            call,
+            "Contracts::call",
+            XtStatus::Finalized,
+        )
+        .await?;
+        Ok(dry_run)
+    }
+
+    /// Matcher of `ContractEmitted`, accepting `expected` emitted by this contract.
+    pub fn emitted(
+        &self,
+        expected: ContractEvent,
+    ) -> impl Fn(&ContractEmitted) -> bool + Send + 'static {
+        let address = self.address.clone();
+        let metadata = self.metadata.clone();
+        move |event: &ContractEmitted| {
+            event.contract == address
+                && metadata
+                    .decode_event(&event.data)
+                    .map_or(false, |decoded| decoded == expected)
+        }
+    }
+}
+
+/// Uploads the code of `artifact`, unless it is already on chain.
+pub async fn upload(
+    connection: &NodeConnection,
+    signer: &KeyPair,
+    artifact: &Artifact,
+) -> AnyResult<()> {
+    if code_exists(connection, artifact.code_hash).await? {
+        return Ok(());
+    }
+
+    let call = compose_call!(
+        connection.as_connection().metadata,
+        "Contracts",
+        "upload_code",
+        artifact.code.clone(),
+        None::<Compact<Balance>>
+    );
+    let code_hash = artifact.code_hash;
+    with_event_matching(
+        connection,
+        move |event: &CodeStored| event.code_hash == code_hash,
+        EVENT_TIMEOUT,
+        send_managed_xt(
+            connection,
+            signer,
+            call,
+            "Contracts::upload_code",
+            XtStatus::Finalized,
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Instantiates the (already uploaded) code of `artifact` with constructor `label` and `args`.
+/// The salt is random, so that every call results in a new contract.
+pub async fn instantiate<A: Encode>(
+    connection: &NodeConnection,
+    signer: &KeyPair,
+    artifact: &Artifact,
+    label: &str,
+    args: A,
+    max_gas: u64,
+) -> AnyResult<Contract> {
+    let data = artifact.metadata.constructor_data(label, args)?;
+    let salt = thread_rng().gen::<[u8; 32]>().to_vec();
+
+    let response = connection
+        .contracts_instantiate(json!({
+            "origin": account_from_keypair(signer).to_string(),
+            "value": 0,
+            "gasLimit": max_gas,
+            "storageDepositLimit": null,
+            "code": { "existing": format!("{:?}", artifact.code_hash) },
+            "data": format!("0x{}", hex::encode(&data)),
+            "salt": format!("0x{}", hex::encode(&salt)),
+        }))
+        .await?;
+    let dry_run = DryRun::parse(label, response)?;
+    dry_run.check_gas(label, max_gas)?;
+    let address = dry_run
+        .account
+        .ok_or_else(|| ContractsError::InvalidResponse("no address".to_string()))?;
+
+    let call = compose_call!(
+        connection.as_connection().metadata,
+        "Contracts",
+        "instantiate",
+        Compact::<Balance>(0),
+        Compact(dry_run.gas_required),
+        None::<Compact<Balance>>,
+        artifact.code_hash,
+        data,
+        salt
+    );
+    let expected = address.clone();
+    with_event_matching(
+        connection,
+        move |event: &Instantiated| event.contract == expected,
+        EVENT_TIMEOUT,
+        send_managed_xt(
+            connection,
+            signer,
+            call,
+            "Contracts::instantiate",
+            XtStatus::Finalized,
+        ),
+    )
+    .await?;
+
+    Ok(Contract {
+        name: artifact.name.clone(),
+        address,
+        metadata: artifact.metadata.clone(),
+    })
+}
@@ -0,0 +1,29 @@
+//! Here we have 'copies' of the events from `pallet_contracts`, since we need `Event` trait
+//! implemented for them.
+use aleph_client::substrate_api_client::{AccountId, Hash};
+use codec::Decode;
+
+use chain_support::Event;
+
+/// Emitted by `upload_code` (and `instantiate_with_code`) when the code was not stored before.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Contracts"]
+pub struct CodeStored {
+    pub code_hash: Hash,
+}
+
+/// Emitted by `instantiate`, with the address of the new contract.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Contracts"]
+pub struct Instantiated {
+    pub _deployer: AccountId,
+    pub contract: AccountId,
+}
+
+/// Emitted when a contract emits an event. `data` is the SCALE-encoded ink! event.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Contracts"]
+pub struct ContractEmitted {
+    pub contract: AccountId,
+    pub data: Vec<u8>,
+}
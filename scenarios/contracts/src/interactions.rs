@@ -0,0 +1,429 @@
+use std::path::PathBuf;
+
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{AccountId, Balance},
+    KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use rand::{seq::SliceRandom, thread_rng};
+use serde::Deserialize;
+
+use chain_support::{with_event_matching, NodeConnection, Preflight, Requirements, EVENT_TIMEOUT};
+use common::{Scenario, ScenarioLogging};
+
+use crate::{
+    contract::{instantiate, upload, Artifact, Contract, DryRun},
+    metadata::{ContractEvent, Value},
+    psp22::{balance_of, deploy_token, transfer_event},
+    storage::reserved_balance,
+    ContractsError,
+};
+
+const FLIPPER: &str = "flipper";
+pub(crate) const PSP22: &str = "psp22";
+const ERC721: &str = "erc721";
+
+type TokenId = u32;
+
+/// Tokens moved by every `Flow::TokenTransfer`.
+const TRANSFER_VALUE: Balance = 1;
+
+pub(crate) fn default_artifacts() -> PathBuf {
+    PathBuf::from("contracts/artifacts")
+}
+
+fn all_flows() -> Vec<Flow> {
+    vec![Flow::Flip, Flow::TokenTransfer, Flow::MintNft]
+}
+
+pub(crate) fn default_max_gas() -> u64 {
+    100_000_000_000
+}
+
+pub(crate) fn default_token_supply() -> u64 {
+    1_000_000_000
+}
+
+fn default_nft_price() -> u64 {
+    10
+}
+
+chain_support::default_pool!("Contracts");
+
+/// What is done in a single run.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Flow {
+    /// `flip` on the flipper contract.
+    Flip,
+    /// PSP22 `transfer` between two accounts of the pool.
+    TokenTransfer,
+    /// PSP22 `approve` of the NFT contract, followed by `mint` on it, which pays for the token
+    /// with a contract-to-contract call to PSP22 `transfer_from`.
+    MintNft,
+}
+
+/// Contracts instantiated during setup.
+#[derive(Clone, Debug)]
+struct Deployed {
+    flipper: Contract,
+    token: Contract,
+    nft: Contract,
+}
+
+/// Total reserved balance of `contracts`, i.e. their storage deposit.
+async fn deposit(connection: &NodeConnection, contracts: &[&Contract]) -> AnyResult<i128> {
+    let mut total = 0;
+    for contract in contracts {
+        total += reserved_balance(connection, &contract.address).await? as i128;
+    }
+    Ok(total)
+}
+
+/// Checks that the storage deposit of the contracts has changed (from `before`) as estimated
+/// by `dry_run`.
+async fn check_deposit(
+    connection: &NodeConnection,
+    label: &str,
+    contracts: &[&Contract],
+    before: i128,
+    dry_run: &DryRun,
+) -> AnyResult<()> {
+    let found = deposit(connection, contracts).await? - before;
+    ensure!(
+        found == dry_run.storage_deposit,
+        ContractsError::UnexpectedDeposit {
+            label: label.to_string(),
+            found,
+            expected: dry_run.storage_deposit,
+        }
+    );
+    Ok(())
+}
+
+/// Id of the minted NFT, if `minted` is `Ok(id)`.
+fn minted_id(minted: &Value) -> Option<TokenId> {
+    match minted {
+        Value::Variant(variant, fields) if variant == "Ok" => match fields.as_slice() {
+            [Value::UInt(id)] => TokenId::try_from(*id).ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A scenario that deploys a few ink! contracts and interacts with them.
+///
+/// During setup, the flipper, the PSP22 token and the ERC721-like NFT contract (priced in the
+/// token) are uploaded (unless the code is already on chain) and instantiated by the first account
+/// of the pool, which then shares the token supply with the rest of the pool. The artifacts are
+/// read from `artifacts`, by default the ones checked in under `contracts/artifacts`.
+///
+/// Every run goes through one of `flows` (chosen randomly) with random accounts of the pool. Each
+/// call is dry-run first: it must return the expected value and its gas estimation must fit
+/// within `max_gas`. The call is then sent with exactly the required gas as the limit. It is
+/// confirmed by the `ContractEmitted` events (decoded with the contract metadata), the state read
+/// afterwards with dry-runs, and the change of the storage deposit of the contracts, which must
+/// match the estimated one.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ContractInteractions {
+    /// Directory with `<contract>.wasm` and `<contract>.json` files. By default,
+    /// `contracts/artifacts`.
+    #[serde(default = "default_artifacts")]
+    artifacts: PathBuf,
+    /// Flows chosen from. By default, all of them.
+    #[serde(default = "all_flows")]
+    flows: Vec<Flow>,
+    /// Most gas a single call may require.
+    #[serde(default = "default_max_gas")]
+    max_gas: u64,
+    /// Total supply of the PSP22 token.
+    #[serde(default = "default_token_supply")]
+    token_supply: u64,
+    /// Price of a single NFT in the PSP22 token.
+    #[serde(default = "default_nft_price")]
+    nft_price: u64,
+    /// Account pool of the callers. By default, `Contracts`.
+    #[serde(default = "default_pool")]
+    pool: String,
+    #[serde(skip)]
+    deployed: Option<Deployed>,
+}
+
+impl ContractInteractions {
+    /// `Flow::Flip`.
+    async fn flip(
+        &self,
+        connection: &NodeConnection,
+        flipper: &Contract,
+        caller: &KeyPair,
+    ) -> AnyResult<()> {
+        let value = match flipper.query(connection, "get", (), self.max_gas).await? {
+            Value::Bool(value) => value,
+            found => {
+                return Err(ContractsError::UnexpectedReturn {
+                    label: "get".to_string(),
+                    found,
+                    expected: Value::Bool(false),
+                }
+                .into())
+            }
+        };
+
+        let before = deposit(connection, &[flipper]).await?;
+        let (dry_run, _) = with_event_matching(
+            connection,
+            flipper.emitted(ContractEvent::new("Flipped").field("new_value", Value::Bool(!value))),
+            EVENT_TIMEOUT,
+            flipper.call(connection, caller, "flip", (), Value::unit(), self.max_gas),
+        )
+        .await?;
+        check_deposit(connection, "flip", &[flipper], before, &dry_run).await?;
+
+        flipper
+            .expect(connection, "get", (), Value::Bool(!value), self.max_gas)
+            .await
+    }
+
+    /// `Flow::TokenTransfer`.
+    async fn transfer_tokens(
+        &self,
+        connection: &NodeConnection,
+        token: &Contract,
+        sender: &KeyPair,
+        receiver: &AccountId,
+    ) -> AnyResult<()> {
+        let sender_account = account_from_keypair(sender);
+        let balances = [
+            balance_of(connection, token, &sender_account, self.max_gas).await?,
+            balance_of(connection, token, receiver, self.max_gas).await?,
+        ];
+
+        let before = deposit(connection, &[token]).await?;
+        let (dry_run, _) = with_event_matching(
+            connection,
+            token.emitted(transfer_event(&sender_account, receiver, TRANSFER_VALUE)),
+            EVENT_TIMEOUT,
+            token.call(
+                connection,
+                sender,
+                "transfer",
+                (receiver, TRANSFER_VALUE, Vec::<u8>::new()),
+                Value::ok(Value::unit()),
+                self.max_gas,
+            ),
+        )
+        .await?;
+        check_deposit(connection, "transfer", &[token], before, &dry_run).await?;
+
+        token
+            .expect(
+                connection,
+                "balance_of",
+                &sender_account,
+                Value::UInt(balances[0] - TRANSFER_VALUE),
+                self.max_gas,
+            )
+            .await?;
+        token
+            .expect(
+                connection,
+                "balance_of",
+                receiver,
+                Value::UInt(balances[1] + TRANSFER_VALUE),
+                self.max_gas,
+            )
+            .await
+    }
+
+    /// `Flow::MintNft`. The price goes to the deployer of the NFT contract.
+    async fn mint_nft(
+        &self,
+        connection: &NodeConnection,
+        deployed: &Deployed,
+        buyer: &KeyPair,
+        beneficiary: &AccountId,
+    ) -> AnyResult<()> {
+        let (token, nft) = (&deployed.token, &deployed.nft);
+        let buyer_account = account_from_keypair(buyer);
+        let price = self.nft_price as Balance;
+
+        let approval = ContractEvent::new("Approval")
+            .field("owner", Value::account(&buyer_account))
+            .field("spender", Value::account(&nft.address))
+            .field("value", Value::UInt(price));
+        with_event_matching(
+            connection,
+            token.emitted(approval),
+            EVENT_TIMEOUT,
+            token.call(
+                connection,
+                buyer,
+                "approve",
+                (&nft.address, price),
+                Value::ok(Value::unit()),
+                self.max_gas,
+            ),
+        )
+        .await?;
+
+        // The id of the next token is known only to the contract.
+        let dry_run = nft
+            .dry_run(connection, &buyer_account, "mint", (), self.max_gas)
+            .await?;
+        let minted = nft.metadata.decode_return("mint", &dry_run.data)?;
+        let id = minted_id(&minted).ok_or_else(|| ContractsError::UnexpectedReturn {
+            label: "mint".to_string(),
+            found: minted.clone(),
+            expected: Value::ok(Value::UInt(0)),
+        })?;
+
+        let nft_transfer = ContractEvent::new("Transfer")
+            .field("from", Value::none())
+            .field("to", Value::some(Value::account(&buyer_account)))
+            .field("id", Value::UInt(id as u128));
+        let contracts = [token, nft];
+        let before = deposit(connection, &contracts).await?;
+        let ((dry_run, _), _) = with_event_matching(
+            connection,
+            token.emitted(transfer_event(&buyer_account, beneficiary, price)),
+            EVENT_TIMEOUT,
+            with_event_matching(
+                connection,
+                nft.emitted(nft_transfer),
+                EVENT_TIMEOUT,
+                nft.call(
+                    connection,
+                    buyer,
+                    "mint",
+                    (),
+                    Value::ok(Value::UInt(id as u128)),
+                    self.max_gas,
+                ),
+            ),
+        )
+        .await?;
+        check_deposit(connection, "mint", &contracts, before, &dry_run).await?;
+
+        nft.expect(
+            connection,
+            "owner_of",
+            id,
+            Value::some(Value::account(&buyer_account)),
+            self.max_gas,
+        )
+        .await
+    }
+}
+
+impl Preflight for ContractInteractions {
+    fn requirements(&self) -> Requirements {
+        let mut requirements = Requirements::default()
+            .call("Contracts", "upload_code")
+            .call("Contracts", "instantiate")
+            .call("Contracts", "call")
+            .pool(&self.pool, 2)
+            .ensure(!self.flows.is_empty(), "There should be some flows");
+        for name in [FLIPPER, PSP22, ERC721] {
+            requirements = requirements.ensure(
+                Artifact::exists(&self.artifacts, name),
+                format!(
+                    "Artifacts of `{}` should be in `{}` (checked in or rebuilt with `make build-contracts`)",
+                    name,
+                    self.artifacts.display()
+                ),
+            );
+        }
+        requirements
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for ContractInteractions {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let deployed = self.deployed.as_ref().ok_or(ContractsError::NotDeployed)?;
+        let accounts = connection.accounts(&self.pool)?;
+        let keypairs = accounts.random_keypairs(2)?;
+        let flow = *self
+            .flows
+            .choose(&mut thread_rng())
+            .expect("There are some flows");
+
+        let caller = account_from_keypair(&keypairs[0]);
+        logger.info(format!("{:?} by {}", flow, caller));
+        let result = match flow {
+            Flow::Flip => self.flip(connection, &deployed.flipper, &keypairs[0]).await,
+            Flow::TokenTransfer => {
+                let receiver = account_from_keypair(&keypairs[1]);
+                self.transfer_tokens(connection, &deployed.token, &keypairs[0], &receiver)
+                    .await
+            }
+            Flow::MintNft => {
+                self.mint_nft(connection, deployed, &keypairs[0], &accounts.account(0))
+                    .await
+            }
+        };
+        logger.log_result(result)?;
+        logger.info(format!("{:?} by {} confirmed", flow, caller));
+        Ok(())
+    }
+
+    /// Uploads and instantiates the contracts, and shares the token supply with the whole pool.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let accounts = connection.accounts(&self.pool)?;
+        let deployer = accounts.keypair(0);
+
+        let mut artifacts = vec![];
+        for name in [FLIPPER, PSP22, ERC721] {
+            let artifact = Artifact::load(&self.artifacts, name)?;
+            upload(connection, &deployer, &artifact).await?;
+            artifacts.push(artifact);
+        }
+
+        let flipper = instantiate(
+            connection,
+            &deployer,
+            &artifacts[0],
+            "new",
+            false,
+            self.max_gas,
+        )
+        .await?;
+        let token = deploy_token(
+            connection,
+            &accounts,
+            &artifacts[1],
+            self.token_supply as Balance,
+            self.max_gas,
+        )
+        .await?;
+        let nft = instantiate(
+            connection,
+            &deployer,
+            &artifacts[2],
+            "new",
+            (&token.address, self.nft_price as Balance),
+            self.max_gas,
+        )
+        .await?;
+        logger.info(format!(
+            "Deployed flipper at {}, token at {} and NFT at {}",
+            flipper.address, token.address, nft.address
+        ));
+
+        self.deployed = Some(Deployed {
+            flipper,
+            token,
+            nft,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use aleph_client::substrate_api_client::AccountId;
+use anyhow::Result as AnyResult;
+use thiserror::Error;
+
+use chain_support::{ChainScenarioKind, ChainScenarioRegistry};
+pub use contract::{instantiate, upload, Artifact, Contract, DryRun};
+pub use interactions::{ContractInteractions, Flow};
+pub use metadata::{ContractEvent, ContractMetadata, Value};
+pub use psp22::{Operation, Psp22Transfers};
+
+mod contract;
+mod events;
+mod interactions;
+mod metadata;
+mod psp22;
+mod storage;
+
+/// Gathers all possible errors from this crate.
+#[derive(Debug, Error)]
+pub enum ContractsError {
+    #[error("📜❌ Artifact `{0:?}` cannot be read (see `make build-contracts`).")]
+    MissingArtifact(PathBuf),
+    #[error("📜❌ There is no message or constructor `{0}` in the contract metadata.")]
+    UnknownMessage(String),
+    #[error("📜❌ Selector `{0}` is not a valid 4-byte hex string.")]
+    InvalidSelector(String),
+    #[error("📜❌ Type #{0} is not described in the contract metadata.")]
+    UnknownType(u32),
+    #[error("📜❌ Type #{ty} has no variant with index {index}.")]
+    UnknownVariant { ty: u32, index: u8 },
+    #[error("📜❌ There is no event with index {0} in the contract metadata.")]
+    UnknownEvent(u8),
+    #[error("📜❌ Unexpected response of the contracts RPC: `{0}`.")]
+    InvalidResponse(String),
+    #[error("📜❌ Dry-run of `{label}` has failed with `{error}`.")]
+    DryRunFailed { label: String, error: String },
+    #[error("📜❌ `{label}` has reverted with `{data:?}`.")]
+    Reverted { label: String, data: Vec<u8> },
+    #[error(
+        "📜❌ `{label}` has consumed {consumed} and required {required} gas, which is not within \
+        the limit of {max}."
+    )]
+    UnexpectedGas {
+        label: String,
+        consumed: u64,
+        required: u64,
+        max: u64,
+    },
+    #[error("📜❌ `{label}` returns `{found:?}` instead of `{expected:?}`.")]
+    UnexpectedReturn {
+        label: String,
+        found: Value,
+        expected: Value,
+    },
+    #[error(
+        "📜❌ After `{label}`, storage deposit of the contracts has changed by {found} instead of \
+        {expected}."
+    )]
+    UnexpectedDeposit {
+        label: String,
+        found: i128,
+        expected: i128,
+    },
+    #[error("📜❌ Token balance of `{account:?}` has changed by {found} instead of {expected}.")]
+    UnexpectedTokenBalance {
+        account: AccountId,
+        found: i128,
+        expected: i128,
+    },
+    #[error("📜❌ Contracts have not been deployed during setup.")]
+    NotDeployed,
+}
+
+/// Registers all the contract scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "ContractInteractions",
+        &[
+            "artifacts (optional): directory with the built contracts, by default \
+            `contracts/artifacts`",
+            "flows (optional): subset of `Flip`, `TokenTransfer` and `MintNft`, by default all of \
+            them",
+            "max_gas (optional): most gas a single call may require, by default 100_000_000_000",
+            "token_supply (optional): total supply of the PSP22 token, by default 1_000_000_000",
+            "nft_price (optional): price of a single NFT in the PSP22 token, by default 10",
+            "pool (optional): account pool of the callers, by default `Contracts`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<ContractInteractions>()?)),
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "Psp22Transfers",
+        &[
+            "direction: `OneToMany`, `ManyToOne` or `ManyToMany`",
+            "transfer_mode: `Sequential`, `Concurrent`, `{ WithDelay = <interval> }` or `{ Span = \
+            <interval> }`",
+            "transfers: number of transfers in every run",
+            "transfer_value: tokens (of the contract) sent in every transfer",
+            "operations (optional): subset of `Transfer`, `TransferFrom` and `IncreaseAllowance`, \
+            by default all of them",
+            "artifacts (optional): directory with the built contracts, by default \
+            `contracts/artifacts`",
+            "token_supply (optional): total supply of the token, by default 1_000_000_000",
+            "max_gas (optional): most gas a single call may require, by default 100_000_000_000",
+            "pool (optional): account pool, by default `Psp22Transfers`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Psp22Transfers>()?)),
+    ))
+}
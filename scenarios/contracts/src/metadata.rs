@@ -0,0 +1,431 @@
+//! Just enough of the ink! metadata (version 3) to encode calls to a contract and to decode what
+//! it returns and emits.
+use std::collections::HashMap;
+
+use aleph_client::substrate_api_client::AccountId;
+use anyhow::Result as AnyResult;
+use codec::{Compact, Decode, Encode};
+use hex::FromHex;
+use serde::Deserialize;
+
+use crate::ContractsError;
+
+/// A decoded SCALE value. Composites with a single unnamed field (like `AccountId`) are
+/// represented by the value of that field, and sequences or arrays of bytes by `Value::Bytes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Bool(bool),
+    UInt(u128),
+    Int(i128),
+    Str(String),
+    Bytes(Vec<u8>),
+    Seq(Vec<Value>),
+    Tuple(Vec<Value>),
+    Composite(Vec<(String, Value)>),
+    Variant(String, Vec<Value>),
+}
+
+impl Value {
+    pub fn account(account: &AccountId) -> Self {
+        Value::Bytes(account.encode())
+    }
+
+    pub fn some(value: Value) -> Self {
+        Value::Variant("Some".to_string(), vec![value])
+    }
+
+    pub fn none() -> Self {
+        Value::Variant("None".to_string(), vec![])
+    }
+
+    pub fn ok(value: Value) -> Self {
+        Value::Variant("Ok".to_string(), vec![value])
+    }
+
+    pub fn unit() -> Self {
+        Value::Tuple(vec![])
+    }
+}
+
+/// A decoded ink! event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractEvent {
+    pub label: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+impl ContractEvent {
+    pub fn new<S: Into<String>>(label: S) -> Self {
+        ContractEvent {
+            label: label.into(),
+            fields: vec![],
+        }
+    }
+
+    pub fn field<S: Into<String>>(mut self, label: S, value: Value) -> Self {
+        self.fields.push((label.into(), value));
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Primitive {
+    Bool,
+    Char,
+    Str,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    I256,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Field {
+    name: Option<String>,
+    #[serde(rename = "type")]
+    ty: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct VariantDef {
+    name: String,
+    #[serde(default)]
+    fields: Vec<Field>,
+    index: u8,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TypeDef {
+    Composite {
+        #[serde(default)]
+        fields: Vec<Field>,
+    },
+    Variant {
+        #[serde(default)]
+        variants: Vec<VariantDef>,
+    },
+    Sequence {
+        #[serde(rename = "type")]
+        ty: u32,
+    },
+    Array {
+        len: u32,
+        #[serde(rename = "type")]
+        ty: u32,
+    },
+    Tuple(Vec<u32>),
+    Primitive(Primitive),
+    Compact {
+        #[serde(rename = "type")]
+        _ty: u32,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Type {
+    def: TypeDef,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct PortableType {
+    id: u32,
+    #[serde(rename = "type")]
+    ty: Type,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct TypeSpec {
+    #[serde(rename = "type")]
+    ty: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Callable {
+    label: String,
+    selector: String,
+    #[serde(default)]
+    return_type: Option<TypeSpec>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct EventArg {
+    label: String,
+    #[serde(rename = "type")]
+    ty: TypeSpec,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct EventSpec {
+    label: String,
+    args: Vec<EventArg>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Spec {
+    constructors: Vec<Callable>,
+    messages: Vec<Callable>,
+    events: Vec<EventSpec>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct V3 {
+    spec: Spec,
+    types: Vec<PortableType>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Versioned {
+    #[serde(rename = "V3")]
+    v3: V3,
+}
+
+/// Metadata of a single contract, as generated by `cargo contract build`.
+#[derive(Clone, Debug)]
+pub struct ContractMetadata {
+    spec: Spec,
+    types: HashMap<u32, TypeDef>,
+}
+
+impl ContractMetadata {
+    pub fn from_json(json: &str) -> AnyResult<Self> {
+        let versioned: Versioned = serde_json::from_str(json)?;
+        Ok(ContractMetadata {
+            spec: versioned.v3.spec,
+            types: versioned
+                .v3
+                .types
+                .into_iter()
+                .map(|portable| (portable.id, portable.ty.def))
+                .collect(),
+        })
+    }
+
+    /// Input data calling message `label` with `args` (a tuple of all the arguments, if there is
+    /// more than one).
+    pub fn message_data<A: Encode>(&self, label: &str, args: A) -> AnyResult<Vec<u8>> {
+        let message = Self::find(&self.spec.messages, label)
+            .ok_or_else(|| ContractsError::UnknownMessage(label.to_string()))?;
+        Self::with_selector(message, args)
+    }
+
+    /// Like `ContractMetadata::message_data`, but for constructor `label`.
+    pub fn constructor_data<A: Encode>(&self, label: &str, args: A) -> AnyResult<Vec<u8>> {
+        let constructor = Self::find(&self.spec.constructors, label)
+            .ok_or_else(|| ContractsError::UnknownMessage(label.to_string()))?;
+        Self::with_selector(constructor, args)
+    }
+
+    /// Decodes what message `label` has returned.
+    pub fn decode_return(&self, label: &str, data: &[u8]) -> AnyResult<Value> {
+        let message = Self::find(&self.spec.messages, label)
+            .ok_or_else(|| ContractsError::UnknownMessage(label.to_string()))?;
+        match &message.return_type {
+            Some(spec) => self.decode(spec.ty, &mut &data[..]),
+            None => Ok(Value::unit()),
+        }
+    }
+
+    /// Decodes `data` of `ContractEmitted`: the index of the event followed by all its fields.
+    pub fn decode_event(&self, data: &[u8]) -> AnyResult<ContractEvent> {
+        let input = &mut &data[..];
+        let index = u8::decode(input)?;
+        let spec = self
+            .spec
+            .events
+            .get(index as usize)
+            .ok_or(ContractsError::UnknownEvent(index))?;
+
+        let mut event = ContractEvent::new(spec.label.clone());
+        for arg in &spec.args {
+            event = event.field(arg.label.clone(), self.decode(arg.ty.ty, input)?);
+        }
+        Ok(event)
+    }
+
+    fn find<'a>(callables: &'a [Callable], label: &str) -> Option<&'a Callable> {
+        callables.iter().find(|callable| callable.label == label)
+    }
+
+    fn with_selector<A: Encode>(callable: &Callable, args: A) -> AnyResult<Vec<u8>> {
+        let selector = <[u8; 4]>::from_hex(callable.selector.trim_start_matches("0x"))
+            .map_err(|_| ContractsError::InvalidSelector(callable.selector.clone()))?;
+        let mut data = selector.to_vec();
+        args.encode_to(&mut data);
+        Ok(data)
+    }
+
+    fn decode(&self, ty: u32, input: &mut &[u8]) -> AnyResult<Value> {
+        match self.types.get(&ty).ok_or(ContractsError::UnknownType(ty))? {
+            TypeDef::Composite { fields } => self.decode_composite(fields, input),
+            TypeDef::Variant { variants } => {
+                let index = u8::decode(input)?;
+                let variant = variants
+                    .iter()
+                    .find(|variant| variant.index == index)
+                    .ok_or(ContractsError::UnknownVariant { ty, index })?;
+                let values = variant
+                    .fields
+                    .iter()
+                    .map(|field| self.decode(field.ty, input))
+                    .collect::<AnyResult<_>>()?;
+                Ok(Value::Variant(variant.name.clone(), values))
+            }
+            TypeDef::Sequence { ty } => {
+                let len = Compact::<u32>::decode(input)?.0;
+                self.decode_many(*ty, len, input)
+            }
+            TypeDef::Array { len, ty } => self.decode_many(*ty, *len, input),
+            TypeDef::Tuple(types) => Ok(Value::Tuple(
+                types
+                    .iter()
+                    .map(|ty| self.decode(*ty, input))
+                    .collect::<AnyResult<_>>()?,
+            )),
+            TypeDef::Primitive(primitive) => Self::decode_primitive(*primitive, input),
+            TypeDef::Compact { .. } => Ok(Value::UInt(Compact::<u128>::decode(input)?.0)),
+        }
+    }
+
+    fn decode_composite(&self, fields: &[Field], input: &mut &[u8]) -> AnyResult<Value> {
+        if fields.len() == 1 && fields[0].name.is_none() {
+            return self.decode(fields[0].ty, input);
+        }
+
+        let mut named = vec![];
+        let mut unnamed = vec![];
+        for field in fields {
+            let value = self.decode(field.ty, input)?;
+            match &field.name {
+                Some(name) => named.push((name.clone(), value)),
+                None => unnamed.push(value),
+            }
+        }
+        if unnamed.is_empty() {
+            Ok(Value::Composite(named))
+        } else {
+            Ok(Value::Tuple(unnamed))
+        }
+    }
+
+    fn decode_many(&self, ty: u32, len: u32, input: &mut &[u8]) -> AnyResult<Value> {
+        if matches!(self.types.get(&ty), Some(TypeDef::Primitive(Primitive::U8))) {
+            let mut bytes = vec![0u8; len as usize];
+            codec::Input::read(input, &mut bytes)?;
+            return Ok(Value::Bytes(bytes));
+        }
+        Ok(Value::Seq(
+            (0..len)
+                .map(|_| self.decode(ty, input))
+                .collect::<AnyResult<_>>()?,
+        ))
+    }
+
+    fn decode_primitive(primitive: Primitive, input: &mut &[u8]) -> AnyResult<Value> {
+        Ok(match primitive {
+            Primitive::Bool => Value::Bool(bool::decode(input)?),
+            Primitive::Char => Value::UInt(u32::decode(input)? as u128),
+            Primitive::Str => Value::Str(String::decode(input)?),
+            Primitive::U8 => Value::UInt(u8::decode(input)? as u128),
+            Primitive::U16 => Value::UInt(u16::decode(input)? as u128),
+            Primitive::U32 => Value::UInt(u32::decode(input)? as u128),
+            Primitive::U64 => Value::UInt(u64::decode(input)? as u128),
+            Primitive::U128 => Value::UInt(u128::decode(input)?),
+            Primitive::I8 => Value::Int(i8::decode(input)? as i128),
+            Primitive::I16 => Value::Int(i16::decode(input)? as i128),
+            Primitive::I32 => Value::Int(i32::decode(input)? as i128),
+            Primitive::I64 => Value::Int(i64::decode(input)? as i128),
+            Primitive::I128 => Value::Int(i128::decode(input)?),
+            Primitive::U256 | Primitive::I256 => Value::Bytes(<[u8; 32]>::decode(input)?.to_vec()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aleph_client::substrate_api_client::AccountId;
+    use codec::Encode;
+
+    use crate::metadata::{ContractEvent, ContractMetadata, Value};
+
+    /// A trimmed version of what `cargo contract` generates for a PSP22 token.
+    const METADATA: &str = r#"{
+        "V3": {
+            "spec": {
+                "constructors": [
+                    { "args": [], "label": "new", "selector": "0x9bae9d5e" }
+                ],
+                "messages": [
+                    { "args": [], "label": "balance_of", "selector": "0x6568382f",
+                      "returnType": { "displayName": ["Balance"], "type": 3 } },
+                    { "args": [], "label": "transfer", "selector": "0xdb20f9f5",
+                      "returnType": { "displayName": ["Result"], "type": 5 } }
+                ],
+                "events": [
+                    { "label": "Transfer", "args": [
+                        { "label": "from", "type": { "type": 4 } },
+                        { "label": "to", "type": { "type": 4 } },
+                        { "label": "value", "type": { "type": 3 } }
+                    ] }
+                ]
+            },
+            "types": [
+                { "id": 0, "type": { "def": { "composite": { "fields": [
+                    { "type": 1, "typeName": "[u8; 32]" }
+                ] } }, "path": ["ink_env", "types", "AccountId"] } },
+                { "id": 1, "type": { "def": { "array": { "len": 32, "type": 2 } } } },
+                { "id": 2, "type": { "def": { "primitive": "u8" } } },
+                { "id": 3, "type": { "def": { "primitive": "u128" } } },
+                { "id": 4, "type": { "def": { "variant": { "variants": [
+                    { "index": 0, "name": "None" },
+                    { "fields": [{ "type": 0 }], "index": 1, "name": "Some" }
+                ] } } } },
+                { "id": 5, "type": { "def": { "variant": { "variants": [
+                    { "fields": [{ "type": 6 }], "index": 0, "name": "Ok" },
+                    { "fields": [{ "type": 3 }], "index": 1, "name": "Err" }
+                ] } } } },
+                { "id": 6, "type": { "def": { "tuple": [] } } }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn calls_are_encoded_and_results_decoded() {
+        let metadata = ContractMetadata::from_json(METADATA).expect("Metadata is valid");
+        let account = AccountId::from([7u8; 32]);
+
+        assert_eq!(
+            metadata
+                .message_data("balance_of", &account)
+                .expect("Message exists"),
+            [&[0x65, 0x68, 0x38, 0x2f][..], &[7u8; 32][..]].concat()
+        );
+        assert!(metadata.message_data("burn", ()).is_err());
+
+        assert_eq!(
+            metadata
+                .decode_return("transfer", &Ok::<(), u128>(()).encode())
+                .expect("Return value is valid"),
+            Value::ok(Value::unit())
+        );
+
+        let data = (0u8, None::<AccountId>, Some(account.clone()), 10u128).encode();
+        assert_eq!(
+            metadata.decode_event(&data).expect("Event is valid"),
+            ContractEvent::new("Transfer")
+                .field("from", Value::none())
+                .field("to", Value::some(Value::account(&account)))
+                .field("value", Value::UInt(10))
+        );
+    }
+}
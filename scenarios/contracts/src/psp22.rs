@@ -0,0 +1,540 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter::repeat,
+    path::PathBuf,
+    time::Duration,
+};
+
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{AccountId, Balance},
+    KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use codec::Encode;
+use futures::future::join_all;
+use rand::{
+    prelude::{IteratorRandom, SliceRandom},
+    thread_rng,
+};
+use serde::Deserialize;
+use tokio::time::sleep;
+
+use chain_support::{
+    with_event_matching, Accounts, NodeConnection, Preflight, Requirements, EVENT_TIMEOUT,
+};
+use common::{Scenario, ScenarioError, ScenarioLogging};
+use scenarios_transfer::{get_random_delays, Direction, TransferMode, MILLIS_PER_TRANSACTION};
+
+use crate::{
+    contract::{instantiate, upload, Artifact, Contract},
+    interactions::{default_artifacts, default_max_gas, default_token_supply, PSP22},
+    metadata::{ContractEvent, Value},
+    ContractsError,
+};
+
+chain_support::default_pool!("Psp22Transfers");
+
+fn all_operations() -> Vec<Operation> {
+    vec![
+        Operation::Transfer,
+        Operation::TransferFrom,
+        Operation::IncreaseAllowance,
+    ]
+}
+
+/// `Transfer` event of a PSP22 token.
+pub(crate) fn transfer_event(from: &AccountId, to: &AccountId, value: Balance) -> ContractEvent {
+    ContractEvent::new("Transfer")
+        .field("from", Value::some(Value::account(from)))
+        .field("to", Value::some(Value::account(to)))
+        .field("value", Value::UInt(value))
+}
+
+/// `Approval` event of a PSP22 token.
+fn approval_event(owner: &AccountId, spender: &AccountId, value: Balance) -> ContractEvent {
+    ContractEvent::new("Approval")
+        .field("owner", Value::account(owner))
+        .field("spender", Value::account(spender))
+        .field("value", Value::UInt(value))
+}
+
+/// Reads (with a dry-run) what message `label` of `token` returns, expecting a balance.
+async fn query_balance<A: Encode>(
+    connection: &NodeConnection,
+    token: &Contract,
+    label: &str,
+    args: A,
+    max_gas: u64,
+) -> AnyResult<Balance> {
+    match token.query(connection, label, args, max_gas).await? {
+        Value::UInt(balance) => Ok(balance),
+        found => Err(ContractsError::UnexpectedReturn {
+            label: label.to_string(),
+            found,
+            expected: Value::UInt(0),
+        }
+        .into()),
+    }
+}
+
+/// Balance of `owner` in `token`.
+pub(crate) async fn balance_of(
+    connection: &NodeConnection,
+    token: &Contract,
+    owner: &AccountId,
+    max_gas: u64,
+) -> AnyResult<Balance> {
+    query_balance(connection, token, "balance_of", owner, max_gas).await
+}
+
+/// Instantiates the (already uploaded) PSP22 token from `artifact` by the first account of
+/// `accounts`, who then shares `supply` equally with the rest of them.
+pub(crate) async fn deploy_token(
+    connection: &NodeConnection,
+    accounts: &Accounts<'_>,
+    artifact: &Artifact,
+    supply: Balance,
+    max_gas: u64,
+) -> AnyResult<Contract> {
+    let deployer = accounts.keypair(0);
+    let token = instantiate(connection, &deployer, artifact, "new", supply, max_gas).await?;
+
+    let share = supply / accounts.size() as Balance;
+    for receiver in accounts.accounts().iter().skip(1) {
+        token
+            .call(
+                connection,
+                &deployer,
+                "transfer",
+                (receiver, share, Vec::<u8>::new()),
+                Value::ok(Value::unit()),
+                max_gas,
+            )
+            .await?;
+    }
+    Ok(token)
+}
+
+/// How tokens are moved from the sender to the receiver.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Operation {
+    /// `transfer` by the sender.
+    Transfer,
+    /// `approve` of the receiver by the sender, followed by `transfer_from` by the receiver.
+    TransferFrom,
+    /// `increase_allowance` of the receiver by the sender, followed by `transfer_from` by the
+    /// receiver.
+    IncreaseAllowance,
+}
+
+/// Represents a single sender-receiver pair, together with the operation.
+struct TokenTransfer {
+    sender: KeyPair,
+    sender_id: usize,
+    receiver: KeyPair,
+    receiver_id: usize,
+    operation: Operation,
+}
+
+/// Scenario making traffic through random PSP22 transfers within the account pool, like
+/// `RandomTransfers` does it with the native token.
+///
+/// During setup, the token is uploaded (unless the code is already on chain) and instantiated by
+/// the first account of the pool, which then shares `token_supply` with the rest of the pool.
+///
+/// In every run, `transfers` (sender, receiver) pairs are chosen according to `direction` and
+/// every sender moves `transfer_value` tokens to their receiver with one of `operations` (chosen
+/// randomly). Every call must emit the expected `Transfer` (or `Approval`) event and allowances
+/// are checked after they are spent. After all the transfers, balances of the involved accounts
+/// are read with `balance_of` dry-runs and compared with the ones from before the run.
+///
+/// Depending on `transfer_mode`, transfers are submitted sequentially or concurrently. Contract
+/// calls are signed by different accounts, so they cannot be batched.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Psp22Transfers {
+    /// Directory with `psp22.wasm` and `psp22.json` files. By default, `contracts/artifacts`, where
+    /// they are checked in.
+    #[serde(default = "default_artifacts")]
+    artifacts: PathBuf,
+    /// Account pool within which transfers are made. By default, `Psp22Transfers`.
+    #[serde(default = "default_pool")]
+    pool: String,
+    /// What type of traffic should be made.
+    direction: Direction,
+    /// How to submit extrinsics.
+    transfer_mode: TransferMode,
+    /// How many transfers should be performed during a single run.
+    transfers: usize,
+    /// How many tokens (of the contract) should be transferred in a single transfer.
+    transfer_value: u64,
+    /// Operations chosen from. By default, all of them.
+    #[serde(default = "all_operations")]
+    operations: Vec<Operation>,
+    /// Total supply of the token.
+    #[serde(default = "default_token_supply")]
+    token_supply: u64,
+    /// Most gas a single call may require.
+    #[serde(default = "default_max_gas")]
+    max_gas: u64,
+    #[serde(skip)]
+    token: Option<Contract>,
+}
+
+impl Psp22Transfers {
+    /// Returns a vector of `self.transfers` random (sender, receiver) pairs corresponding
+    /// to `self.direction`, each with a random operation.
+    fn designate_transfers(&self, accounts: &Accounts) -> Vec<TokenTransfer> {
+        let mut generator = thread_rng();
+        self.direction
+            .pairs(accounts.size())
+            .choose_multiple(&mut generator, self.transfers)
+            .into_iter()
+            .map(|(s, r)| TokenTransfer {
+                sender: accounts.keypair(s),
+                sender_id: s,
+                receiver: accounts.keypair(r),
+                receiver_id: r,
+                operation: *self
+                    .operations
+                    .choose(&mut generator)
+                    .expect("There are some operations"),
+            })
+            .collect()
+    }
+
+    /// `transfer_from` of `self.transfer_value` by `spender` (to themselves), after which
+    /// `remaining` allowance should be left.
+    async fn spend_allowance(
+        &self,
+        connection: &NodeConnection,
+        token: &Contract,
+        owner: &AccountId,
+        spender: &KeyPair,
+        remaining: Balance,
+    ) -> AnyResult<()> {
+        let value = self.transfer_value as Balance;
+        let spender_account = account_from_keypair(spender);
+        with_event_matching(
+            connection,
+            token.emitted(transfer_event(owner, &spender_account, value)),
+            EVENT_TIMEOUT,
+            token.call(
+                connection,
+                spender,
+                "transfer_from",
+                (owner, &spender_account, value, Vec::<u8>::new()),
+                Value::ok(Value::unit()),
+                self.max_gas,
+            ),
+        )
+        .await?;
+        token
+            .expect(
+                connection,
+                "allowance",
+                (owner, &spender_account),
+                Value::UInt(remaining),
+                self.max_gas,
+            )
+            .await
+    }
+
+    async fn send_transfer(
+        &self,
+        connection: &NodeConnection,
+        token: &Contract,
+        transfer: &TokenTransfer,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        logger.debug(format!(
+            "{:?} of tokens from #{} to #{}.",
+            transfer.operation, transfer.sender_id, transfer.receiver_id
+        ));
+
+        let value = self.transfer_value as Balance;
+        let sender = account_from_keypair(&transfer.sender);
+        let receiver = account_from_keypair(&transfer.receiver);
+        match transfer.operation {
+            Operation::Transfer => {
+                with_event_matching(
+                    connection,
+                    token.emitted(transfer_event(&sender, &receiver, value)),
+                    EVENT_TIMEOUT,
+                    token.call(
+                        connection,
+                        &transfer.sender,
+                        "transfer",
+                        (&receiver, value, Vec::<u8>::new()),
+                        Value::ok(Value::unit()),
+                        self.max_gas,
+                    ),
+                )
+                .await?;
+                Ok(())
+            }
+            Operation::TransferFrom => {
+                with_event_matching(
+                    connection,
+                    token.emitted(approval_event(&sender, &receiver, value)),
+                    EVENT_TIMEOUT,
+                    token.call(
+                        connection,
+                        &transfer.sender,
+                        "approve",
+                        (&receiver, value),
+                        Value::ok(Value::unit()),
+                        self.max_gas,
+                    ),
+                )
+                .await?;
+                self.spend_allowance(connection, token, &sender, &transfer.receiver, 0)
+                    .await
+            }
+            Operation::IncreaseAllowance => {
+                let before = query_balance(
+                    connection,
+                    token,
+                    "allowance",
+                    (&sender, &receiver),
+                    self.max_gas,
+                )
+                .await?;
+                with_event_matching(
+                    connection,
+                    token.emitted(approval_event(&sender, &receiver, before + value)),
+                    EVENT_TIMEOUT,
+                    token.call(
+                        connection,
+                        &transfer.sender,
+                        "increase_allowance",
+                        (&receiver, value),
+                        Value::ok(Value::unit()),
+                        self.max_gas,
+                    ),
+                )
+                .await?;
+                self.spend_allowance(connection, token, &sender, &transfer.receiver, before)
+                    .await
+            }
+        }
+    }
+
+    async fn send_concurrently(
+        &self,
+        connection: &NodeConnection,
+        token: &Contract,
+        transfers: &[TokenTransfer],
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let results = join_all(
+            transfers
+                .iter()
+                .map(|transfer| self.send_transfer(connection, token, transfer, logger)),
+        )
+        .await;
+
+        for result in results {
+            logger.log_result(result)?;
+        }
+        Ok(())
+    }
+
+    async fn send_with_delays<I: IntoIterator<Item = Duration>>(
+        &self,
+        connection: &NodeConnection,
+        token: &Contract,
+        transfers: &[TokenTransfer],
+        delays: I,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        for (idx, (transfer, delay)) in transfers.iter().zip(delays).enumerate() {
+            let result = self
+                .send_transfer(connection, token, transfer, logger)
+                .await;
+            logger.log_result(result)?;
+
+            logger.debug(format!(
+                "Completed {}/{} transfers. Waiting {}ms until next transfer",
+                idx + 1,
+                transfers.len(),
+                delay.as_millis()
+            ));
+            sleep(delay).await;
+        }
+        Ok(())
+    }
+
+    async fn send_within_span(
+        &self,
+        span: Duration,
+        connection: &NodeConnection,
+        token: &Contract,
+        transfers: &[TokenTransfer],
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let time_needed_to_send_all = MILLIS_PER_TRANSACTION * transfers.len() as u128;
+        if span.as_millis() < time_needed_to_send_all {
+            return Err(ScenarioError::BadConfig.into());
+        }
+
+        let idle_time = span.as_millis() - time_needed_to_send_all;
+        let delays = get_random_delays(idle_time, transfers.len())
+            .into_iter()
+            .map(|d| Duration::from_millis(d as u64));
+        self.send_with_delays(connection, token, transfers, delays, logger)
+            .await
+    }
+
+    /// Token balances of the accounts of the pool with `ids`.
+    async fn balances(
+        &self,
+        connection: &NodeConnection,
+        token: &Contract,
+        accounts: &Accounts<'_>,
+        ids: &BTreeSet<usize>,
+    ) -> AnyResult<BTreeMap<usize, Balance>> {
+        let mut balances = BTreeMap::new();
+        for id in ids {
+            let balance =
+                balance_of(connection, token, &accounts.account(*id), self.max_gas).await?;
+            balances.insert(*id, balance);
+        }
+        Ok(balances)
+    }
+}
+
+impl Preflight for Psp22Transfers {
+    fn requirements(&self) -> Requirements {
+        // With a single sender (or receiver), every transfer needs a distinct counterpart.
+        let pool_size = match self.direction {
+            Direction::OneToMany | Direction::ManyToOne => self.transfers + 1,
+            Direction::ManyToMany => 2,
+        };
+        let requirements = Requirements::default()
+            .call("Contracts", "upload_code")
+            .call("Contracts", "instantiate")
+            .call("Contracts", "call")
+            .pool(&self.pool, pool_size)
+            .ensure(self.transfers > 0, "At least 1 transfer is needed")
+            .ensure(
+                !self.operations.is_empty(),
+                "There should be some operations",
+            )
+            .ensure(
+                !matches!(self.transfer_mode, TransferMode::Batched),
+                "Contract calls cannot be batched",
+            )
+            .ensure(
+                Artifact::exists(&self.artifacts, PSP22),
+                format!(
+                    "Artifacts of `{}` should be in `{}` (checked in or rebuilt with `make build-contracts`)",
+                    PSP22,
+                    self.artifacts.display()
+                ),
+            );
+
+        match self.transfer_mode {
+            TransferMode::Span(span) => requirements.ensure(
+                span.as_millis() >= MILLIS_PER_TRANSACTION * self.transfers as u128,
+                format!(
+                    "Span of {}ms is too short for {} transfers ({}ms each)",
+                    span.as_millis(),
+                    self.transfers,
+                    MILLIS_PER_TRANSACTION
+                ),
+            ),
+            _ => requirements,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Psp22Transfers {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let token = self.token.as_ref().ok_or(ContractsError::NotDeployed)?;
+        let accounts = connection.accounts(&self.pool)?;
+        accounts.ensure_size(2)?;
+        let transfers = self.designate_transfers(&accounts);
+
+        let ids = transfers
+            .iter()
+            .flat_map(|transfer| [transfer.sender_id, transfer.receiver_id])
+            .collect::<BTreeSet<_>>();
+        let before = self.balances(connection, token, &accounts, &ids).await?;
+
+        match self.transfer_mode {
+            TransferMode::Sequential => {
+                self.send_with_delays(
+                    connection,
+                    token,
+                    &transfers,
+                    repeat(Duration::ZERO),
+                    logger,
+                )
+                .await
+            }
+            TransferMode::Concurrent => {
+                self.send_concurrently(connection, token, &transfers, logger)
+                    .await
+            }
+            TransferMode::WithDelay(delay) => {
+                self.send_with_delays(connection, token, &transfers, repeat(delay), logger)
+                    .await
+            }
+            TransferMode::Span(span) => {
+                self.send_within_span(span, connection, token, &transfers, logger)
+                    .await
+            }
+            TransferMode::Batched => Err(ScenarioError::BadConfig.into()),
+        }?;
+
+        let mut expected = BTreeMap::<usize, i128>::new();
+        for transfer in &transfers {
+            *expected.entry(transfer.sender_id).or_default() -= self.transfer_value as i128;
+            *expected.entry(transfer.receiver_id).or_default() += self.transfer_value as i128;
+        }
+        let after = self.balances(connection, token, &accounts, &ids).await?;
+        for (id, expected) in expected {
+            let found = after[&id] as i128 - before[&id] as i128;
+            ensure!(
+                found == expected,
+                ContractsError::UnexpectedTokenBalance {
+                    account: accounts.account(id),
+                    found,
+                    expected,
+                }
+            );
+        }
+
+        logger.info(format!("Completed {} token transfers", transfers.len()));
+        Ok(())
+    }
+
+    /// Uploads and instantiates the token, and shares its supply with the whole pool.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let accounts = connection.accounts(&self.pool)?;
+        let artifact = Artifact::load(&self.artifacts, PSP22)?;
+        upload(connection, &accounts.keypair(0), &artifact).await?;
+        let token = deploy_token(
+            connection,
+            &accounts,
+            &artifact,
+            self.token_supply as Balance,
+            self.max_gas,
+        )
+        .await?;
+        logger.info(format!("Deployed token at {}", token.address));
+
+        self.token = Some(token);
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+//! Here we read the storage of `pallet_contracts` (and balances of contract accounts), so that we
+//! can check the effects of the calls.
+use aleph_client::substrate_api_client::{AccountId, Balance, Hash};
+use anyhow::Result as AnyResult;
+
+use chain_support::NodeConnection;
+
+/// Whether the code with `code_hash` has already been uploaded.
+pub async fn code_exists(connection: &NodeConnection, code_hash: Hash) -> AnyResult<bool> {
+    connection
+        .read_blocking(move |api| {
+            Ok(api
+                .get_storage_map::<Hash, Vec<u8>>("Contracts", "PristineCode", code_hash, None)?
+                .is_some())
+        })
+        .await
+}
+
+/// Reserved balance of `who`. For a contract, this is where its storage deposit is kept.
+pub async fn reserved_balance(connection: &NodeConnection, who: &AccountId) -> AnyResult<Balance> {
+    let who = who.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_account_data(&who)?.map_or(0, |data| data.reserved)))
+        .await
+}
@@ -0,0 +1,56 @@
+//! Here we have 'copies' of the events from `pallet_identity`, since we need `Event` trait
+//! implemented for them.
+use aleph_client::substrate_api_client::{AccountId, Balance};
+use codec::Decode;
+
+use chain_support::Event;
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Identity"]
+pub struct IdentitySet {
+    pub who: AccountId,
+}
+
+/// Emitted by `clear_identity`, with the whole released deposit (including the one for subs).
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Identity"]
+pub struct IdentityCleared {
+    pub who: AccountId,
+    pub deposit: Balance,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Identity"]
+pub struct JudgementRequested {
+    pub who: AccountId,
+    pub registrar_index: u32,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Identity"]
+pub struct JudgementGiven {
+    pub target: AccountId,
+    pub registrar_index: u32,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Identity"]
+pub struct RegistrarAdded {
+    pub registrar_index: u32,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Identity"]
+pub struct SubIdentityAdded {
+    pub sub: AccountId,
+    pub main: AccountId,
+    pub deposit: Balance,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Identity"]
+pub struct SubIdentityRemoved {
+    pub sub: AccountId,
+    pub main: AccountId,
+    pub deposit: Balance,
+}
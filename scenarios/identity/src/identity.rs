@@ -0,0 +1,646 @@
+use aleph_client::{
+    account_from_keypair, keypair_from_string,
+    substrate_api_client::{compose_call, AccountId, Balance, GenericAddress, XtStatus},
+    AnyConnection, KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use codec::Compact;
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use serde::Deserialize;
+
+use chain_support::{
+    send_managed_xt, send_with_event, NodeConnection, Preflight, Requirements, SecretSource,
+};
+use common::{Scenario, ScenarioLogging};
+
+use crate::{
+    events::{
+        IdentityCleared, IdentitySet, JudgementGiven, JudgementRequested, RegistrarAdded,
+        SubIdentityAdded, SubIdentityRemoved,
+    },
+    storage::{
+        identity, registrars, reserved_balance, subs, super_of, Data, IdentityInfo, Judgement,
+    },
+    IdentityError,
+};
+
+/// How many additional fields (at most) are set. Well below `MaxAdditionalFields` of any sensible
+/// runtime.
+const MAX_ADDITIONAL_FIELDS: usize = 2;
+
+/// How many sub-accounts (at most) every identity has.
+const MAX_SUBS: usize = 3;
+
+/// Judgements given by the registrar. `Erroneous` is missing, as it cannot be requested again.
+const JUDGEMENTS: [Judgement; 4] = [
+    Judgement::Reasonable,
+    Judgement::KnownGood,
+    Judgement::OutOfDate,
+    Judgement::LowQuality,
+];
+
+chain_support::default_pool!("Identity");
+
+chain_support::default_pool!(fn default_registrar_pool = "IdentityRegistrar");
+
+/// A single step of the identity lifecycle.
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    SetIdentity,
+    ClearIdentity,
+    RequestJudgement,
+    ProvideJudgement,
+    AddSub,
+    RenameSub,
+    RemoveSub,
+}
+
+/// Deposits of `pallet_identity` (read from its constants).
+#[derive(Clone, Copy, Debug, Default)]
+struct Deposits {
+    basic: Balance,
+    field: Balance,
+    sub_account: Balance,
+}
+
+/// Raw data made of `prefix` and a random number.
+fn random_data(rng: &mut impl Rng, prefix: &str) -> Data {
+    Data::Raw(format!("{}{}", prefix, rng.gen::<u32>()).into_bytes())
+}
+
+/// Like `random_data`, but only in half of the cases.
+fn maybe_random_data(rng: &mut impl Rng, prefix: &str) -> Data {
+    if rng.gen_bool(0.5) {
+        random_data(rng, prefix)
+    } else {
+        Data::None
+    }
+}
+
+/// Identity with a random display name, some of the other fields set randomly and a few
+/// additional fields.
+fn random_info() -> IdentityInfo {
+    let mut rng = thread_rng();
+    let additional = (0..rng.gen_range(0..=MAX_ADDITIONAL_FIELDS))
+        .map(|i| {
+            (
+                Data::Raw(format!("key{}", i).into_bytes()),
+                random_data(&mut rng, "value-"),
+            )
+        })
+        .collect();
+    IdentityInfo {
+        additional,
+        display: random_data(&mut rng, "bot-"),
+        legal: maybe_random_data(&mut rng, "Bot "),
+        web: maybe_random_data(&mut rng, "https://bot.example/"),
+        riot: maybe_random_data(&mut rng, "@bot:"),
+        email: maybe_random_data(&mut rng, "bot@"),
+        pgp_fingerprint: rng.gen_bool(0.5).then(|| rng.gen()),
+        image: Data::None,
+        twitter: maybe_random_data(&mut rng, "@bot"),
+    }
+}
+
+/// Checks that reserved balance of `account` has changed by `expected` after `step`.
+fn check_reserve(
+    step: &'static str,
+    account: &AccountId,
+    before: Balance,
+    after: Balance,
+    expected: i128,
+) -> AnyResult<()> {
+    let found = after as i128 - before as i128;
+    ensure!(
+        found == expected,
+        IdentityError::UnexpectedReserve {
+            step,
+            account: account.clone(),
+            found,
+            expected,
+        }
+    );
+    Ok(())
+}
+
+/// Checks that deposit reported by `event` is as `expected`.
+fn check_event_deposit(event: &'static str, found: Balance, expected: Balance) -> AnyResult<()> {
+    ensure!(
+        found == expected,
+        IdentityError::UnexpectedEventDeposit {
+            event,
+            found,
+            expected,
+        }
+    );
+    Ok(())
+}
+
+/// Reads `Identity` constant `constant`.
+fn constant<C: AnyConnection>(connection: &C, constant: &'static str) -> AnyResult<Balance> {
+    connection
+        .as_connection()
+        .get_constant::<Balance>("Identity", constant)
+        .map_err(|_| IdentityError::MissingConstant(constant).into())
+}
+
+/// Index of the registrar `account`, if it is one.
+async fn registrar_index(
+    connection: &NodeConnection,
+    account: &AccountId,
+) -> AnyResult<Option<u32>> {
+    Ok(registrars(connection)
+        .await?
+        .iter()
+        .position(|registrar| {
+            registrar
+                .as_ref()
+                .map_or(false, |registrar| registrar.account == *account)
+        })
+        .map(|index| index as u32))
+}
+
+/// A scenario that goes through the whole lifecycle of an identity.
+///
+/// Every run takes a random account of the pool and moves it one step further: an account
+/// without identity sets a random one, an account with a pending judgement request gets the
+/// judgement from the registrar, and any other account either requests a judgement, clears its
+/// identity or adds, renames or removes (random) sub-accounts.
+///
+/// Every step is confirmed by the corresponding `Identity` event (if there is any), by reading the
+/// identity (or the sub-account) afterwards and by checking that exactly the expected deposit has
+/// been reserved or released.
+///
+/// The registrar is the first account of `registrar_pool`. If it is not a registrar yet, it is
+/// added during setup with `Sudo::sudo`, which requires `sudo`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Identity {
+    /// Where the seed phrase of the sudo account is kept. Needed only if the registrar has not
+    /// been added yet.
+    #[serde(default)]
+    sudo: Option<SecretSource>,
+    /// Account pool of the identities. By default, `Identity`.
+    #[serde(default = "default_pool")]
+    pool: String,
+    /// Account pool whose first account is the registrar. By default, `IdentityRegistrar`.
+    #[serde(default = "default_registrar_pool")]
+    registrar_pool: String,
+    /// Index of the registrar (known after setup).
+    #[serde(skip)]
+    registrar_index: u32,
+    /// Deposits of the pallet (known after setup).
+    #[serde(skip)]
+    deposits: Deposits,
+}
+
+impl Identity {
+    fn registrar(&self, connection: &NodeConnection) -> AnyResult<KeyPair> {
+        Ok(connection.accounts(&self.registrar_pool)?.keypair(0))
+    }
+
+    /// Chooses the next step for `account`, basing on its identity and sub-accounts.
+    async fn next_step(&self, connection: &NodeConnection, account: &AccountId) -> AnyResult<Step> {
+        let registration = match identity(connection, account).await? {
+            Some(registration) => registration,
+            None => return Ok(Step::SetIdentity),
+        };
+        if let Some(Judgement::FeePaid(_)) = registration.judgement(self.registrar_index) {
+            return Ok(Step::ProvideJudgement);
+        }
+
+        let (_, subs) = subs(connection, account).await?;
+        let mut steps = vec![Step::RequestJudgement, Step::ClearIdentity];
+        if subs.len() < MAX_SUBS {
+            steps.push(Step::AddSub);
+        }
+        if !subs.is_empty() {
+            steps.extend([Step::RenameSub, Step::RemoveSub]);
+        }
+        Ok(*steps
+            .choose(&mut thread_rng())
+            .expect("There are always some steps"))
+    }
+
+    async fn set_identity(&self, connection: &NodeConnection, who: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(who);
+        let info = random_info();
+        let before = reserved_balance(connection, &account).await?;
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Identity",
+            "set_identity",
+            info.clone()
+        );
+        let who_account = account.clone();
+        send_with_event(
+            connection,
+            who,
+            call,
+            "Identity::set_identity",
+            move |event: &IdentitySet| event.who == who_account,
+        )
+        .await?;
+
+        let registration = identity(connection, &account)
+            .await?
+            .ok_or_else(|| IdentityError::NoIdentity(account.clone()))?;
+        ensure!(
+            registration.info == info,
+            IdentityError::UnexpectedIdentity(account)
+        );
+        let deposit = self.deposits.basic + self.deposits.field * info.additional.len() as Balance;
+        check_reserve(
+            "set_identity",
+            &account,
+            before,
+            reserved_balance(connection, &account).await?,
+            deposit as i128,
+        )
+    }
+
+    async fn clear_identity(&self, connection: &NodeConnection, who: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(who);
+        let registration = identity(connection, &account)
+            .await?
+            .ok_or_else(|| IdentityError::NoIdentity(account.clone()))?;
+        let (subs_deposit, _) = subs(connection, &account).await?;
+        let deposit = registration.deposit + subs_deposit;
+        let before = reserved_balance(connection, &account).await?;
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Identity",
+            "clear_identity"
+        );
+        let who_account = account.clone();
+        let (_, event) = send_with_event(
+            connection,
+            who,
+            call,
+            "Identity::clear_identity",
+            move |event: &IdentityCleared| event.who == who_account,
+        )
+        .await?;
+
+        check_event_deposit("IdentityCleared", event.deposit, deposit)?;
+        ensure!(
+            identity(connection, &account).await?.is_none(),
+            IdentityError::IdentityNotCleared(account)
+        );
+        check_reserve(
+            "clear_identity",
+            &account,
+            before,
+            reserved_balance(connection, &account).await?,
+            -(deposit as i128),
+        )
+    }
+
+    async fn request_judgement(&self, connection: &NodeConnection, who: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(who);
+        let registrar_index = self.registrar_index;
+        let fee = registrars(connection)
+            .await?
+            .get(registrar_index as usize)
+            .cloned()
+            .flatten()
+            .map_or(0, |registrar| registrar.fee);
+        let before = reserved_balance(connection, &account).await?;
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Identity",
+            "request_judgement",
+            Compact(registrar_index),
+            Compact(fee)
+        );
+        let who_account = account.clone();
+        send_with_event(
+            connection,
+            who,
+            call,
+            "Identity::request_judgement",
+            move |event: &JudgementRequested| {
+                event.who == who_account && event.registrar_index == registrar_index
+            },
+        )
+        .await?;
+
+        self.check_judgement(connection, &account, Judgement::FeePaid(fee))
+            .await?;
+        check_reserve(
+            "request_judgement",
+            &account,
+            before,
+            reserved_balance(connection, &account).await?,
+            fee as i128,
+        )
+    }
+
+    async fn provide_judgement(
+        &self,
+        connection: &NodeConnection,
+        target: &AccountId,
+    ) -> AnyResult<()> {
+        let registrar_index = self.registrar_index;
+        let fee = match identity(connection, target)
+            .await?
+            .and_then(|registration| registration.judgement(registrar_index))
+        {
+            Some(Judgement::FeePaid(fee)) => fee,
+            _ => 0,
+        };
+        let judgement = *JUDGEMENTS
+            .choose(&mut thread_rng())
+            .expect("There are some judgements");
+        let before = reserved_balance(connection, target).await?;
+
+        let registrar = self.registrar(connection)?;
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Identity",
+            "provide_judgement",
+            Compact(registrar_index),
+            GenericAddress::Id(target.clone()),
+            judgement
+        );
+        let target_account = target.clone();
+        send_with_event(
+            connection,
+            &registrar,
+            call,
+            "Identity::provide_judgement",
+            move |event: &JudgementGiven| {
+                event.target == target_account && event.registrar_index == registrar_index
+            },
+        )
+        .await?;
+
+        self.check_judgement(connection, target, judgement).await?;
+        // The fee goes to the registrar.
+        check_reserve(
+            "provide_judgement",
+            target,
+            before,
+            reserved_balance(connection, target).await?,
+            -(fee as i128),
+        )
+    }
+
+    async fn check_judgement(
+        &self,
+        connection: &NodeConnection,
+        account: &AccountId,
+        expected: Judgement,
+    ) -> AnyResult<()> {
+        let found = identity(connection, account)
+            .await?
+            .and_then(|registration| registration.judgement(self.registrar_index));
+        ensure!(
+            found == Some(expected),
+            IdentityError::UnexpectedJudgement {
+                account: account.clone(),
+                found,
+                expected,
+            }
+        );
+        Ok(())
+    }
+
+    /// Checks that `sub` is a sub-account of `main` named `name`.
+    async fn check_sub(
+        connection: &NodeConnection,
+        sub: &AccountId,
+        main: &AccountId,
+        name: Data,
+    ) -> AnyResult<()> {
+        ensure!(
+            super_of(connection, sub).await? == Some((main.clone(), name)),
+            IdentityError::UnexpectedSub(sub.clone())
+        );
+        Ok(())
+    }
+
+    async fn add_sub(&self, connection: &NodeConnection, main: &KeyPair) -> AnyResult<()> {
+        let main_account = account_from_keypair(main);
+        // Sub-accounts do not need to sign anything, so any account will do.
+        let sub = AccountId::from(thread_rng().gen::<[u8; 32]>());
+        let name = random_data(&mut thread_rng(), "sub-");
+        let before = reserved_balance(connection, &main_account).await?;
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Identity",
+            "add_sub",
+            GenericAddress::Id(sub.clone()),
+            name.clone()
+        );
+        let (sub_account, main_account_) = (sub.clone(), main_account.clone());
+        let (_, event) = send_with_event(
+            connection,
+            main,
+            call,
+            "Identity::add_sub",
+            move |event: &SubIdentityAdded| event.sub == sub_account && event.main == main_account_,
+        )
+        .await?;
+
+        check_event_deposit("SubIdentityAdded", event.deposit, self.deposits.sub_account)?;
+        Self::check_sub(connection, &sub, &main_account, name).await?;
+        check_reserve(
+            "add_sub",
+            &main_account,
+            before,
+            reserved_balance(connection, &main_account).await?,
+            self.deposits.sub_account as i128,
+        )
+    }
+
+    /// Some sub-account of `main`.
+    async fn random_sub(connection: &NodeConnection, main: &AccountId) -> AnyResult<AccountId> {
+        let (_, subs) = subs(connection, main).await?;
+        Ok(subs
+            .choose(&mut thread_rng())
+            .ok_or_else(|| IdentityError::UnexpectedSub(main.clone()))?
+            .clone())
+    }
+
+    async fn rename_sub(&self, connection: &NodeConnection, main: &KeyPair) -> AnyResult<()> {
+        let main_account = account_from_keypair(main);
+        let sub = Self::random_sub(connection, &main_account).await?;
+        let name = random_data(&mut thread_rng(), "renamed-");
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Identity",
+            "rename_sub",
+            GenericAddress::Id(sub.clone()),
+            name.clone()
+        );
+        // There is no event for renaming.
+        send_managed_xt(
+            connection,
+            main,
+            call,
+            "Identity::rename_sub",
+            XtStatus::Finalized,
+        )
+        .await?;
+
+        Self::check_sub(connection, &sub, &main_account, name)
+    }
+
+    async fn remove_sub(&self, connection: &NodeConnection, main: &KeyPair) -> AnyResult<()> {
+        let main_account = account_from_keypair(main);
+        let sub = Self::random_sub(connection, &main_account).await?;
+        let before = reserved_balance(connection, &main_account).await?;
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Identity",
+            "remove_sub",
+            GenericAddress::Id(sub.clone())
+        );
+        let (sub_account, main_account_) = (sub.clone(), main_account.clone());
+        let (_, event) = send_with_event(
+            connection,
+            main,
+            call,
+            "Identity::remove_sub",
+            move |event: &SubIdentityRemoved| {
+                event.sub == sub_account && event.main == main_account_
+            },
+        )
+        .await?;
+
+        check_event_deposit(
+            "SubIdentityRemoved",
+            event.deposit,
+            self.deposits.sub_account,
+        )?;
+        ensure!(
+            super_of(connection, &sub).await?.is_none(),
+            IdentityError::SubNotRemoved(sub)
+        );
+        check_reserve(
+            "remove_sub",
+            &main_account,
+            before,
+            reserved_balance(connection, &main_account).await?,
+            -(self.deposits.sub_account as i128),
+        )
+    }
+
+    async fn perform(
+        &self,
+        step: Step,
+        connection: &NodeConnection,
+        who: &KeyPair,
+    ) -> AnyResult<()> {
+        match step {
+            Step::SetIdentity => self.set_identity(connection, who).await,
+            Step::ClearIdentity => self.clear_identity(connection, who).await,
+            Step::RequestJudgement => self.request_judgement(connection, who).await,
+            Step::ProvideJudgement => {
+                self.provide_judgement(connection, &account_from_keypair(who))
+                    .await
+            }
+            Step::AddSub => self.add_sub(connection, who).await,
+            Step::RenameSub => self.rename_sub(connection, who).await,
+            Step::RemoveSub => self.remove_sub(connection, who).await,
+        }
+    }
+
+    /// Adds the registrar with `Sudo::sudo`.
+    async fn add_registrar(
+        &self,
+        connection: &NodeConnection,
+        registrar: &AccountId,
+    ) -> AnyResult<()> {
+        let sudo = self
+            .sudo
+            .as_ref()
+            .ok_or_else(|| IdentityError::NoSudo(registrar.clone()))?;
+        let sudo = keypair_from_string(&sudo.read()?);
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Identity",
+            "add_registrar",
+            registrar.clone()
+        );
+        let call = compose_call!(connection.as_connection().metadata, "Sudo", "sudo", call);
+        send_with_event(
+            connection,
+            &sudo,
+            call,
+            "Sudo::sudo",
+            |_: &RegistrarAdded| true,
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+impl Preflight for Identity {
+    fn requirements(&self) -> Requirements {
+        let requirements = Requirements::default()
+            .call("Identity", "set_identity")
+            .call("Identity", "clear_identity")
+            .call("Identity", "request_judgement")
+            .call("Identity", "provide_judgement")
+            .call("Identity", "add_sub")
+            .call("Identity", "rename_sub")
+            .call("Identity", "remove_sub")
+            .pool(&self.pool, 1)
+            .pool(&self.registrar_pool, 1);
+        match self.sudo {
+            Some(_) => requirements
+                .call("Sudo", "sudo")
+                .call("Identity", "add_registrar"),
+            None => requirements,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Identity {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let who = connection.accounts(&self.pool)?.random_keypair();
+        let account = account_from_keypair(&who);
+        let step = logger.log_result(self.next_step(connection, &account).await)?;
+
+        logger.info(format!("{:?} with {}", step, account));
+        logger.log_result(self.perform(step, connection, &who).await)?;
+        logger.info(format!("{:?} with {} confirmed", step, account));
+        Ok(())
+    }
+
+    /// Reads the deposits and makes sure that the registrar is there.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        self.deposits = Deposits {
+            basic: constant(connection, "BasicDeposit")?,
+            field: constant(connection, "FieldDeposit")?,
+            sub_account: constant(connection, "SubAccountDeposit")?,
+        };
+
+        let registrar = account_from_keypair(&self.registrar(connection)?);
+        if registrar_index(connection, &registrar).await?.is_none() {
+            logger.info(format!("Adding {} as a registrar", registrar));
+            self.add_registrar(connection, &registrar).await?;
+        }
+        self.registrar_index = registrar_index(connection, &registrar)
+            .await?
+            .ok_or(IdentityError::RegistrarNotAdded(registrar))?;
+        Ok(())
+    }
+}
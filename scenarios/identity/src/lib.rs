@@ -0,0 +1,72 @@
+use aleph_client::substrate_api_client::{AccountId, Balance};
+use anyhow::Result as AnyResult;
+use thiserror::Error;
+
+use chain_support::{ChainScenarioKind, ChainScenarioRegistry};
+pub use identity::Identity;
+
+use crate::storage::Judgement;
+
+mod events;
+mod identity;
+mod storage;
+
+/// Gathers all possible errors from this crate.
+#[derive(Debug, Error)]
+pub enum IdentityError {
+    #[error("🆔❌ Constant `{0}` cannot be read from the metadata of `Identity`.")]
+    MissingConstant(&'static str),
+    #[error("🆔❌ `{0:?}` is not a registrar and there is no sudo key to add it.")]
+    NoSudo(AccountId),
+    #[error("🆔❌ `{0:?}` has not been added as a registrar.")]
+    RegistrarNotAdded(AccountId),
+    #[error("🆔❌ Account `{0:?}` should have an identity.")]
+    NoIdentity(AccountId),
+    #[error("🆔❌ Account `{0:?}` has a different identity than it has set.")]
+    UnexpectedIdentity(AccountId),
+    #[error("🆔❌ Account `{0:?}` still has an identity after clearing it.")]
+    IdentityNotCleared(AccountId),
+    #[error("🆔❌ Account `{account:?}` has a judgement `{found:?}` instead of `{expected:?}`.")]
+    UnexpectedJudgement {
+        account: AccountId,
+        found: Option<Judgement>,
+        expected: Judgement,
+    },
+    #[error(
+        "🆔❌ Sub-account `{0:?}` has a different main account or name than it has been given."
+    )]
+    UnexpectedSub(AccountId),
+    #[error("🆔❌ Sub-account `{0:?}` still has a main account after removing it.")]
+    SubNotRemoved(AccountId),
+    #[error(
+        "🆔❌ After `{step}`, reserved balance of `{account:?}` has changed by {found} instead of \
+        {expected}."
+    )]
+    UnexpectedReserve {
+        step: &'static str,
+        account: AccountId,
+        found: i128,
+        expected: i128,
+    },
+    #[error("🆔❌ `{event}` reports deposit of {found} instead of {expected}.")]
+    UnexpectedEventDeposit {
+        event: &'static str,
+        found: Balance,
+        expected: Balance,
+    },
+}
+
+/// Registers all the identity scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "Identity",
+        &[
+            "sudo (optional): where the seed phrase of the sudo account is kept (like `{ Env = \
+            \"SUDO_SEED\" }`), needed only if the registrar has not been added yet",
+            "pool (optional): account pool of the identities, by default `Identity`",
+            "registrar_pool (optional): account pool whose first account is the registrar, by \
+            default `IdentityRegistrar`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Identity>()?)),
+    ))
+}
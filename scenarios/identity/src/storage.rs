@@ -0,0 +1,186 @@
+//! Here we have 'copies' of the types and storage items of `pallet_identity` (together with reading
+//! them), so that we can both compose calls and check their effects.
+use aleph_client::substrate_api_client::{AccountId, Balance};
+use anyhow::Result as AnyResult;
+use codec::{Decode, Encode, Error as CodecError, Input, Output};
+
+use chain_support::NodeConnection;
+
+/// Copy of `pallet_identity::Data`.
+///
+/// Raw data (at most 32 bytes) is encoded with its length (plus one) as the tag. We never set
+/// hashes, but we still must be able to read them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Data {
+    None,
+    Raw(Vec<u8>),
+    Hashed(u8, [u8; 32]),
+}
+
+impl Encode for Data {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        match self {
+            Data::None => dest.push_byte(0),
+            Data::Raw(bytes) => {
+                dest.push_byte(bytes.len() as u8 + 1);
+                dest.write(bytes);
+            }
+            Data::Hashed(tag, hash) => {
+                dest.push_byte(*tag);
+                dest.write(hash);
+            }
+        }
+    }
+}
+
+impl Decode for Data {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        match input.read_byte()? {
+            0 => Ok(Data::None),
+            tag @ 1..=33 => {
+                let mut bytes = vec![0u8; tag as usize - 1];
+                input.read(&mut bytes)?;
+                Ok(Data::Raw(bytes))
+            }
+            // `BlakeTwo256`, `Sha256`, `Keccak256` and `ShaThree256`.
+            tag @ 34..=37 => {
+                let mut hash = [0u8; 32];
+                input.read(&mut hash)?;
+                Ok(Data::Hashed(tag, hash))
+            }
+            _ => Err("Unknown tag of identity data".into()),
+        }
+    }
+}
+
+/// Copy of `pallet_identity::IdentityInfo`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct IdentityInfo {
+    pub additional: Vec<(Data, Data)>,
+    pub display: Data,
+    pub legal: Data,
+    pub web: Data,
+    pub riot: Data,
+    pub email: Data,
+    pub pgp_fingerprint: Option<[u8; 20]>,
+    pub image: Data,
+    pub twitter: Data,
+}
+
+/// Copy of `pallet_identity::Judgement`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum Judgement {
+    Unknown,
+    FeePaid(Balance),
+    Reasonable,
+    KnownGood,
+    OutOfDate,
+    LowQuality,
+    Erroneous,
+}
+
+/// Copy of `pallet_identity::Registration`.
+#[derive(Clone, Debug, Decode)]
+pub struct Registration {
+    pub judgements: Vec<(u32, Judgement)>,
+    pub deposit: Balance,
+    pub info: IdentityInfo,
+}
+
+impl Registration {
+    /// Judgement of the registrar `registrar_index`, if there is any.
+    pub fn judgement(&self, registrar_index: u32) -> Option<Judgement> {
+        self.judgements
+            .iter()
+            .find(|(index, _)| *index == registrar_index)
+            .map(|(_, judgement)| *judgement)
+    }
+}
+
+/// Copy of `pallet_identity::RegistrarInfo`.
+#[derive(Clone, Debug, Decode)]
+pub struct RegistrarInfo {
+    pub account: AccountId,
+    pub fee: Balance,
+    _fields: u64,
+}
+
+/// Identity of `who`, if it has set any.
+pub async fn identity(
+    connection: &NodeConnection,
+    who: &AccountId,
+) -> AnyResult<Option<Registration>> {
+    let who = who.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_storage_map("Identity", "IdentityOf", who, None)?))
+        .await
+}
+
+/// Deposit reserved for the sub-accounts of `who` and the sub-accounts themselves.
+pub async fn subs(
+    connection: &NodeConnection,
+    who: &AccountId,
+) -> AnyResult<(Balance, Vec<AccountId>)> {
+    let who = who.clone();
+    connection
+        .read_blocking(move |api| {
+            Ok(api
+                .get_storage_map("Identity", "SubsOf", who, None)?
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/// Main account and name of the sub-account `sub`, if it is one.
+pub async fn super_of(
+    connection: &NodeConnection,
+    sub: &AccountId,
+) -> AnyResult<Option<(AccountId, Data)>> {
+    let sub = sub.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_storage_map("Identity", "SuperOf", sub, None)?))
+        .await
+}
+
+/// All the registrars (removed ones are `None`), in the order of their indices.
+pub async fn registrars(connection: &NodeConnection) -> AnyResult<Vec<Option<RegistrarInfo>>> {
+    connection
+        .read_blocking(|api| {
+            Ok(api
+                .get_storage_value("Identity", "Registrars", None)?
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/// Reserved balance of `who`.
+pub async fn reserved_balance(connection: &NodeConnection, who: &AccountId) -> AnyResult<Balance> {
+    let who = who.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_account_data(&who)?.map_or(0, |data| data.reserved)))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use codec::{Decode, Encode};
+
+    use crate::storage::Data;
+
+    #[test]
+    fn data_is_encoded_like_in_the_pallet() {
+        assert_eq!(vec![0u8], Data::None.encode());
+        assert_eq!(
+            vec![4u8, b'b', b'o', b't'],
+            Data::Raw(b"bot".to_vec()).encode()
+        );
+
+        for data in [
+            Data::None,
+            Data::Raw(vec![7; 32]),
+            Data::Hashed(34, [1; 32]),
+        ] {
+            assert_eq!(Ok(data.clone()), Data::decode(&mut &data.encode()[..]));
+        }
+    }
+}
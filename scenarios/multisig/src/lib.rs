@@ -1,21 +1,22 @@
-// for `do_async!`
-#![feature(fn_traits)]
-
 use std::{fmt::Debug, time::Duration};
 
 use aleph_client::{
     account_from_keypair, compute_call_hash,
-    substrate_api_client::{AccountId, UncheckedExtrinsicV4},
-    AnyConnection, KeyPair, MultisigParty, SignatureAggregation, SignedConnection,
+    substrate_api_client::{UncheckedExtrinsicV4, XtStatus},
+    BlockNumber, KeyPair,
 };
 use anyhow::Result as AnyResult;
 use codec::Encode;
+use pallet_multisig::Timepoint;
 use rand::{random, thread_rng, Rng};
 use serde::Deserialize;
 use thiserror::Error;
 
-use chain_support::{do_async, with_event_listening};
+use chain_support::{
+    send_managed_xt, with_event_listening, ChainScenarioKind, ChainScenarioRegistry, NodeConnection,
+};
 pub use multisig::Multisig;
+use party::Party;
 use Action::*;
 use Strategy::*;
 
@@ -26,6 +27,22 @@ use crate::events::{
 
 mod events;
 mod multisig;
+mod party;
+
+/// Registers the multisig scenario.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "Multisig",
+        &[
+            "party_size: `Small`, `Medium`, `Large` or `{ Precise = <size> }`",
+            "threshold: `Random` or `{ Precise = <threshold> }`",
+            "strategy: `Optimal`, `Mess` or `InAdvance`",
+            "cancel: whether the aggregation is cancelled",
+            "pool (optional): account pool of the members, by default `Multisig`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Multisig>()?)),
+    ))
+}
 
 /// How long are we willing to wait for a particular event.
 const EVENT_TIMEOUT: Duration = Duration::from_millis(3000);
@@ -62,6 +79,16 @@ pub enum PartySize {
 }
 
 impl PartySize {
+    /// The smallest size that can be drawn and the smallest pool from which any size can be drawn.
+    pub(crate) fn bounds(&self) -> (usize, usize) {
+        match self {
+            PartySize::Small => (2, 5),
+            PartySize::Medium => (6, 14),
+            PartySize::Large => (15, 15),
+            PartySize::Precise(size) => (*size, *size),
+        }
+    }
+
     pub fn get(self, upper_bound: usize) -> AnyResult<usize> {
         match self {
             PartySize::Small => Ok(thread_rng().gen_range(2..6)),
@@ -116,17 +143,6 @@ enum Action {
     Cancel,
 }
 
-/// Auxiliary function flattening result of result.
-///
-/// Highly useful for results being returned from within `do_async!`.
-fn flatten<T, E: Into<anyhow::Error>>(result: Result<AnyResult<T>, E>) -> AnyResult<T> {
-    match result {
-        Ok(Ok(r)) => Ok(r),
-        Ok(Err(e)) => Err(e),
-        Err(e) => Err(e.into()),
-    }
-}
-
 impl Action {
     /// Checks whether the action carries a whole call (not just its hash).
     pub fn requires_call(&self) -> bool {
@@ -143,13 +159,10 @@ impl Action {
         matches!(self, InitiateWithCall | InitiateWithHash)
     }
 
-    /// Effectively performs the semantics behind `Action`. Calls corresponding methods of `party`.
+    /// Effectively performs the semantics behind `Action`. Submits corresponding extrinsic on
+    /// behalf of `party`.
     ///
-    /// Unfortunately, `party` has to be passed by value here, as `MultisigParty` does not implement
-    /// `Clone` trait, and passing a reference would require `'static` lifetime from the calling
-    /// code.
-    ///
-    /// `sig_agg` should be `None` iff `self.is_initial()`.
+    /// `timepoint` should be `None` iff `self.is_initial()`.
     ///
     /// `should_finalize` is a flag indicating whether this approval should result in executing
     /// `call`.
@@ -157,159 +170,171 @@ impl Action {
     /// Note: if the action is `InitiateWithCall` or `ApproveWithCall`, `call` will be stored
     /// (unless this is the final approval). In other words, the pallet call flag `store_call` is
     /// always set to `true`.
-    pub async fn perform<C: AnyConnection, CallDetails: Encode + Clone + Send + 'static>(
+    pub async fn perform<CallDetails: Encode>(
         &self,
-        connection: &C,
-        party: MultisigParty,
-        sig_agg: Option<SignatureAggregation>,
-        call: UncheckedExtrinsicV4<CallDetails>,
+        connection: &NodeConnection,
+        party: &Party,
+        timepoint: Option<Timepoint<BlockNumber>>,
+        call: &UncheckedExtrinsicV4<CallDetails>,
         caller: &KeyPair,
         should_finalize: bool,
-    ) -> AnyResult<Option<SignatureAggregation>> {
-        if !self.is_initial() && sig_agg.is_none() {
+    ) -> AnyResult<()> {
+        if self.is_initial() != timepoint.is_none() {
             return Err(MultisigError::InvalidAggregation.into());
         }
 
-        let connection = SignedConnection::from_any_connection(connection, caller.clone());
-        let caller = account_from_keypair(caller);
-        let caller_idx = party.get_member_index(caller.clone())?;
-        let call_hash = compute_call_hash(&call);
+        let call_hash = compute_call_hash(call);
+        let caller_account = account_from_keypair(caller);
 
         // The schema is common for all the cases. Firstly, we build an `event` we expect to confirm
-        // the action. Then we call corresponding method from `MultisigParty` wrapped with
-        // `with_event_listening`. This part is done asynchronously and non-blocking due to
-        // `do_async!`.
-        //
-        // When succeeded, we return new `SignatureAggregation` (in case of `Cancel`, this will be
-        // unchanged `sig_agg`).
+        // the action. Then we submit corresponding extrinsic wrapped with `with_event_listening`.
         //
         // As for similar code: since `event` object is different, we cannot easily (without some
         // ugly boxing) extract common schema.
         match self {
             InitiateWithHash => {
-                let event =
-                    NewMultisigEvent::from_relevant_fields(caller, party.get_account(), call_hash);
-                with_event_listening(&connection, event, EVENT_TIMEOUT, async {
-                    flatten(do_async!(
-                        MultisigParty::initiate_aggregation_with_hash,
-                        party,
-                        &connection,
-                        call_hash,
-                        caller_idx
-                    ))
+                let event = NewMultisigEvent::from_relevant_fields(
+                    caller_account,
+                    party.account(),
+                    call_hash,
+                );
+                let multisig_call = party.approve_as_multi(connection, caller, None, call_hash);
+                with_event_listening(connection, event, EVENT_TIMEOUT, async {
+                    send_managed_xt(
+                        connection,
+                        caller,
+                        multisig_call,
+                        "Multisig::approve_as_multi",
+                        XtStatus::Finalized,
+                    )
+                    .await
                 })
                 .await
-                .map(|(sig_agg, _event)| Some(sig_agg))
+                .map(|_| ())
             }
             InitiateWithCall => {
-                let event =
-                    NewMultisigEvent::from_relevant_fields(caller, party.get_account(), call_hash);
-                with_event_listening(&connection, event, EVENT_TIMEOUT, async {
-                    flatten(do_async!(
-                        MultisigParty::initiate_aggregation_with_call,
-                        party,
-                        &connection,
-                        call,
-                        { true },
-                        caller_idx
-                    ))
+                let event = NewMultisigEvent::from_relevant_fields(
+                    caller_account,
+                    party.account(),
+                    call_hash,
+                );
+                let multisig_call = party.as_multi(connection, caller, None, call);
+                with_event_listening(connection, event, EVENT_TIMEOUT, async {
+                    send_managed_xt(
+                        connection,
+                        caller,
+                        multisig_call,
+                        "Multisig::as_multi",
+                        XtStatus::Finalized,
+                    )
+                    .await
                 })
                 .await
-                .map(|(sig_agg, _event)| Some(sig_agg))
+                .map(|_| ())
             }
             ApproveWithHash if should_finalize => {
                 let event = MultisigExecutedEvent::from_relevant_fields(
-                    caller,
-                    party.get_account(),
+                    caller_account,
+                    party.account(),
                     call_hash,
                 );
-                with_event_listening(&connection, event, EVENT_TIMEOUT, async {
-                    flatten(do_async!(
-                        MultisigParty::approve,
-                        party,
-                        &connection,
-                        caller_idx,
-                        sig_agg.unwrap()
-                    ))
+                let multisig_call =
+                    party.approve_as_multi(connection, caller, timepoint, call_hash);
+                with_event_listening(connection, event, EVENT_TIMEOUT, async {
+                    send_managed_xt(
+                        connection,
+                        caller,
+                        multisig_call,
+                        "Multisig::approve_as_multi",
+                        XtStatus::Finalized,
+                    )
+                    .await
                 })
                 .await
-                .map(|(sig_agg, _event)| Some(sig_agg))
+                .map(|_| ())
             }
             ApproveWithCall if should_finalize => {
                 let event = MultisigExecutedEvent::from_relevant_fields(
-                    caller,
-                    party.get_account(),
+                    caller_account,
+                    party.account(),
                     call_hash,
                 );
-                with_event_listening(&connection, event, EVENT_TIMEOUT, async {
-                    flatten(do_async!(
-                        MultisigParty::approve_with_call,
-                        party,
-                        &connection,
-                        caller_idx,
-                        sig_agg.unwrap(),
-                        call,
-                        { true }
-                    ))
+                let multisig_call = party.as_multi(connection, caller, timepoint, call);
+                with_event_listening(connection, event, EVENT_TIMEOUT, async {
+                    send_managed_xt(
+                        connection,
+                        caller,
+                        multisig_call,
+                        "Multisig::as_multi",
+                        XtStatus::Finalized,
+                    )
+                    .await
                 })
                 .await
-                .map(|(sig_agg, _event)| Some(sig_agg))
+                .map(|_| ())
             }
             ApproveWithHash => {
                 let event = MultisigApprovalEvent::from_relevant_fields(
-                    caller,
-                    party.get_account(),
+                    caller_account,
+                    party.account(),
                     call_hash,
                 );
-                with_event_listening(&connection, event, EVENT_TIMEOUT, async {
-                    flatten(do_async!(
-                        MultisigParty::approve,
-                        party,
-                        &connection,
-                        caller_idx,
-                        sig_agg.unwrap()
-                    ))
+                let multisig_call =
+                    party.approve_as_multi(connection, caller, timepoint, call_hash);
+                with_event_listening(connection, event, EVENT_TIMEOUT, async {
+                    send_managed_xt(
+                        connection,
+                        caller,
+                        multisig_call,
+                        "Multisig::approve_as_multi",
+                        XtStatus::Finalized,
+                    )
+                    .await
                 })
                 .await
-                .map(|(sig_agg, _event)| Some(sig_agg))
+                .map(|_| ())
             }
             ApproveWithCall => {
                 let event = MultisigApprovalEvent::from_relevant_fields(
-                    caller,
-                    party.get_account(),
+                    caller_account,
+                    party.account(),
                     call_hash,
                 );
-                with_event_listening(&connection, event, EVENT_TIMEOUT, async {
-                    flatten(do_async!(
-                        MultisigParty::approve_with_call,
-                        party,
-                        &connection,
-                        caller_idx,
-                        sig_agg.unwrap(),
-                        call,
-                        { true }
-                    ))
+                let multisig_call = party.as_multi(connection, caller, timepoint, call);
+                with_event_listening(connection, event, EVENT_TIMEOUT, async {
+                    send_managed_xt(
+                        connection,
+                        caller,
+                        multisig_call,
+                        "Multisig::as_multi",
+                        XtStatus::Finalized,
+                    )
+                    .await
                 })
                 .await
-                .map(|(sig_agg, _event)| Some(sig_agg))
+                .map(|_| ())
             }
             Cancel => {
                 let event = MultisigCancelledEvent::from_relevant_fields(
-                    caller,
-                    party.get_account(),
+                    caller_account,
+                    party.account(),
                     call_hash,
                 );
-                with_event_listening(&connection, event, EVENT_TIMEOUT, async {
-                    flatten(do_async!(
-                        MultisigParty::cancel,
-                        party,
-                        &connection,
-                        caller_idx,
-                        sig_agg.unwrap()
-                    ))
+                // Checked at the beginning.
+                let multisig_call =
+                    party.cancel_as_multi(connection, caller, timepoint.unwrap(), call_hash);
+                with_event_listening(connection, event, EVENT_TIMEOUT, async {
+                    send_managed_xt(
+                        connection,
+                        caller,
+                        multisig_call,
+                        "Multisig::cancel_as_multi",
+                        XtStatus::Finalized,
+                    )
+                    .await
                 })
                 .await
-                .map(|_| None)
+                .map(|_| ())
             }
         }
     }
@@ -1,27 +1,19 @@
 use aleph_client::{
+    compute_call_hash,
     substrate_api_client::{extrinsic::balances::BalanceTransferXt, GenericAddress},
-    AnyConnection, Connection, KeyPair, MultisigParty,
+    AnyConnection, KeyPair,
 };
 use anyhow::Result as AnyResult;
-use rand::{seq::index::sample, thread_rng, Rng};
+use rand::{thread_rng, Rng};
 use serde::Deserialize;
 
-use chain_support::keypair_derived_from_seed;
+use chain_support::{NodeConnection, Preflight, Requirements};
 use common::{Scenario, ScenarioLogging};
 
-use crate::{Action, Cancel, PartySize, Strategy, Threshold};
+use crate::{Action, Cancel, Party, PartySize, Strategy, Threshold};
 
-/// We operate on an account pool based on this seed. The final seeds will have a form of
-/// `MULTISIG_SEED{i: usize}`.
-const MULTISIG_SEED: &str = "//Multisig";
-
-/// We expect that there are as many endowed accounts (of seed phrases: `MULTISIG_SEED{i}`, where
-/// `i` is from 0 to this value (exclusively)).
-const AVAILABLE_ACCOUNTS: usize = 50;
-
-/// Returns keypair of the common account with index `idx`.
-fn compute_keypair(idx: usize) -> KeyPair {
-    keypair_derived_from_seed(format!("{}{}", MULTISIG_SEED, idx))
+fn default_pool() -> String {
+    "Multisig".to_string()
 }
 
 type Call = BalanceTransferXt;
@@ -29,6 +21,9 @@ type Call = BalanceTransferXt;
 /// Configuration for `Multisig` scenario.
 #[derive(Clone, Debug, Deserialize)]
 pub struct Multisig {
+    /// Account pool from which party members are chosen. By default, `Multisig`.
+    #[serde(default = "default_pool")]
+    pool: String,
     /// Multisig party will be derived from `party_size` and `threshold`. Each time scenario is
     /// launched, these parameters will potentially be different.
     party_size: PartySize,
@@ -40,15 +35,6 @@ pub struct Multisig {
 }
 
 impl Multisig {
-    /// Randomly selects `party_size` accounts.
-    fn select_members(&self, party_size: usize) -> Vec<KeyPair> {
-        let mut rng = thread_rng();
-        sample(&mut rng, AVAILABLE_ACCOUNTS, party_size)
-            .iter()
-            .map(compute_keypair)
-            .collect()
-    }
-
     /// Returns a sequence of actions.
     ///
     /// There will be either
@@ -81,34 +67,30 @@ impl Multisig {
     ///
     /// We use simple money transfer which will always fail, but this does not matter at all in
     /// context of scenario success.
-    fn prepare_call(connection: &Connection) -> Call {
+    fn prepare_call(connection: &NodeConnection) -> Call {
         connection
             .as_connection()
             .balance_transfer(GenericAddress::Address32(Default::default()), 0)
     }
 
-    /// Due to the problems described in `crate::Action::perform` we have to create party for each
-    /// call.
-    ///
-    /// Fortunately it is not so expensive.
-    fn get_party(members: &[KeyPair], threshold: usize) -> AnyResult<MultisigParty> {
-        MultisigParty::new(members.to_vec(), threshold as u16)
-    }
-
     /// Executes `actions`. `i`th action will be performed by `members[i]` (unless this is `Cancel`
     /// which should be performed by `members[0]`).
     async fn perform_multisig(
-        connection: &Connection,
+        connection: &NodeConnection,
         members: Vec<KeyPair>,
         threshold: usize,
         actions: Vec<Action>,
         call: Call,
         logger: &ScenarioLogging,
     ) -> AnyResult<()> {
-        let party = Self::get_party(&members, threshold)?;
+        let party = Party::new(&members, threshold)?;
         logger.info("Initializing signature aggregation");
-        let mut sig_agg = actions[0]
-            .perform(connection, party, None, call.clone(), &members[0], false)
+        actions[0]
+            .perform(connection, &party, None, &call, &members[0], false)
+            .await?;
+
+        let timepoint = party
+            .timepoint(connection, compute_call_hash(&call))
             .await?;
 
         // Here `i` is one less then the actual member index.
@@ -122,12 +104,12 @@ impl Multisig {
 
             let idx = if action.is_cancel() { 0 } else { i + 1 };
 
-            sig_agg = action
+            action
                 .perform(
                     connection,
-                    Self::get_party(&members, threshold)?,
-                    sig_agg,
-                    call.clone(),
+                    &party,
+                    Some(timepoint),
+                    &call,
                     &members[idx],
                     should_finalize,
                 )
@@ -142,10 +124,43 @@ impl Multisig {
     }
 }
 
+impl Preflight for Multisig {
+    fn requirements(&self) -> Requirements {
+        let (min_party_size, pool_size) = self.party_size.bounds();
+        let requirements = Requirements::default()
+            .call("Multisig", "as_multi")
+            .call("Multisig", "approve_as_multi")
+            .pool(&self.pool, pool_size)
+            .ensure(min_party_size >= 2, "Party should have at least 2 members");
+        let requirements = match self.threshold {
+            Threshold::Precise(threshold) => requirements
+                .ensure(threshold >= 2, "Threshold should be at least 2")
+                .ensure(
+                    threshold <= min_party_size,
+                    format!(
+                        "Threshold {} is higher than the party size (which can be {})",
+                        threshold, min_party_size
+                    ),
+                ),
+            Threshold::Random => requirements,
+        };
+        if self.cancel {
+            requirements.call("Multisig", "cancel_as_multi")
+        } else {
+            requirements
+        }
+    }
+}
+
 #[async_trait::async_trait]
-impl Scenario<Connection> for Multisig {
-    async fn play(&mut self, connection: &Connection, logger: &ScenarioLogging) -> AnyResult<()> {
-        let party_size = self.party_size.clone().get(AVAILABLE_ACCOUNTS)?;
+impl Scenario<NodeConnection> for Multisig {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let accounts = connection.accounts(&self.pool)?;
+        let party_size = self.party_size.clone().get(accounts.size())?;
         let threshold = self.threshold.clone().get(party_size)?;
 
         logger.info(format!(
@@ -153,7 +168,7 @@ impl Scenario<Connection> for Multisig {
             party_size, threshold
         ));
 
-        let members = self.select_members(party_size);
+        let members = accounts.random_keypairs(party_size)?;
         let actions = self.prepare_actions(threshold);
         let call = Self::prepare_call(connection);
 
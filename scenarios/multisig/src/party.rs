@@ -0,0 +1,160 @@
+//! A minimal counterpart of `aleph_client::MultisigParty`. The original one sends extrinsics in
+//! a blocking manner, so here we only compose the calls and leave signing and submission to the
+//! caller.
+
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{compose_call, AccountId, Balance, UncheckedExtrinsicV4},
+    AnyConnection, BlockNumber, KeyPair,
+};
+use anyhow::Result as AnyResult;
+use codec::{Decode, Encode};
+use pallet_multisig::Timepoint;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+
+use chain_support::NodeConnection;
+
+use crate::{CallHash, MultisigError};
+
+/// Upper bound for the weight of the call executed after reaching threshold. Our calls are simple
+/// transfers, so this is more than enough.
+const MAX_WEIGHT: u64 = 10_000_000_000;
+
+/// Mirror of `pallet_multisig::Multisig` (which has private fields) for reading storage.
+#[derive(Decode)]
+struct MultisigInfo {
+    when: Timepoint<BlockNumber>,
+    _deposit: Balance,
+    _depositor: AccountId,
+    _approvals: Vec<AccountId>,
+}
+
+/// Multisig party: its members and the threshold.
+pub struct Party {
+    /// Members' accounts, sorted (as the pallet requires).
+    accounts: Vec<AccountId>,
+    threshold: u16,
+    /// Account of the multisig itself.
+    account: AccountId,
+}
+
+impl Party {
+    pub fn new(members: &[KeyPair], threshold: usize) -> AnyResult<Self> {
+        if threshold > members.len() {
+            return Err(MultisigError::ThresholdTooHigh.into());
+        }
+        let mut accounts = members.iter().map(account_from_keypair).collect::<Vec<_>>();
+        accounts.sort();
+        let threshold = threshold as u16;
+
+        Ok(Party {
+            account: Self::multi_account_id(&accounts, threshold),
+            accounts,
+            threshold,
+        })
+    }
+
+    /// Computes multisig account in the same way as `pallet_multisig::Pallet::multi_account_id`.
+    fn multi_account_id(accounts: &[AccountId], threshold: u16) -> AccountId {
+        let entropy = BlakeTwo256::hash_of(&(*b"modlpy/utilisuba", accounts, threshold));
+        AccountId::from(entropy.to_fixed_bytes())
+    }
+
+    /// Account of the multisig.
+    pub fn account(&self) -> AccountId {
+        self.account.clone()
+    }
+
+    /// All the members except for `caller` (sorted).
+    fn other_signatories(&self, caller: &KeyPair) -> Vec<AccountId> {
+        let caller = account_from_keypair(caller);
+        self.accounts
+            .iter()
+            .filter(|a| **a != caller)
+            .cloned()
+            .collect()
+    }
+
+    /// Reads from storage when the aggregation for `call_hash` has been opened.
+    pub async fn timepoint(
+        &self,
+        connection: &NodeConnection,
+        call_hash: CallHash,
+    ) -> AnyResult<Timepoint<BlockNumber>> {
+        let account = self.account();
+        connection
+            .read_blocking(move |api| {
+                Ok(api.get_storage_double_map::<_, _, MultisigInfo>(
+                    "Multisig",
+                    "Multisigs",
+                    account,
+                    call_hash,
+                    None,
+                )?)
+            })
+            .await?
+            .map(|info| info.when)
+            .ok_or_else(|| MultisigError::InvalidAggregation.into())
+    }
+
+    /// Composes `Multisig::approve_as_multi` call to be signed by `caller`.
+    pub fn approve_as_multi<C: AnyConnection>(
+        &self,
+        connection: &C,
+        caller: &KeyPair,
+        timepoint: Option<Timepoint<BlockNumber>>,
+        call_hash: CallHash,
+    ) -> impl Encode + Clone {
+        compose_call!(
+            connection.as_connection().metadata,
+            "Multisig",
+            "approve_as_multi",
+            self.threshold,
+            self.other_signatories(caller),
+            timepoint,
+            call_hash,
+            MAX_WEIGHT
+        )
+    }
+
+    /// Composes `Multisig::as_multi` call to be signed by `caller`. `call` is always stored.
+    pub fn as_multi<C: AnyConnection, CallDetails: Encode>(
+        &self,
+        connection: &C,
+        caller: &KeyPair,
+        timepoint: Option<Timepoint<BlockNumber>>,
+        call: &UncheckedExtrinsicV4<CallDetails>,
+    ) -> impl Encode + Clone {
+        compose_call!(
+            connection.as_connection().metadata,
+            "Multisig",
+            "as_multi",
+            self.threshold,
+            self.other_signatories(caller),
+            timepoint,
+            // `OpaqueCall` is encoded just like a vector of bytes.
+            call.function.encode(),
+            true,
+            MAX_WEIGHT
+        )
+    }
+
+    /// Composes `Multisig::cancel_as_multi` call to be signed by `caller`.
+    pub fn cancel_as_multi<C: AnyConnection>(
+        &self,
+        connection: &C,
+        caller: &KeyPair,
+        timepoint: Timepoint<BlockNumber>,
+        call_hash: CallHash,
+    ) -> impl Encode + Clone {
+        compose_call!(
+            connection.as_connection().metadata,
+            "Multisig",
+            "cancel_as_multi",
+            self.threshold,
+            self.other_signatories(caller),
+            timepoint,
+            call_hash
+        )
+    }
+}
@@ -0,0 +1,183 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{ensure, Result as AnyResult};
+use serde::Deserialize;
+
+use chain_support::{NodeConnection, Preflight, Requirements};
+use common::{parse_interval, Scenario, ScenarioLogging};
+
+use crate::{
+    storage::{active_era, current_session},
+    ObserverError,
+};
+
+/// An index (of a session or an era) together with the moment it was first seen.
+#[derive(Clone, Debug)]
+struct Observed {
+    index: u32,
+    since: Instant,
+}
+
+impl Observed {
+    fn new(index: u32, now: Instant) -> Self {
+        Observed { index, since: now }
+    }
+
+    /// Records `index` seen at `now`. Returns for how long the index has not changed.
+    ///
+    /// Lower indices are ignored, as they might come from a node which is a bit behind.
+    fn observe(&mut self, index: u32, now: Instant) -> Duration {
+        if index > self.index {
+            *self = Observed::new(index, now);
+        }
+        now.duration_since(self.since)
+    }
+}
+
+/// A passive scenario, which watches whether the chain makes progress.
+///
+/// Every run samples `Session::CurrentIndex`, `Staking::ActiveEra` and the numbers of the best
+/// and the last finalized block. It fails if the session (or the era) has not changed for longer
+/// than `max_session_duration` (`max_era_duration`), or if finalization is more than
+/// `max_finality_lag` blocks behind. The first sample is taken during setup.
+///
+/// It sends no extrinsics and needs no accounts. Its interval should be considerably shorter than
+/// the bounds, so that a stalled chain is noticed early.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChainProgress {
+    /// For how long the session index may stay the same.
+    #[serde(deserialize_with = "parse_interval")]
+    max_session_duration: Duration,
+    /// For how long the active era may stay the same.
+    #[serde(deserialize_with = "parse_interval")]
+    max_era_duration: Duration,
+    /// By how many blocks finalization may lag behind the best block.
+    max_finality_lag: u32,
+    /// Session and era seen so far.
+    #[serde(skip)]
+    observed: Option<(Observed, Observed)>,
+}
+
+impl ChainProgress {
+    /// Reads the current session and the active era.
+    async fn sample(connection: &NodeConnection) -> AnyResult<(u32, u32)> {
+        let session = current_session(connection).await?;
+        let era = active_era(connection)
+            .await?
+            .ok_or(ObserverError::NoActiveEra)?;
+        Ok((session, era.index))
+    }
+
+    async fn check_progress(&mut self, connection: &NodeConnection) -> AnyResult<()> {
+        let (session, era) = Self::sample(connection).await?;
+        let now = Instant::now();
+        let (observed_session, observed_era) = self
+            .observed
+            .get_or_insert_with(|| (Observed::new(session, now), Observed::new(era, now)));
+
+        let stalled = observed_session.observe(session, now);
+        ensure!(
+            stalled <= self.max_session_duration,
+            ObserverError::SessionStalled {
+                index: observed_session.index,
+                stalled,
+                bound: self.max_session_duration,
+            }
+        );
+        let stalled = observed_era.observe(era, now);
+        ensure!(
+            stalled <= self.max_era_duration,
+            ObserverError::EraStalled {
+                index: observed_era.index,
+                stalled,
+                bound: self.max_era_duration,
+            }
+        );
+        Ok(())
+    }
+
+    async fn check_finality(&self, connection: &NodeConnection) -> AnyResult<()> {
+        let best = connection.best_block_number().await?;
+        let finalized = connection.finalized_block_number().await?;
+        let lag = best.saturating_sub(finalized);
+        ensure!(
+            lag <= self.max_finality_lag,
+            ObserverError::FinalityLagging {
+                best,
+                finalized,
+                lag,
+                bound: self.max_finality_lag,
+            }
+        );
+        Ok(())
+    }
+}
+
+impl Preflight for ChainProgress {
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+            .ensure(
+                !self.max_session_duration.is_zero(),
+                "`max_session_duration` should be positive",
+            )
+            .ensure(
+                self.max_era_duration >= self.max_session_duration,
+                "`max_era_duration` should not be shorter than `max_session_duration`",
+            )
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for ChainProgress {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        logger.log_result(self.check_progress(connection).await)?;
+        logger.log_result(self.check_finality(connection).await)?;
+        if let Some((session, era)) = &self.observed {
+            logger.debug(format!(
+                "Session {} for {:?}, era {} for {:?}",
+                session.index,
+                session.since.elapsed(),
+                era.index,
+                era.since.elapsed()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Takes the first sample, so that the bounds are counted from the launch.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        _logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let (session, era) = Self::sample(connection).await?;
+        let now = Instant::now();
+        self.observed = Some((Observed::new(session, now), Observed::new(era, now)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::chain_progress::Observed;
+
+    #[test]
+    fn observed_index_resets_only_when_it_grows() {
+        let start = Instant::now();
+        let mut observed = Observed::new(5, start);
+
+        let later = start + Duration::from_secs(10);
+        assert_eq!(Duration::from_secs(10), observed.observe(5, later));
+        assert_eq!(Duration::from_secs(10), observed.observe(4, later));
+
+        let even_later = later + Duration::from_secs(10);
+        assert_eq!(Duration::ZERO, observed.observe(6, even_later));
+        assert_eq!(6, observed.index);
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use aleph_client::BlockNumber;
+use anyhow::Result as AnyResult;
+use thiserror::Error;
+
+pub use chain_progress::ChainProgress;
+use chain_support::{ChainScenarioKind, ChainScenarioRegistry};
+
+mod chain_progress;
+mod storage;
+
+/// Gathers all possible errors from this crate.
+#[derive(Debug, Error)]
+pub enum ObserverError {
+    #[error("🔭❌ There is no active era.")]
+    NoActiveEra,
+    #[error("🔭❌ Session {index} has not changed for {stalled:?} (at most {bound:?} allowed).")]
+    SessionStalled {
+        index: u32,
+        stalled: Duration,
+        bound: Duration,
+    },
+    #[error("🔭❌ Era {index} has not changed for {stalled:?} (at most {bound:?} allowed).")]
+    EraStalled {
+        index: u32,
+        stalled: Duration,
+        bound: Duration,
+    },
+    #[error(
+        "🔭❌ Finalized block #{finalized} is {lag} blocks behind the best block #{best} (at most \
+        {bound} allowed)."
+    )]
+    FinalityLagging {
+        best: BlockNumber,
+        finalized: BlockNumber,
+        lag: BlockNumber,
+        bound: BlockNumber,
+    },
+}
+
+/// Registers all the observer scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "ChainProgress",
+        &[
+            "max_session_duration: for how long the session index may stay the same",
+            "max_era_duration: for how long the active era may stay the same",
+            "max_finality_lag: by how many blocks finalization may lag behind the best block",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<ChainProgress>()?)),
+    ))
+}
@@ -0,0 +1,30 @@
+//! 'Copies' of the storage items read by the observers (together with reading them).
+use anyhow::Result as AnyResult;
+use codec::Decode;
+
+use chain_support::NodeConnection;
+
+/// Copy of `pallet_staking::ActiveEraInfo`.
+#[derive(Clone, Debug, Decode)]
+pub struct ActiveEraInfo {
+    pub index: u32,
+    _start: Option<u64>,
+}
+
+/// Index of the current session.
+pub async fn current_session(connection: &NodeConnection) -> AnyResult<u32> {
+    connection
+        .read_blocking(|api| {
+            Ok(api
+                .get_storage_value("Session", "CurrentIndex", None)?
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/// The active era, if there has been any.
+pub async fn active_era(connection: &NodeConnection) -> AnyResult<Option<ActiveEraInfo>> {
+    connection
+        .read_blocking(|api| Ok(api.get_storage_value("Staking", "ActiveEra", None)?))
+        .await
+}
@@ -0,0 +1,52 @@
+//! Here we have 'copies' of the events from `pallet_proxy`, since we need `Event` trait
+//! implemented for them.
+use aleph_client::{substrate_api_client::AccountId, BlockNumber};
+use codec::Decode;
+use sp_runtime::DispatchError;
+
+use chain_support::Event;
+
+use crate::{storage::ProxyType, CallHash};
+
+/// Emitted by `proxy` and `proxy_announced`, with the result of the proxied call.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Proxy"]
+pub struct ProxyExecuted {
+    pub result: Result<(), DispatchError>,
+}
+
+/// Emitted by `anonymous` (known as `create_pure` in the newer versions of the pallet).
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Proxy"]
+pub struct AnonymousCreated {
+    pub anonymous: AccountId,
+    pub who: AccountId,
+    pub proxy_type: ProxyType,
+    pub disambiguation_index: u16,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Proxy"]
+pub struct Announced {
+    pub real: AccountId,
+    pub proxy: AccountId,
+    pub call_hash: CallHash,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Proxy"]
+pub struct ProxyAdded {
+    pub delegator: AccountId,
+    pub delegatee: AccountId,
+    pub proxy_type: ProxyType,
+    pub delay: BlockNumber,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Proxy"]
+pub struct ProxyRemoved {
+    pub delegator: AccountId,
+    pub delegatee: AccountId,
+    pub proxy_type: ProxyType,
+    pub delay: BlockNumber,
+}
@@ -0,0 +1,47 @@
+use aleph_client::substrate_api_client::AccountId;
+use anyhow::Result as AnyResult;
+use sp_runtime::DispatchError;
+use thiserror::Error;
+
+use chain_support::{ChainScenarioKind, ChainScenarioRegistry};
+pub use proxy::{Flow, Proxy};
+pub use storage::ProxyType;
+
+mod events;
+mod proxy;
+mod storage;
+
+type CallHash = [u8; 32];
+
+/// Gathers all possible errors from this crate.
+#[derive(Debug, Error)]
+pub enum ProxyError {
+    #[error(
+        "🎭❌ `{call}` through a `{proxy_type:?}` proxy should have been {expected}, but the \
+        result is `{result:?}`."
+    )]
+    UnexpectedResult {
+        call: &'static str,
+        proxy_type: ProxyType,
+        expected: &'static str,
+        result: Result<(), DispatchError>,
+    },
+    #[error("🎭❌ Account `{0:?}` still has proxies.")]
+    ProxiesNotRemoved(AccountId),
+}
+
+/// Registers all the proxy scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "Proxy",
+        &[
+            "transfer_value: tokens sent in every transfer",
+            "flows (optional): list of `Delegate`, `Announced` and `Pure`, by default all of them",
+            "proxy_types (optional): proxy types used by `Delegate`, by default `Any`, \
+            `NonTransfer` and `Staking`",
+            "delay (optional): announcement delay (in blocks) used by `Announced`, by default 2",
+            "pool (optional): account pool of real accounts and proxies, by default `Proxy`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Proxy>()?)),
+    ))
+}
@@ -0,0 +1,512 @@
+use std::time::Duration;
+
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{compose_call, AccountId, Balance, GenericAddress, XtStatus},
+    AnyConnection, BlockNumber, KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use codec::{Compact, Encode};
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use serde::Deserialize;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    DispatchError,
+};
+
+use chain_support::{
+    real_amount, send_located_xt, send_managed_xt, send_with_event, with_event_matching,
+    NodeConnection, Preflight, Requirements, EVENT_TIMEOUT,
+};
+use common::{Scenario, ScenarioLogging};
+
+use crate::{
+    events::{Announced, AnonymousCreated, ProxyAdded, ProxyExecuted, ProxyRemoved},
+    storage::{proxies, ProxyType},
+    ProxyError,
+};
+
+/// How many anonymous (pure) proxies every account may spawn at the same time.
+const PURE_INDICES: u16 = 4;
+
+/// Delay of proxies that can be used immediately.
+const NO_DELAY: BlockNumber = 0;
+
+/// How long we are willing to wait for a single block, when waiting for the announcement delay.
+const BLOCK_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+fn default_flows() -> Vec<Flow> {
+    vec![Flow::Delegate, Flow::Announced, Flow::Pure]
+}
+
+fn default_proxy_types() -> Vec<ProxyType> {
+    vec![ProxyType::Any, ProxyType::NonTransfer, ProxyType::Staking]
+}
+
+fn default_delay() -> BlockNumber {
+    2
+}
+
+chain_support::default_pool!("Proxy");
+
+/// What is done in a single run.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Flow {
+    /// `add_proxy` of a random type, a transfer and a remark through `proxy` (each of them either
+    /// allowed or filtered out, depending on the type) and `remove_proxy`.
+    Delegate,
+    /// `add_proxy` with a delay, `announce` of a transfer, `proxy_announced` after the delay and
+    /// `remove_proxies`.
+    Announced,
+    /// `anonymous` (`create_pure`), a transfer from the new account through `proxy` and
+    /// `kill_anonymous` (`kill_pure`), again through `proxy`.
+    Pure,
+}
+
+/// A scenario that goes through the features of `pallet_proxy`.
+///
+/// Every run takes two random accounts of the pool (a real account and its proxy) and goes
+/// through one of `flows` (chosen randomly). Every step is confirmed by the corresponding `Proxy`
+/// event. Results of proxied calls (from `ProxyExecuted`) are checked against the filter of the
+/// proxy type. Removal of all proxies is confirmed by reading `Proxy::Proxies`.
+///
+/// Proxies left by a failed run are removed at the beginning of the next run of the same account.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Proxy {
+    /// Tokens sent in every transfer.
+    transfer_value: u64,
+    /// Flows chosen from. By default, all of them.
+    #[serde(default = "default_flows")]
+    flows: Vec<Flow>,
+    /// Proxy types chosen from in `Flow::Delegate`. By default, all of them.
+    #[serde(default = "default_proxy_types")]
+    proxy_types: Vec<ProxyType>,
+    /// Announcement delay (in blocks) in `Flow::Announced`. By default, 2.
+    #[serde(default = "default_delay")]
+    delay: BlockNumber,
+    /// Account pool of real accounts and proxies. By default, `Proxy`.
+    #[serde(default = "default_pool")]
+    pool: String,
+}
+
+/// Checks that `call` through a `proxy_type` proxy has been `allowed` (or filtered out).
+fn check_result(
+    call: &'static str,
+    proxy_type: ProxyType,
+    allowed: bool,
+    result: Result<(), DispatchError>,
+) -> AnyResult<()> {
+    ensure!(
+        result.is_ok() == allowed,
+        ProxyError::UnexpectedResult {
+            call,
+            proxy_type,
+            expected: if allowed { "allowed" } else { "filtered out" },
+            result,
+        }
+    );
+    Ok(())
+}
+
+/// Dispatches `call` as `real` through `proxy` (with `Proxy::proxy`). Returns the result of the
+/// call.
+async fn proxied<Call: Encode + Clone>(
+    connection: &NodeConnection,
+    proxy: &KeyPair,
+    real: &AccountId,
+    call: Call,
+) -> AnyResult<Result<(), DispatchError>> {
+    let call = compose_call!(
+        connection.as_connection().metadata,
+        "Proxy",
+        "proxy",
+        real.clone(),
+        None::<ProxyType>,
+        call
+    );
+    executed(connection, proxy, call, "Proxy::proxy").await
+}
+
+/// Sends `call` (named `label`) signed by `signer` and returns the result of the call that it has
+/// dispatched through a proxy.
+///
+/// `ProxyExecuted` does not say whose call it reports, so it is looked for only among the events of
+/// this very extrinsic.
+async fn executed<Call: Encode + Clone>(
+    connection: &NodeConnection,
+    signer: &KeyPair,
+    call: Call,
+    label: &'static str,
+) -> AnyResult<Result<(), DispatchError>> {
+    let (_, location) = send_located_xt(connection, signer, call, label).await?;
+    let event = connection
+        .extrinsic_event(location, |_: &ProxyExecuted| true)
+        .await?;
+    Ok(event.result)
+}
+
+/// `Balances::transfer` of `value` to `to`.
+fn transfer(
+    connection: &NodeConnection,
+    to: &AccountId,
+    value: Balance,
+) -> ([u8; 2], GenericAddress, Compact<Balance>) {
+    compose_call!(
+        connection.as_connection().metadata,
+        "Balances",
+        "transfer",
+        GenericAddress::Id(to.clone()),
+        Compact(value)
+    )
+}
+
+impl Proxy {
+    async fn add_proxy(
+        connection: &NodeConnection,
+        real: &KeyPair,
+        proxy: &AccountId,
+        proxy_type: ProxyType,
+        delay: BlockNumber,
+    ) -> AnyResult<()> {
+        let real_account = account_from_keypair(real);
+        let proxy_account = proxy.clone();
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Proxy",
+            "add_proxy",
+            proxy.clone(),
+            proxy_type,
+            delay
+        );
+        send_with_event(
+            connection,
+            real,
+            call,
+            "Proxy::add_proxy",
+            move |event: &ProxyAdded| {
+                event.delegator == real_account
+                    && event.delegatee == proxy_account
+                    && event.proxy_type == proxy_type
+                    && event.delay == delay
+            },
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn remove_proxies(connection: &NodeConnection, real: &KeyPair) -> AnyResult<()> {
+        let real_account = account_from_keypair(real);
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Proxy",
+            "remove_proxies"
+        );
+        send_managed_xt(
+            connection,
+            real,
+            call,
+            "Proxy::remove_proxies",
+            XtStatus::Finalized,
+        )
+        .await?;
+
+        ensure!(
+            proxies(connection, &real_account).await?.is_empty(),
+            ProxyError::ProxiesNotRemoved(real_account)
+        );
+        Ok(())
+    }
+
+    /// Removes proxies left by some failed run.
+    async fn clean_up(
+        connection: &NodeConnection,
+        real: &KeyPair,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let real_account = account_from_keypair(real);
+        if !proxies(connection, &real_account).await?.is_empty() {
+            logger.info(format!("Removing stale proxies of {}", real_account));
+            Self::remove_proxies(connection, real).await?;
+        }
+        Ok(())
+    }
+
+    async fn delegate(
+        &self,
+        connection: &NodeConnection,
+        real: &KeyPair,
+        proxy: &KeyPair,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let real_account = account_from_keypair(real);
+        let proxy_account = account_from_keypair(proxy);
+        let proxy_type = *self
+            .proxy_types
+            .choose(&mut thread_rng())
+            .expect("There are some proxy types");
+        Self::add_proxy(connection, real, &proxy_account, proxy_type, NO_DELAY).await?;
+        logger.debug(format!("Added {:?} proxy", proxy_type));
+
+        let transfer = transfer(
+            connection,
+            &proxy_account,
+            real_amount(&self.transfer_value),
+        );
+        let result = proxied(connection, proxy, &real_account, transfer).await?;
+        check_result(
+            "Balances::transfer",
+            proxy_type,
+            proxy_type.allows_transfer(),
+            result,
+        )?;
+
+        let remark = compose_call!(
+            connection.as_connection().metadata,
+            "System",
+            "remark",
+            b"Proxy".to_vec()
+        );
+        let result = proxied(connection, proxy, &real_account, remark).await?;
+        check_result(
+            "System::remark",
+            proxy_type,
+            proxy_type.allows_remark(),
+            result,
+        )?;
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Proxy",
+            "remove_proxy",
+            proxy_account.clone(),
+            proxy_type,
+            NO_DELAY
+        );
+        send_with_event(
+            connection,
+            real,
+            call,
+            "Proxy::remove_proxy",
+            move |event: &ProxyRemoved| {
+                event.delegator == real_account
+                    && event.delegatee == proxy_account
+                    && event.proxy_type == proxy_type
+            },
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn announced(
+        &self,
+        connection: &NodeConnection,
+        real: &KeyPair,
+        proxy: &KeyPair,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let real_account = account_from_keypair(real);
+        let proxy_account = account_from_keypair(proxy);
+        Self::add_proxy(connection, real, &proxy_account, ProxyType::Any, self.delay).await?;
+
+        let transfer = transfer(
+            connection,
+            &proxy_account,
+            real_amount(&self.transfer_value),
+        );
+        let call_hash = BlakeTwo256::hash_of(&transfer).to_fixed_bytes();
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Proxy",
+            "announce",
+            real_account.clone(),
+            call_hash
+        );
+        let (announced_real, announcing_proxy) = (real_account.clone(), proxy_account.clone());
+        // The delay is counted from the block that has included the announcement.
+        let ((_, location), _) = with_event_matching(
+            connection,
+            move |event: &Announced| {
+                event.real == announced_real
+                    && event.proxy == announcing_proxy
+                    && event.call_hash == call_hash
+            },
+            EVENT_TIMEOUT,
+            send_located_xt(connection, proxy, call, "Proxy::announce"),
+        )
+        .await?;
+
+        let announced_at = location.block;
+        logger.debug(format!(
+            "Announced at #{}, waiting {} blocks",
+            announced_at, self.delay
+        ));
+        connection
+            .wait_for_block(announced_at + self.delay, BLOCK_TIME_LIMIT)
+            .await?;
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Proxy",
+            "proxy_announced",
+            proxy_account,
+            real_account,
+            None::<ProxyType>,
+            transfer
+        );
+        let result = executed(connection, proxy, call, "Proxy::proxy_announced").await?;
+        check_result("Balances::transfer", ProxyType::Any, true, result)?;
+
+        Self::remove_proxies(connection, real).await
+    }
+
+    async fn pure(
+        &self,
+        connection: &NodeConnection,
+        spawner: &KeyPair,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let spawner_account = account_from_keypair(spawner);
+        let index = thread_rng().gen_range(0..PURE_INDICES);
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Proxy",
+            "anonymous",
+            ProxyType::Any,
+            NO_DELAY,
+            index
+        );
+        let who = spawner_account.clone();
+        // `kill_anonymous` needs to know where exactly the account has been created.
+        let ((_, location), created) = with_event_matching(
+            connection,
+            move |event: &AnonymousCreated| {
+                event.who == who
+                    && event.proxy_type == ProxyType::Any
+                    && event.disambiguation_index == index
+            },
+            EVENT_TIMEOUT,
+            send_located_xt(connection, spawner, call, "Proxy::anonymous"),
+        )
+        .await?;
+        let pure = created.anonymous;
+        logger.debug(format!(
+            "Created {} at #{} (extrinsic {})",
+            pure, location.block, location.index
+        ));
+
+        let value = real_amount(&self.transfer_value);
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Balances",
+            "transfer",
+            GenericAddress::Id(pure.clone()),
+            Compact(2 * value)
+        );
+        send_managed_xt(
+            connection,
+            spawner,
+            call,
+            "Balances::transfer",
+            XtStatus::Finalized,
+        )
+        .await?;
+
+        let transfer = transfer(connection, &spawner_account, value);
+        let result = proxied(connection, spawner, &pure, transfer).await?;
+        check_result("Balances::transfer", ProxyType::Any, true, result)?;
+
+        let kill = compose_call!(
+            connection.as_connection().metadata,
+            "Proxy",
+            "kill_anonymous",
+            spawner_account,
+            ProxyType::Any,
+            index,
+            Compact(location.block),
+            Compact(location.index)
+        );
+        let result = proxied(connection, spawner, &pure, kill).await?;
+        check_result("Proxy::kill_anonymous", ProxyType::Any, true, result)?;
+
+        ensure!(
+            proxies(connection, &pure).await?.is_empty(),
+            ProxyError::ProxiesNotRemoved(pure)
+        );
+        Ok(())
+    }
+
+    async fn perform(
+        &self,
+        flow: Flow,
+        connection: &NodeConnection,
+        real: &KeyPair,
+        proxy: &KeyPair,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        match flow {
+            Flow::Delegate => {
+                Self::clean_up(connection, real, logger).await?;
+                self.delegate(connection, real, proxy, logger).await
+            }
+            Flow::Announced => {
+                Self::clean_up(connection, real, logger).await?;
+                self.announced(connection, real, proxy, logger).await
+            }
+            Flow::Pure => self.pure(connection, real, logger).await,
+        }
+    }
+}
+
+impl Preflight for Proxy {
+    fn requirements(&self) -> Requirements {
+        let mut requirements = Requirements::default()
+            .call("Balances", "transfer")
+            .call("Proxy", "proxy")
+            .pool(&self.pool, 2)
+            .ensure(!self.flows.is_empty(), "There should be some flows")
+            .ensure(
+                !self.proxy_types.is_empty(),
+                "There should be some proxy types",
+            );
+        for flow in &self.flows {
+            requirements = match flow {
+                Flow::Delegate => requirements
+                    .call("System", "remark")
+                    .call("Proxy", "add_proxy")
+                    .call("Proxy", "remove_proxy")
+                    .call("Proxy", "remove_proxies"),
+                Flow::Announced => requirements
+                    .call("Proxy", "add_proxy")
+                    .call("Proxy", "announce")
+                    .call("Proxy", "proxy_announced")
+                    .call("Proxy", "remove_proxies"),
+                Flow::Pure => requirements
+                    .call("Proxy", "anonymous")
+                    .call("Proxy", "kill_anonymous"),
+            };
+        }
+        requirements
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Proxy {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let keypairs = connection.accounts(&self.pool)?.random_keypairs(2)?;
+        let (real, proxy) = (&keypairs[0], &keypairs[1]);
+        let flow = *self
+            .flows
+            .choose(&mut thread_rng())
+            .expect("There are some flows");
+
+        let real_account = account_from_keypair(real);
+        logger.info(format!("{:?} with {}", flow, real_account));
+        logger.log_result(self.perform(flow, connection, real, proxy, logger).await)?;
+        logger.info(format!("{:?} with {} confirmed", flow, real_account));
+        Ok(())
+    }
+}
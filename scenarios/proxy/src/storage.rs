@@ -0,0 +1,60 @@
+//! Here we have 'copies' of the types of `pallet_proxy` and of the runtime (together with reading
+//! the storage), so that we can both compose calls and check their effects.
+use aleph_client::{
+    substrate_api_client::{AccountId, Balance},
+    BlockNumber,
+};
+use anyhow::Result as AnyResult;
+use codec::{Decode, Encode};
+use serde::Deserialize;
+
+use chain_support::NodeConnection;
+
+/// Copy of the `ProxyType` of the runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, Deserialize)]
+pub enum ProxyType {
+    /// Allows all calls.
+    Any,
+    /// Allows all calls but the ones transferring funds.
+    NonTransfer,
+    /// Allows only staking-related calls.
+    Staking,
+}
+
+impl ProxyType {
+    /// Whether `Balances::transfer` passes the filter of this proxy type.
+    pub fn allows_transfer(&self) -> bool {
+        matches!(self, ProxyType::Any)
+    }
+
+    /// Whether `System::remark` passes the filter of this proxy type.
+    pub fn allows_remark(&self) -> bool {
+        matches!(self, ProxyType::Any | ProxyType::NonTransfer)
+    }
+}
+
+/// Copy of `pallet_proxy::ProxyDefinition`. We only check whether there are any.
+#[derive(Clone, Debug, Decode)]
+pub struct ProxyDefinition {
+    _delegate: AccountId,
+    _proxy_type: ProxyType,
+    _delay: BlockNumber,
+}
+
+/// All proxies of `real`.
+pub async fn proxies(
+    connection: &NodeConnection,
+    real: &AccountId,
+) -> AnyResult<Vec<ProxyDefinition>> {
+    let real = real.clone();
+    connection
+        .read_blocking(move |api| {
+            Ok(api
+                .get_storage_map::<_, (Vec<ProxyDefinition>, Balance)>(
+                    "Proxy", "Proxies", real, None,
+                )?
+                .map(|(proxies, _deposit)| proxies)
+                .unwrap_or_default())
+        })
+        .await
+}
@@ -0,0 +1,34 @@
+//! Here we have 'copies' of the events from `pallet_staking`, since we need `Event` trait
+//! implemented for them.
+use aleph_client::substrate_api_client::{AccountId, Balance};
+use codec::Decode;
+
+use chain_support::Event;
+
+/// Emitted by `bond`, `bond_extra` and `rebond`.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Staking"]
+pub struct Bonded {
+    pub stash: AccountId,
+    pub amount: Balance,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Staking"]
+pub struct Unbonded {
+    pub stash: AccountId,
+    pub amount: Balance,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Staking"]
+pub struct Withdrawn {
+    pub stash: AccountId,
+    pub amount: Balance,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Staking"]
+pub struct Chilled {
+    pub stash: AccountId,
+}
@@ -0,0 +1,140 @@
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{compose_call, AccountId, Balance, GenericAddress},
+    AnyConnection, KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use codec::Compact;
+use thiserror::Error;
+
+use chain_support::{send_with_event, ChainScenarioKind, ChainScenarioRegistry, NodeConnection};
+pub use nominator::Nominator;
+pub use validator::Validator;
+
+use crate::{
+    events::{Bonded, Chilled},
+    storage::{ledger, RewardDestination},
+};
+
+mod events;
+mod nominator;
+mod storage;
+mod validator;
+
+/// Gathers all possible errors from this crate.
+#[derive(Debug, Error)]
+pub enum StakingError {
+    #[error("🥩❌ Bond of {0} is lower than the minimal one ({1}).")]
+    BondTooLow(Balance, Balance),
+    #[error("🥩❌ There are no validators to nominate.")]
+    NoValidators,
+    #[error("🥩❌ Account `{0:?}` should be bonded.")]
+    NotBonded(AccountId),
+    #[error("🥩❌ After `{step}`, {what} of `{account:?}` is {found} instead of {expected}.")]
+    UnexpectedLedger {
+        step: &'static str,
+        what: &'static str,
+        account: AccountId,
+        found: Balance,
+        expected: Balance,
+    },
+    #[error("🥩❌ Account `{0:?}` does not nominate the chosen validators.")]
+    UnexpectedNominations(AccountId),
+    #[error("🥩❌ Account `{0:?}` still nominates after chilling.")]
+    StillNominating(AccountId),
+    #[error("🥩❌ Account `{0:?}` still validates after chilling.")]
+    StillValidating(AccountId),
+    #[error("🥩❌ Account `{0:?}` has different validator preferences than it has declared.")]
+    UnexpectedPrefs(AccountId),
+    #[error("🥩❌ Account `{0:?}` has different session keys than it has set.")]
+    UnexpectedKeys(AccountId),
+    #[error("🥩❌ Account `{0:?}` still has session keys after purging them.")]
+    KeysNotPurged(AccountId),
+}
+
+/// Checks that `what` (some balance from the ledger of `account`) is as `expected` after `step`.
+fn check_ledger(
+    step: &'static str,
+    what: &'static str,
+    account: &AccountId,
+    found: Balance,
+    expected: Balance,
+) -> AnyResult<()> {
+    ensure!(
+        found == expected,
+        StakingError::UnexpectedLedger {
+            step,
+            what,
+            account: account.clone(),
+            found,
+            expected,
+        }
+    );
+    Ok(())
+}
+
+/// Bonds `value` from `stash` (which becomes its own controller, with rewards going to the stake)
+/// and checks its ledger.
+async fn bond(connection: &NodeConnection, stash: &KeyPair, value: Balance) -> AnyResult<()> {
+    let account = account_from_keypair(stash);
+    let call = compose_call!(
+        connection.as_connection().metadata,
+        "Staking",
+        "bond",
+        GenericAddress::Id(account.clone()),
+        Compact(value),
+        RewardDestination::Staked
+    );
+    let stash_account = account.clone();
+    send_with_event(
+        connection,
+        stash,
+        call,
+        "Staking::bond",
+        move |event: &Bonded| event.stash == stash_account && event.amount == value,
+    )
+    .await?;
+
+    let ledger = ledger(connection, &account)
+        .await?
+        .ok_or(StakingError::NotBonded(account.clone()))?;
+    check_ledger("bond", "active bond", &account, ledger.active, value)
+}
+
+/// Stops `stash` from both nominating and validating. The caller should check which one it was.
+async fn chill(connection: &NodeConnection, stash: &KeyPair) -> AnyResult<()> {
+    let call = compose_call!(connection.as_connection().metadata, "Staking", "chill");
+    let stash_account = account_from_keypair(stash);
+    send_with_event(
+        connection,
+        stash,
+        call,
+        "Staking::chill",
+        move |event: &Chilled| event.stash == stash_account,
+    )
+    .await
+    .map(|_| ())
+}
+
+/// Registers all the staking scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "StakingNominator",
+        &[
+            "bond_value: tokens bonded at first (at least `MinNominatorBond`)",
+            "extra_value: tokens used by `bond_extra`, `unbond` and `rebond`",
+            "targets (optional): how many validators are nominated, by default 3",
+            "pool (optional): account pool of the nominators, by default `StakingNominator`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Nominator>()?)),
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "StakingValidator",
+        &[
+            "bond_value: tokens bonded (at least `MinValidatorBond`)",
+            "commission (optional): commission in percents, by default 10",
+            "pool (optional): account pool of the validators, by default `StakingValidator`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Validator>()?)),
+    ))
+}
@@ -0,0 +1,350 @@
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{compose_call, AccountId, GenericAddress, XtStatus},
+    AnyConnection, KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use codec::Compact;
+use rand::{seq::SliceRandom, thread_rng};
+use serde::Deserialize;
+
+use chain_support::{
+    real_amount, send_managed_xt, send_with_event, NodeConnection, Preflight, Requirements,
+};
+use common::{Scenario, ScenarioLogging};
+
+use crate::{
+    bond, check_ledger, chill,
+    events::{Bonded, Unbonded, Withdrawn},
+    storage::{current_era, ledger, min_nominator_bond, nominations, session_validators, Ledger},
+    StakingError,
+};
+
+/// `MAX_UNLOCKING_CHUNKS` of `pallet_staking`: no more unbonding is possible until some chunks are
+/// withdrawn or rebonded.
+const MAX_UNLOCKING_CHUNKS: usize = 32;
+
+fn default_targets() -> usize {
+    3
+}
+
+chain_support::default_pool!("StakingNominator");
+
+/// A single step of the nominator lifecycle.
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Bond,
+    Nominate,
+    BondExtra,
+    Unbond,
+    Rebond,
+    Chill,
+    Withdraw,
+}
+
+/// A scenario that goes through the whole lifecycle of a nominator.
+///
+/// Every run takes a random account of the pool and moves it one step further: an account that
+/// is not bonded yet bonds `bond_value` (with itself as the controller), a bonded one nominates
+/// random validators of the current session, and a nominating one randomly does one of the
+/// following: bonds extra, unbonds, rebonds (if it is unbonding), chills or withdraws (if some of
+/// its unbonded funds have already passed the bonding period). Active bond never goes below
+/// `bond_value`.
+///
+/// Every step is confirmed by the corresponding `Staking` event (if there is any) and by reading
+/// the ledger (or nominations) of the account afterwards.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Nominator {
+    /// Tokens bonded at first. Should be at least `MinNominatorBond`.
+    bond_value: u64,
+    /// Tokens used by `bond_extra`, `unbond` and `rebond`.
+    extra_value: u64,
+    /// How many validators are nominated (at most). By default, 3.
+    #[serde(default = "default_targets")]
+    targets: usize,
+    /// Account pool of the nominators. By default, `StakingNominator`.
+    #[serde(default = "default_pool")]
+    pool: String,
+}
+
+impl Nominator {
+    async fn bonded_ledger(connection: &NodeConnection, account: &AccountId) -> AnyResult<Ledger> {
+        ledger(connection, account)
+            .await?
+            .ok_or_else(|| StakingError::NotBonded(account.clone()).into())
+    }
+
+    /// Chooses the next step for `account`, basing on its ledger and nominations.
+    async fn next_step(&self, connection: &NodeConnection, account: &AccountId) -> AnyResult<Step> {
+        let ledger = match ledger(connection, account).await? {
+            Some(ledger) => ledger,
+            None => return Ok(Step::Bond),
+        };
+        if nominations(connection, account).await?.is_none() {
+            return Ok(Step::Nominate);
+        }
+
+        let mut steps = vec![Step::BondExtra, Step::Chill];
+        if ledger.unlocking.len() < MAX_UNLOCKING_CHUNKS
+            && ledger.active >= real_amount(&self.bond_value) + real_amount(&self.extra_value)
+        {
+            steps.push(Step::Unbond);
+        }
+        if !ledger.unlocking.is_empty() {
+            steps.push(Step::Rebond);
+        }
+        if ledger.withdrawable(current_era(connection).await?) {
+            steps.push(Step::Withdraw);
+        }
+        Ok(*steps
+            .choose(&mut thread_rng())
+            .expect("There are always some steps"))
+    }
+
+    async fn nominate(&self, connection: &NodeConnection, stash: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(stash);
+        let mut targets = session_validators(connection).await?;
+        ensure!(!targets.is_empty(), StakingError::NoValidators);
+        targets.shuffle(&mut thread_rng());
+        targets.truncate(self.targets);
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Staking",
+            "nominate",
+            targets
+                .iter()
+                .cloned()
+                .map(GenericAddress::Id)
+                .collect::<Vec<_>>()
+        );
+        send_managed_xt(
+            connection,
+            stash,
+            call,
+            "Staking::nominate",
+            XtStatus::Finalized,
+        )
+        .await?;
+
+        let mut nominated = nominations(connection, &account)
+            .await?
+            .map(|nominations| nominations.targets)
+            .unwrap_or_default();
+        nominated.sort();
+        targets.sort();
+        ensure!(
+            nominated == targets,
+            StakingError::UnexpectedNominations(account)
+        );
+        Ok(())
+    }
+
+    async fn bond_extra(&self, connection: &NodeConnection, stash: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(stash);
+        let before = Self::bonded_ledger(connection, &account).await?;
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Staking",
+            "bond_extra",
+            Compact(real_amount(&self.extra_value))
+        );
+        let stash_account = account.clone();
+        let (_, event) = send_with_event(
+            connection,
+            stash,
+            call,
+            "Staking::bond_extra",
+            move |event: &Bonded| event.stash == stash_account,
+        )
+        .await?;
+
+        let after = Self::bonded_ledger(connection, &account).await?;
+        check_ledger(
+            "bond_extra",
+            "active bond",
+            &account,
+            after.active,
+            before.active + event.amount,
+        )
+    }
+
+    async fn unbond(&self, connection: &NodeConnection, stash: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(stash);
+        let before = Self::bonded_ledger(connection, &account).await?;
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Staking",
+            "unbond",
+            Compact(real_amount(&self.extra_value))
+        );
+        let stash_account = account.clone();
+        let (_, event) = send_with_event(
+            connection,
+            stash,
+            call,
+            "Staking::unbond",
+            move |event: &Unbonded| event.stash == stash_account,
+        )
+        .await?;
+
+        let after = Self::bonded_ledger(connection, &account).await?;
+        check_ledger(
+            "unbond",
+            "active bond",
+            &account,
+            after.active,
+            before.active - event.amount,
+        )?;
+        check_ledger(
+            "unbond",
+            "unlocking balance",
+            &account,
+            after.unlocking(),
+            before.unlocking() + event.amount,
+        )
+    }
+
+    async fn rebond(&self, connection: &NodeConnection, stash: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(stash);
+        let before = Self::bonded_ledger(connection, &account).await?;
+        let value = real_amount(&self.extra_value).min(before.unlocking());
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Staking",
+            "rebond",
+            Compact(value)
+        );
+        let stash_account = account.clone();
+        send_with_event(
+            connection,
+            stash,
+            call,
+            "Staking::rebond",
+            move |event: &Bonded| event.stash == stash_account && event.amount == value,
+        )
+        .await?;
+
+        let after = Self::bonded_ledger(connection, &account).await?;
+        check_ledger(
+            "rebond",
+            "active bond",
+            &account,
+            after.active,
+            before.active + value,
+        )?;
+        check_ledger(
+            "rebond",
+            "unlocking balance",
+            &account,
+            after.unlocking(),
+            before.unlocking() - value,
+        )
+    }
+
+    async fn chill(&self, connection: &NodeConnection, stash: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(stash);
+        chill(connection, stash).await?;
+        ensure!(
+            nominations(connection, &account).await?.is_none(),
+            StakingError::StillNominating(account)
+        );
+        Ok(())
+    }
+
+    async fn withdraw(&self, connection: &NodeConnection, stash: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(stash);
+        let before = Self::bonded_ledger(connection, &account).await?;
+        // There are no slashing spans, as we never validate.
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Staking",
+            "withdraw_unbonded",
+            0u32
+        );
+        let stash_account = account.clone();
+        let (_, event) = send_with_event(
+            connection,
+            stash,
+            call,
+            "Staking::withdraw_unbonded",
+            move |event: &Withdrawn| event.stash == stash_account,
+        )
+        .await?;
+
+        let after = Self::bonded_ledger(connection, &account).await?;
+        check_ledger(
+            "withdraw_unbonded",
+            "total bond",
+            &account,
+            after.total,
+            before.total - event.amount,
+        )
+    }
+
+    async fn perform(
+        &self,
+        step: Step,
+        connection: &NodeConnection,
+        stash: &KeyPair,
+    ) -> AnyResult<()> {
+        match step {
+            Step::Bond => bond(connection, stash, real_amount(&self.bond_value)).await,
+            Step::Nominate => self.nominate(connection, stash).await,
+            Step::BondExtra => self.bond_extra(connection, stash).await,
+            Step::Unbond => self.unbond(connection, stash).await,
+            Step::Rebond => self.rebond(connection, stash).await,
+            Step::Chill => self.chill(connection, stash).await,
+            Step::Withdraw => self.withdraw(connection, stash).await,
+        }
+    }
+}
+
+impl Preflight for Nominator {
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+            .call("Staking", "bond")
+            .call("Staking", "nominate")
+            .call("Staking", "bond_extra")
+            .call("Staking", "unbond")
+            .call("Staking", "rebond")
+            .call("Staking", "chill")
+            .call("Staking", "withdraw_unbonded")
+            .pool(&self.pool, 1)
+            .ensure(
+                self.targets > 0,
+                "At least one validator should be nominated",
+            )
+            .ensure(self.extra_value > 0, "`extra_value` should be positive")
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Nominator {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let stash = connection.accounts(&self.pool)?.random_keypair();
+        let account = account_from_keypair(&stash);
+        let step = logger.log_result(self.next_step(connection, &account).await)?;
+
+        logger.info(format!("{:?} with {}", step, account));
+        logger.log_result(self.perform(step, connection, &stash).await)?;
+        logger.info(format!("{:?} with {} confirmed", step, account));
+        Ok(())
+    }
+
+    /// Checks that `bond_value` is enough to become a nominator.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        _logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let bond = real_amount(&self.bond_value);
+        let min_bond = min_nominator_bond(connection).await?;
+        ensure!(bond >= min_bond, StakingError::BondTooLow(bond, min_bond));
+        Ok(())
+    }
+}
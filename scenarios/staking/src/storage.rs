@@ -0,0 +1,183 @@
+//! Here we have 'copies' of the storage items of `pallet_staking` (together with reading them),
+//! so that every step of a scenario can be confirmed by looking into the chain state.
+use aleph_client::substrate_api_client::{AccountId, Balance};
+use anyhow::Result as AnyResult;
+use codec::{Decode, Encode, Error as CodecError, Input, Output};
+
+use chain_support::NodeConnection;
+
+/// Copy of `pallet_staking::UnlockChunk`.
+#[derive(Clone, Debug, Decode)]
+pub struct UnlockChunk {
+    #[codec(compact)]
+    pub value: Balance,
+    #[codec(compact)]
+    pub era: u32,
+}
+
+/// Copy of `pallet_staking::StakingLedger`.
+#[derive(Clone, Debug, Decode)]
+pub struct Ledger {
+    _stash: AccountId,
+    #[codec(compact)]
+    pub total: Balance,
+    #[codec(compact)]
+    pub active: Balance,
+    pub unlocking: Vec<UnlockChunk>,
+    _claimed_rewards: Vec<u32>,
+}
+
+impl Ledger {
+    /// Sum of all the chunks that are being unbonded.
+    pub fn unlocking(&self) -> Balance {
+        self.unlocking.iter().map(|chunk| chunk.value).sum()
+    }
+
+    /// Whether some of the chunks can be already withdrawn in `current_era`.
+    pub fn withdrawable(&self, current_era: u32) -> bool {
+        self.unlocking.iter().any(|chunk| chunk.era <= current_era)
+    }
+}
+
+/// Copy of `pallet_staking::Nominations`.
+#[derive(Clone, Debug, Decode)]
+pub struct Nominations {
+    pub targets: Vec<AccountId>,
+    _submitted_in: u32,
+    _suppressed: bool,
+}
+
+/// Copy of `pallet_staking::RewardDestination`.
+#[derive(Clone, Debug, Encode)]
+#[allow(dead_code)]
+pub enum RewardDestination {
+    Staked,
+    Stash,
+    Controller,
+    Account(AccountId),
+    None,
+}
+
+/// Copy of `pallet_staking::ValidatorPrefs`. `commission` is in parts per billion.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ValidatorPrefs {
+    #[codec(compact)]
+    pub commission: u32,
+    pub blocked: bool,
+}
+
+/// Public session keys, as returned by `author_rotateKeys`.
+///
+/// The runtime knows their structure (`SessionKeys`), but we only pass them on. Hence, they are
+/// encoded as they are (without length prefix) and decoded from the whole storage value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionKeys(pub Vec<u8>);
+
+impl Encode for SessionKeys {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+impl Decode for SessionKeys {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let len = input
+            .remaining_len()?
+            .ok_or("Length of session keys should be known")?;
+        let mut keys = vec![0u8; len];
+        input.read(&mut keys)?;
+        Ok(SessionKeys(keys))
+    }
+}
+
+/// Ledger of `controller`, if it is bonded.
+pub async fn ledger(
+    connection: &NodeConnection,
+    controller: &AccountId,
+) -> AnyResult<Option<Ledger>> {
+    let controller = controller.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_storage_map("Staking", "Ledger", controller, None)?))
+        .await
+}
+
+/// Nominations of `stash`, if it is nominating.
+pub async fn nominations(
+    connection: &NodeConnection,
+    stash: &AccountId,
+) -> AnyResult<Option<Nominations>> {
+    let stash = stash.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_storage_map("Staking", "Nominators", stash, None)?))
+        .await
+}
+
+/// Index of the current era (0 before the first one is planned).
+pub async fn current_era(connection: &NodeConnection) -> AnyResult<u32> {
+    connection
+        .read_blocking(|api| {
+            Ok(api
+                .get_storage_value("Staking", "CurrentEra", None)?
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/// Preferences of `stash`, if it is validating.
+pub async fn validator_prefs(
+    connection: &NodeConnection,
+    stash: &AccountId,
+) -> AnyResult<Option<ValidatorPrefs>> {
+    let stash = stash.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_storage_map("Staking", "Validators", stash, None)?))
+        .await
+}
+
+/// Session keys of `validator` for the next session, if it has set any.
+pub async fn next_keys(
+    connection: &NodeConnection,
+    validator: &AccountId,
+) -> AnyResult<Option<SessionKeys>> {
+    let validator = validator.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_storage_map("Session", "NextKeys", validator, None)?))
+        .await
+}
+
+/// Minimal bond of a validator.
+pub async fn min_validator_bond(connection: &NodeConnection) -> AnyResult<Balance> {
+    connection
+        .read_blocking(|api| {
+            Ok(api
+                .get_storage_value("Staking", "MinValidatorBond", None)?
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/// Minimal bond of a nominator.
+pub async fn min_nominator_bond(connection: &NodeConnection) -> AnyResult<Balance> {
+    connection
+        .read_blocking(|api| {
+            Ok(api
+                .get_storage_value("Staking", "MinNominatorBond", None)?
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/// Validators of the current session.
+pub async fn session_validators(connection: &NodeConnection) -> AnyResult<Vec<AccountId>> {
+    connection
+        .read_blocking(|api| {
+            Ok(api
+                .get_storage_value("Session", "Validators", None)?
+                .unwrap_or_default())
+        })
+        .await
+}
@@ -0,0 +1,233 @@
+use aleph_client::{
+    account_from_keypair,
+    substrate_api_client::{compose_call, AccountId, XtStatus},
+    AnyConnection, KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use rand::{seq::SliceRandom, thread_rng};
+use serde::Deserialize;
+
+use chain_support::{real_amount, send_managed_xt, NodeConnection, Preflight, Requirements};
+use common::{Scenario, ScenarioLogging};
+
+use crate::{
+    bond, chill,
+    storage::{
+        ledger, min_validator_bond, next_keys, validator_prefs, SessionKeys, ValidatorPrefs,
+    },
+    StakingError,
+};
+
+/// `Perbill` corresponding to 1%.
+const PERCENT: u32 = 10_000_000;
+
+fn default_commission() -> u8 {
+    10
+}
+
+chain_support::default_pool!("StakingValidator");
+
+/// A single step of the validator lifecycle.
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Bond,
+    SetKeys,
+    Validate,
+    Chill,
+    PurgeKeys,
+}
+
+/// A scenario that goes through the whole lifecycle of a would-be validator.
+///
+/// Every run takes a random account of the pool and moves it one step further: an account that
+/// is not bonded yet bonds `bond_value` (with itself as the controller), a bonded one without
+/// session keys generates them on the node (`author_rotateKeys`) and sets them, one with keys
+/// either declares itself a validator (with `commission`) or purges its keys, and a validating
+/// one either changes its session keys or chills.
+///
+/// Every step is confirmed by the corresponding `Staking` event (if there is any) and by reading
+/// `Staking::Validators` or `Session::NextKeys` afterwards.
+///
+/// Since `author_rotateKeys` is an unsafe RPC method, nodes used by this scenario must be run with
+/// `--rpc-methods Unsafe`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Validator {
+    /// Tokens bonded. Should be at least `MinValidatorBond`.
+    bond_value: u64,
+    /// Commission (in percents) declared when validating. By default, 10.
+    #[serde(default = "default_commission")]
+    commission: u8,
+    /// Account pool of the validators. By default, `StakingValidator`.
+    #[serde(default = "default_pool")]
+    pool: String,
+}
+
+impl Validator {
+    fn prefs(&self) -> ValidatorPrefs {
+        ValidatorPrefs {
+            commission: self.commission as u32 * PERCENT,
+            blocked: false,
+        }
+    }
+
+    /// Chooses the next step for `account`, basing on its ledger, session keys and preferences.
+    async fn next_step(connection: &NodeConnection, account: &AccountId) -> AnyResult<Step> {
+        if ledger(connection, account).await?.is_none() {
+            return Ok(Step::Bond);
+        }
+        let has_keys = next_keys(connection, account).await?.is_some();
+        let validating = validator_prefs(connection, account).await?.is_some();
+
+        let steps = match (has_keys, validating) {
+            (false, false) => vec![Step::SetKeys],
+            (false, true) => vec![Step::Chill],
+            (true, false) => vec![Step::Validate, Step::PurgeKeys],
+            (true, true) => vec![Step::SetKeys, Step::Chill],
+        };
+        Ok(*steps
+            .choose(&mut thread_rng())
+            .expect("There are always some steps"))
+    }
+
+    async fn set_keys(connection: &NodeConnection, controller: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(controller);
+        let keys = SessionKeys(connection.rotate_keys().await?);
+
+        // Keys of this account are not claimed by anyone else, so no proof is needed.
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Session",
+            "set_keys",
+            keys.clone(),
+            Vec::<u8>::new()
+        );
+        send_managed_xt(
+            connection,
+            controller,
+            call,
+            "Session::set_keys",
+            XtStatus::Finalized,
+        )
+        .await?;
+
+        ensure!(
+            next_keys(connection, &account).await? == Some(keys),
+            StakingError::UnexpectedKeys(account)
+        );
+        Ok(())
+    }
+
+    async fn validate(&self, connection: &NodeConnection, controller: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(controller);
+        let prefs = self.prefs();
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Staking",
+            "validate",
+            prefs.clone()
+        );
+        send_managed_xt(
+            connection,
+            controller,
+            call,
+            "Staking::validate",
+            XtStatus::Finalized,
+        )
+        .await?;
+
+        ensure!(
+            validator_prefs(connection, &account).await? == Some(prefs),
+            StakingError::UnexpectedPrefs(account)
+        );
+        Ok(())
+    }
+
+    async fn chill(connection: &NodeConnection, controller: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(controller);
+        chill(connection, controller).await?;
+        ensure!(
+            validator_prefs(connection, &account).await?.is_none(),
+            StakingError::StillValidating(account)
+        );
+        Ok(())
+    }
+
+    async fn purge_keys(connection: &NodeConnection, controller: &KeyPair) -> AnyResult<()> {
+        let account = account_from_keypair(controller);
+
+        let call = compose_call!(connection.as_connection().metadata, "Session", "purge_keys");
+        send_managed_xt(
+            connection,
+            controller,
+            call,
+            "Session::purge_keys",
+            XtStatus::Finalized,
+        )
+        .await?;
+
+        ensure!(
+            next_keys(connection, &account).await?.is_none(),
+            StakingError::KeysNotPurged(account)
+        );
+        Ok(())
+    }
+
+    async fn perform(
+        &self,
+        step: Step,
+        connection: &NodeConnection,
+        stash: &KeyPair,
+    ) -> AnyResult<()> {
+        match step {
+            Step::Bond => bond(connection, stash, real_amount(&self.bond_value)).await,
+            Step::SetKeys => Self::set_keys(connection, stash).await,
+            Step::Validate => self.validate(connection, stash).await,
+            Step::Chill => Self::chill(connection, stash).await,
+            Step::PurgeKeys => Self::purge_keys(connection, stash).await,
+        }
+    }
+}
+
+impl Preflight for Validator {
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+            .call("Staking", "bond")
+            .call("Staking", "validate")
+            .call("Staking", "chill")
+            .call("Session", "set_keys")
+            .call("Session", "purge_keys")
+            .pool(&self.pool, 1)
+            .ensure(self.commission <= 100, "Commission should be a percentage")
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Validator {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let stash = connection.accounts(&self.pool)?.random_keypair();
+        let account = account_from_keypair(&stash);
+        let step = logger.log_result(Self::next_step(connection, &account).await)?;
+
+        logger.info(format!("{:?} with {}", step, account));
+        logger.log_result(self.perform(step, connection, &stash).await)?;
+        logger.info(format!("{:?} with {} confirmed", step, account));
+        Ok(())
+    }
+
+    /// Checks that `bond_value` is enough to become a validator.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        _logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let bond = real_amount(&self.bond_value);
+        let min_bond = min_validator_bond(connection).await?;
+        ensure!(bond >= min_bond, StakingError::BondTooLow(bond, min_bond));
+        Ok(())
+    }
+}
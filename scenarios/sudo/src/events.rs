@@ -0,0 +1,37 @@
+//! Here we have 'copies' of the events from `pallet_sudo` and `frame_system`, since we need `Event`
+//! trait implemented for them.
+use aleph_client::substrate_api_client::AccountId;
+use codec::Decode;
+use sp_runtime::DispatchError;
+
+use chain_support::Event;
+
+/// Emitted by `sudo` and `sudo_unchecked_weight`, with the result of the dispatched call.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Sudo"]
+pub struct Sudid {
+    pub sudo_result: Result<(), DispatchError>,
+}
+
+/// Emitted by `sudo_as`, with the result of the dispatched call.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Sudo"]
+pub struct SudoAsDone {
+    pub sudo_result: Result<(), DispatchError>,
+}
+
+/// Emitted by `remark_with_event`, with the hash of the remark.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "System"]
+pub struct Remarked {
+    pub sender: AccountId,
+    pub hash: [u8; 32],
+}
+
+/// Emitted for every extrinsic whose dispatch has failed. Only the error is decoded (dispatch info
+/// follows it).
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "System"]
+pub struct ExtrinsicFailed {
+    pub dispatch_error: DispatchError,
+}
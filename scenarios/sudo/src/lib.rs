@@ -0,0 +1,59 @@
+use aleph_client::substrate_api_client::AccountId;
+use anyhow::Result as AnyResult;
+use sp_runtime::DispatchError;
+use thiserror::Error;
+
+use chain_support::{ChainScenarioKind, ChainScenarioRegistry};
+pub use sudo::{Case, Sudo};
+
+mod events;
+mod storage;
+mod sudo;
+
+/// Gathers all possible errors from this crate.
+#[derive(Debug, Error)]
+pub enum SudoError {
+    #[error("👑❌ There is no sudo key on the chain.")]
+    NoSudoKey,
+    #[error("👑❌ Configured account `{configured:?}` is not the sudo key `{actual:?}`.")]
+    WrongSudoKey {
+        configured: AccountId,
+        actual: AccountId,
+    },
+    #[error("👑❌ Pool `{0}` contains the sudo key.")]
+    SudoInPool(String),
+    #[error("👑❌ Pallet `{0}` is missing in the metadata.")]
+    MissingPallet(&'static str),
+    #[error("👑❌ In `{case:?}`, the dispatched call resulted in `{found:?}` instead of `{expected:?}`.")]
+    UnexpectedResult {
+        case: Case,
+        found: Result<(), DispatchError>,
+        expected: Result<(), DispatchError>,
+    },
+    #[error(
+        "👑❌ In `{case:?}`, balance of `{account:?}` has changed by {found} instead of {expected}."
+    )]
+    UnexpectedBalanceChange {
+        case: Case,
+        account: AccountId,
+        found: i128,
+        expected: i128,
+    },
+}
+
+/// Registers all the sudo scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "Sudo",
+        &[
+            "sudo: where the seed phrase of the sudo account is kept (like `{ Env = \
+            \"SUDO_SEED\" }`)",
+            "transfer_value: tokens moved by every `force_transfer` dispatched as root",
+            "cases (optional): subset of `Sudo`, `SudoUncheckedWeight`, `SudoAs` and `NotSudo`, by \
+            default all of them",
+            "pool (optional): account pool of the affected accounts and the non-sudo senders, by \
+            default `Sudo`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Sudo>()?)),
+    ))
+}
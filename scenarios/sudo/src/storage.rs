@@ -0,0 +1,12 @@
+//! Reading the storage of `pallet_sudo`.
+use aleph_client::substrate_api_client::AccountId;
+use anyhow::Result as AnyResult;
+
+use chain_support::NodeConnection;
+
+/// The sudo key, if there is any.
+pub async fn sudo_key(connection: &NodeConnection) -> AnyResult<Option<AccountId>> {
+    connection
+        .read_blocking(|api| Ok(api.get_storage_value("Sudo", "Key", None)?))
+        .await
+}
@@ -0,0 +1,352 @@
+use aleph_client::{
+    account_from_keypair, keypair_from_string,
+    substrate_api_client::{compose_call, AccountId, Balance, GenericAddress},
+    AnyConnection, KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use codec::{Compact, Encode};
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use serde::Deserialize;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    DispatchError, ModuleError,
+};
+
+use chain_support::{
+    free_balance, free_balances, real_amount, send_located_xt, NodeConnection, Preflight,
+    Requirements, SecretSource,
+};
+use common::{Scenario, ScenarioLogging};
+
+use crate::{
+    events::{ExtrinsicFailed, Remarked, Sudid, SudoAsDone},
+    storage::sudo_key,
+    SudoError,
+};
+
+/// Weight declared in `sudo_unchecked_weight`. Dispatched calls are light, so it is plenty.
+const UNCHECKED_WEIGHT: u64 = 1_000_000_000;
+
+/// Index of `RequireSudo` among the errors of `pallet_sudo`.
+const REQUIRE_SUDO: u8 = 0;
+
+fn all_cases() -> Vec<Case> {
+    vec![
+        Case::Sudo,
+        Case::SudoUncheckedWeight,
+        Case::SudoAs,
+        Case::NotSudo,
+    ]
+}
+
+chain_support::default_pool!("Sudo");
+
+/// What is sent in a single run.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Case {
+    /// `sudo` of either `Balances::force_transfer` (which should succeed) or
+    /// `System::remark_with_event` (which requires a signed origin, so it should fail with
+    /// `BadOrigin`).
+    Sudo,
+    /// Like `Sudo`, but with `sudo_unchecked_weight`.
+    SudoUncheckedWeight,
+    /// `sudo_as` of either `System::remark_with_event` (which should succeed and be noted with the
+    /// account as the sender) or `Balances::force_transfer` (which should fail with `BadOrigin`).
+    SudoAs,
+    /// `sudo` signed by an account of the pool, which should fail with `RequireSudo`.
+    NotSudo,
+}
+
+/// A scenario that exercises the dispatch paths of `pallet_sudo`.
+///
+/// Every run takes two random accounts of the pool and goes through one of `cases` (chosen
+/// randomly). Calls dispatched with sudo are valid or invalid (for their origin) at random. The
+/// result of every dispatch is decoded from `Sudid` (or `SudoAsDone`) and compared with the
+/// expected one. Successful `force_transfer`s are confirmed by the balances of both accounts,
+/// successful remarks by `System::Remarked`. Sudo calls signed by other accounts are confirmed by
+/// `System::ExtrinsicFailed` with `RequireSudo`.
+///
+/// The sudo key is checked during setup. The pool must not contain it.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Sudo {
+    /// Where the seed phrase of the sudo account is kept.
+    sudo: SecretSource,
+    /// Tokens moved by every `force_transfer` dispatched as root.
+    transfer_value: u64,
+    /// Cases chosen from. By default, all of them.
+    #[serde(default = "all_cases")]
+    cases: Vec<Case>,
+    /// Account pool of the affected accounts and the non-sudo senders. By default, `Sudo`.
+    #[serde(default = "default_pool")]
+    pool: String,
+}
+
+fn random_remark() -> Vec<u8> {
+    format!("sudo-bot-{}", thread_rng().gen::<u64>()).into_bytes()
+}
+
+/// `Balances::force_transfer` of `value` from `from` to `to`. Requires root.
+fn force_transfer(
+    connection: &NodeConnection,
+    from: &AccountId,
+    to: &AccountId,
+    value: Balance,
+) -> ([u8; 2], GenericAddress, GenericAddress, Compact<Balance>) {
+    compose_call!(
+        connection.as_connection().metadata,
+        "Balances",
+        "force_transfer",
+        GenericAddress::Id(from.clone()),
+        GenericAddress::Id(to.clone()),
+        Compact(value)
+    )
+}
+
+/// `System::remark_with_event` of `remark`. Requires a signed origin.
+fn remark(connection: &NodeConnection, remark: Vec<u8>) -> ([u8; 2], Vec<u8>) {
+    compose_call!(
+        connection.as_connection().metadata,
+        "System",
+        "remark_with_event",
+        remark
+    )
+}
+
+/// Checks that the call dispatched in `case` resulted in `expected`.
+fn check_result(
+    case: Case,
+    found: Result<(), DispatchError>,
+    expected: Result<(), DispatchError>,
+) -> AnyResult<()> {
+    ensure!(
+        found == expected,
+        SudoError::UnexpectedResult {
+            case,
+            found,
+            expected,
+        }
+    );
+    Ok(())
+}
+
+impl Sudo {
+    fn sudo(&self) -> AnyResult<KeyPair> {
+        Ok(keypair_from_string(&self.sudo.read()?))
+    }
+
+    /// Dispatches `call` as root (with `sudo` or `sudo_unchecked_weight`, depending on `case`).
+    /// Returns the result of the call.
+    ///
+    /// `Sudid` does not say which call it reports and other scenarios may use the sudo key at the
+    /// same time, so the event is taken only from this very extrinsic.
+    async fn dispatch_as_root<Call: Encode + Clone>(
+        case: Case,
+        sudo: &KeyPair,
+        connection: &NodeConnection,
+        call: Call,
+    ) -> AnyResult<Result<(), DispatchError>> {
+        let metadata = connection.as_connection().metadata;
+        let (_, location) = match case {
+            Case::SudoUncheckedWeight => {
+                let call = compose_call!(
+                    metadata,
+                    "Sudo",
+                    "sudo_unchecked_weight",
+                    call,
+                    UNCHECKED_WEIGHT
+                );
+                send_located_xt(connection, sudo, call, "Sudo::sudo_unchecked_weight").await?
+            }
+            _ => {
+                let call = compose_call!(metadata, "Sudo", "sudo", call);
+                send_located_xt(connection, sudo, call, "Sudo::sudo").await?
+            }
+        };
+        let event = connection
+            .extrinsic_event(location, |_: &Sudid| true)
+            .await?;
+        Ok(event.sudo_result)
+    }
+
+    /// `Case::Sudo` and `Case::SudoUncheckedWeight`.
+    async fn as_root(
+        &self,
+        case: Case,
+        connection: &NodeConnection,
+        from: &AccountId,
+        to: &AccountId,
+    ) -> AnyResult<()> {
+        let sudo = self.sudo()?;
+        let value = real_amount(&self.transfer_value) as i128;
+        let before = free_balances(connection, &[from.clone(), to.clone()]).await?;
+
+        let (found, expected, changes) = if thread_rng().gen_bool(0.5) {
+            let call = force_transfer(connection, from, to, value as Balance);
+            let found = Self::dispatch_as_root(case, &sudo, connection, call).await?;
+            (found, Ok(()), [-value, value])
+        } else {
+            let call = remark(connection, random_remark());
+            let found = Self::dispatch_as_root(case, &sudo, connection, call).await?;
+            (found, Err(DispatchError::BadOrigin), [0, 0])
+        };
+        check_result(case, found, expected)?;
+
+        for ((account, before), expected) in [from, to].into_iter().zip(before).zip(changes) {
+            let found = free_balance(connection, account).await? as i128 - before as i128;
+            ensure!(
+                found == expected,
+                SudoError::UnexpectedBalanceChange {
+                    case,
+                    account: account.clone(),
+                    found,
+                    expected,
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// `Case::SudoAs`. Like in `Self::dispatch_as_root`, the events are taken only from this very
+    /// extrinsic.
+    async fn as_signed(&self, connection: &NodeConnection, who: &AccountId) -> AnyResult<()> {
+        let sudo = self.sudo()?;
+
+        if thread_rng().gen_bool(0.5) {
+            let remark_bytes = random_remark();
+            let hash = BlakeTwo256::hash(&remark_bytes).to_fixed_bytes();
+            let call = remark(connection, remark_bytes);
+            let call = compose_call!(
+                connection.as_connection().metadata,
+                "Sudo",
+                "sudo_as",
+                GenericAddress::Id(who.clone()),
+                call
+            );
+            let (_, location) = send_located_xt(connection, &sudo, call, "Sudo::sudo_as").await?;
+            let done = connection
+                .extrinsic_event(location, |_: &SudoAsDone| true)
+                .await?;
+            check_result(Case::SudoAs, done.sudo_result, Ok(()))?;
+            connection
+                .extrinsic_event(location, |event: &Remarked| {
+                    event.sender == *who && event.hash == hash
+                })
+                .await
+                .map(|_| ())
+        } else {
+            let value = real_amount(&self.transfer_value);
+            let call = force_transfer(connection, who, who, value);
+            let call = compose_call!(
+                connection.as_connection().metadata,
+                "Sudo",
+                "sudo_as",
+                GenericAddress::Id(who.clone()),
+                call
+            );
+            let (_, location) = send_located_xt(connection, &sudo, call, "Sudo::sudo_as").await?;
+            let done = connection
+                .extrinsic_event(location, |_: &SudoAsDone| true)
+                .await?;
+            check_result(
+                Case::SudoAs,
+                done.sudo_result,
+                Err(DispatchError::BadOrigin),
+            )
+        }
+    }
+
+    /// `Case::NotSudo`.
+    async fn not_sudo(connection: &NodeConnection, sender: &KeyPair) -> AnyResult<()> {
+        let sudo_index = connection
+            .as_connection()
+            .metadata
+            .pallet("Sudo")
+            .map_err(|_| SudoError::MissingPallet("Sudo"))?
+            .index;
+
+        let call = remark(connection, random_remark());
+        let call = compose_call!(connection.as_connection().metadata, "Sudo", "sudo", call);
+        let (_, location) = send_located_xt(connection, sender, call, "Sudo::sudo").await?;
+        connection
+            .extrinsic_event(location, |event: &ExtrinsicFailed| {
+                matches!(
+                    event.dispatch_error,
+                    DispatchError::Module(ModuleError { index, error, .. })
+                        if index == sudo_index && error == REQUIRE_SUDO
+                )
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+impl Preflight for Sudo {
+    fn requirements(&self) -> Requirements {
+        let mut requirements = Requirements::default()
+            .call("Balances", "force_transfer")
+            .call("System", "remark_with_event")
+            .pool(&self.pool, 2)
+            .ensure(!self.cases.is_empty(), "There should be some cases");
+        for case in &self.cases {
+            requirements = match case {
+                Case::Sudo | Case::NotSudo => requirements.call("Sudo", "sudo"),
+                Case::SudoUncheckedWeight => requirements.call("Sudo", "sudo_unchecked_weight"),
+                Case::SudoAs => requirements.call("Sudo", "sudo_as"),
+            };
+        }
+        requirements
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Sudo {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let keypairs = connection.accounts(&self.pool)?.random_keypairs(2)?;
+        let (first, second) = (
+            account_from_keypair(&keypairs[0]),
+            account_from_keypair(&keypairs[1]),
+        );
+        let case = *self
+            .cases
+            .choose(&mut thread_rng())
+            .expect("There are some cases");
+
+        logger.info(format!("{:?} with {}", case, first));
+        let result = match case {
+            Case::Sudo | Case::SudoUncheckedWeight => {
+                self.as_root(case, connection, &first, &second).await
+            }
+            Case::SudoAs => self.as_signed(connection, &first).await,
+            Case::NotSudo => Self::not_sudo(connection, &keypairs[0]).await,
+        };
+        logger.log_result(result)?;
+        logger.info(format!("{:?} with {} confirmed", case, first));
+        Ok(())
+    }
+
+    /// Makes sure that the configured account is the sudo key and that it is not in the pool.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        _logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let configured = account_from_keypair(&keypair_from_string(&self.sudo.read()?));
+        let actual = sudo_key(connection).await?.ok_or(SudoError::NoSudoKey)?;
+        ensure!(
+            configured == actual,
+            SudoError::WrongSudoKey { configured, actual }
+        );
+        ensure!(
+            !connection
+                .accounts(&self.pool)?
+                .accounts()
+                .contains(&actual),
+            SudoError::SudoInPool(self.pool.clone())
+        );
+        Ok(())
+    }
+}
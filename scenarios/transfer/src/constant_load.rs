@@ -0,0 +1,309 @@
+use std::time::{Duration, Instant};
+
+use aleph_client::{account_from_keypair, KeyPair};
+use anyhow::Result as AnyResult;
+use futures::{
+    future::{Fuse, FusedFuture, FutureExt},
+    stream::FuturesUnordered,
+    StreamExt,
+};
+use rand::{thread_rng, Rng};
+use serde::Deserialize;
+use tokio::time::interval;
+
+use chain_support::{
+    real_amount, NodeConnection, Preflight, Requirements, SubmissionError, WaitFor, XtStatusUpdate,
+};
+use common::{parse_interval, LatencySample, Scenario, ScenarioLogging};
+
+use crate::{random_transfers::default_pool, send_transfer};
+
+/// How often we decide how many new transfers should be sent.
+const TICK: Duration = Duration::from_millis(10);
+
+/// How often we report achieved TPS and pool backlog.
+const REPORT_PERIOD: Duration = Duration::from_secs(5);
+
+/// Describes how the transfer rate changes over time.
+///
+/// Use:
+/// - `Steady` for keeping `tps` all the time
+/// - `RampUp(d)` for growing linearly from 0 to `tps` during the first `d`
+/// - `Spike { every, lasting, multiplier }` for keeping `tps`, except for the last `lasting` of
+///   every `every` period, when the rate is `multiplier` times higher
+#[derive(Clone, Debug, Deserialize)]
+pub enum LoadProfile {
+    Steady,
+    #[serde(deserialize_with = "parse_interval")]
+    RampUp(Duration),
+    Spike {
+        #[serde(deserialize_with = "parse_interval")]
+        every: Duration,
+        #[serde(deserialize_with = "parse_interval")]
+        lasting: Duration,
+        multiplier: u32,
+    },
+}
+
+impl LoadProfile {
+    /// Expected rate (in transfers per second) `elapsed` after start.
+    fn rate_at(&self, tps: u32, elapsed: Duration) -> f64 {
+        let tps = tps as f64;
+        match self {
+            LoadProfile::Steady => tps,
+            LoadProfile::RampUp(ramp_up) if elapsed < *ramp_up => {
+                tps * elapsed.as_secs_f64() / ramp_up.as_secs_f64()
+            }
+            LoadProfile::RampUp(_) => tps,
+            LoadProfile::Spike {
+                every,
+                lasting,
+                multiplier,
+            } => {
+                let in_period = elapsed.as_millis() % every.as_millis().max(1);
+                if in_period >= every.saturating_sub(*lasting).as_millis() {
+                    tps * *multiplier as f64
+                } else {
+                    tps
+                }
+            }
+        }
+    }
+}
+
+/// Final state of a single transfer.
+enum Outcome {
+    Succeeded,
+    /// Dropped or usurped from the pool.
+    Dropped,
+    /// Refused by the node or declared invalid.
+    Invalid,
+    /// Any other error (e.g. timeout).
+    Failed,
+}
+
+impl From<&AnyResult<LatencySample>> for Outcome {
+    fn from(result: &AnyResult<LatencySample>) -> Self {
+        let err = match result {
+            Ok(_) => return Outcome::Succeeded,
+            Err(err) => err,
+        };
+        match err.downcast_ref::<SubmissionError>() {
+            Some(SubmissionError::Rejected(XtStatusUpdate::Dropped))
+            | Some(SubmissionError::Rejected(XtStatusUpdate::Usurped(_))) => Outcome::Dropped,
+            Some(SubmissionError::Rejected(XtStatusUpdate::Invalid))
+            | Some(SubmissionError::Refused(_)) => Outcome::Invalid,
+            _ => Outcome::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LoadStats {
+    sent: usize,
+    succeeded: usize,
+    dropped: usize,
+    invalid: usize,
+    failed: usize,
+    max_backlog: usize,
+}
+
+impl LoadStats {
+    fn register(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Succeeded => self.succeeded += 1,
+            Outcome::Dropped => self.dropped += 1,
+            Outcome::Invalid => self.invalid += 1,
+            Outcome::Failed => self.failed += 1,
+        }
+    }
+}
+
+/// Open-loop scenario: keeps sending transfers within an account pool (by default, the one of
+/// `RandomTransfers`) at a configured rate, regardless of how fast the chain processes them.
+///
+/// Every transfer has a random sender and a random receiver. Nonces are assigned by the nonce
+/// manager, so a single account can have many transfers in flight. There are no retries:
+/// every failure is reported as a dropped, invalid or failed transfer.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConstantLoad {
+    /// Account pool within which transfers are made. By default, `RandomTransfer`.
+    #[serde(default = "default_pool")]
+    pool: String,
+    /// Target rate (transfers per second).
+    tps: u32,
+    /// For how long the load should be sustained.
+    #[serde(deserialize_with = "parse_interval")]
+    duration: Duration,
+    /// How the rate changes over time.
+    profile: LoadProfile,
+    /// How many tokens should be transferred (in a single transfer).
+    transfer_value: u64,
+    /// Which status of every transfer is considered a success. By default, `Finalized`.
+    #[serde(default)]
+    wait_for: WaitFor,
+}
+
+impl ConstantLoad {
+    /// Sends a transfer between two random (distinct) accounts from `accounts`.
+    async fn send_random_transfer(
+        &self,
+        connection: &NodeConnection,
+        accounts: &[KeyPair],
+    ) -> Outcome {
+        let (sender, receiver) = {
+            let mut rng = thread_rng();
+            let sender = rng.gen_range(0..accounts.len());
+            let receiver = (sender + rng.gen_range(1..accounts.len())) % accounts.len();
+            (sender, receiver)
+        };
+
+        let result = send_transfer(
+            connection,
+            &accounts[sender],
+            &account_from_keypair(&accounts[receiver]),
+            real_amount(&self.transfer_value),
+            self.wait_for,
+        )
+        .await;
+        Outcome::from(&result)
+    }
+
+    fn report(&self, stats: &LoadStats, elapsed: Duration, logger: &ScenarioLogging) {
+        logger.info(format!(
+            "Achieved {:.2} TPS (target: {}). Sent: {}, succeeded: {}, dropped: {}, invalid: {}, \
+            failed: {}. Max pool backlog: {}.",
+            stats.succeeded as f64 / elapsed.as_secs_f64(),
+            self.tps,
+            stats.sent,
+            stats.succeeded,
+            stats.dropped,
+            stats.invalid,
+            stats.failed,
+            stats.max_backlog,
+        ));
+    }
+}
+
+impl Preflight for ConstantLoad {
+    fn requirements(&self) -> Requirements {
+        let requirements = Requirements::default()
+            .call("Balances", "transfer")
+            .pool(&self.pool, 2)
+            .ensure(self.tps > 0, "Rate (`tps`) should be positive");
+
+        match self.profile {
+            LoadProfile::Spike { every, lasting, .. } => requirements.ensure(
+                lasting < every,
+                "Spikes should be shorter than the period between them",
+            ),
+            _ => requirements,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for ConstantLoad {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        logger.info(format!(
+            "Starting load of {} TPS ({:?}) for {:?}",
+            self.tps, self.profile, self.duration
+        ));
+
+        let accounts = {
+            let pool = connection.accounts(&self.pool)?;
+            pool.ensure_size(2)?;
+            pool.keypairs()
+        };
+        let mut stats = LoadStats::default();
+        let mut in_flight = FuturesUnordered::new();
+
+        let start = Instant::now();
+        let mut last_report = start;
+        let mut ticker = interval(TICK);
+        // How many transfers we are late with (fractional part is carried to the next tick).
+        let mut due = 0f64;
+        // Reading the backlog takes a round trip to the node, so it must not hold up the ticks.
+        let backlog = Fuse::terminated();
+        futures::pin_mut!(backlog);
+
+        while start.elapsed() < self.duration {
+            futures::select! {
+                _ = ticker.tick().fuse() => {
+                    due += self.profile.rate_at(self.tps, start.elapsed()) * TICK.as_secs_f64();
+                    while due >= 1. {
+                        in_flight.push(self.send_random_transfer(connection, &accounts));
+                        stats.sent += 1;
+                        due -= 1.;
+                    }
+
+                    if last_report.elapsed() >= REPORT_PERIOD {
+                        last_report = Instant::now();
+                        if backlog.is_terminated() {
+                            backlog.set(connection.pending_extrinsics().fuse());
+                        }
+                        self.report(&stats, start.elapsed(), logger);
+                    }
+                }
+                result = backlog => match result {
+                    Ok(pending) => {
+                        stats.max_backlog = stats.max_backlog.max(pending);
+                        logger.debug(format!("Pool backlog: {}", pending));
+                    }
+                    Err(e) => logger.warn(format!("Cannot read pool backlog: {:?}", e)),
+                },
+                outcome = in_flight.select_next_some() => stats.register(outcome),
+            }
+        }
+
+        logger.info(format!(
+            "Load finished. Waiting for {} transfers in flight",
+            in_flight.len()
+        ));
+        while let Some(outcome) = in_flight.next().await {
+            stats.register(outcome);
+        }
+        // Transfers completed while draining count too, so the time spent on it does as well.
+        self.report(&stats, start.elapsed(), logger);
+
+        logger.info("Scenario finished successfully");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::constant_load::LoadProfile;
+
+    #[test]
+    fn ramp_up_grows_linearly_and_saturates() {
+        let profile = LoadProfile::RampUp(Duration::from_secs(10));
+
+        assert_eq!(0., profile.rate_at(100, Duration::ZERO));
+        assert_eq!(50., profile.rate_at(100, Duration::from_secs(5)));
+        assert_eq!(100., profile.rate_at(100, Duration::from_secs(10)));
+        assert_eq!(100., profile.rate_at(100, Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn spike_happens_at_the_end_of_every_period() {
+        let profile = LoadProfile::Spike {
+            every: Duration::from_secs(10),
+            lasting: Duration::from_secs(2),
+            multiplier: 5,
+        };
+
+        assert_eq!(10., profile.rate_at(10, Duration::from_secs(0)));
+        assert_eq!(10., profile.rate_at(10, Duration::from_millis(7999)));
+        assert_eq!(50., profile.rate_at(10, Duration::from_secs(8)));
+        assert_eq!(50., profile.rate_at(10, Duration::from_millis(9999)));
+        assert_eq!(10., profile.rate_at(10, Duration::from_secs(10)));
+        assert_eq!(50., profile.rate_at(10, Duration::from_secs(19)));
+    }
+}
@@ -1,55 +1,174 @@
-#![feature(fn_traits)]
-
 use std::time::Duration;
 
-use aleph_client::{
-    account_from_keypair, substrate_api_client, try_send_xt, AnyConnection, KeyPair,
-    SignedConnection,
-};
+use aleph_client::{account_from_keypair, substrate_api_client, AnyConnection, KeyPair};
 use anyhow::Result as AnyResult;
-use substrate_api_client::{AccountId, GenericAddress, XtStatus};
+use codec::{Compact, Encode};
+use substrate_api_client::{compose_call, AccountId, GenericAddress};
 use tokio::time::sleep;
 
-use chain_support::{with_event_listening, TransferEvent};
-use common::ScenarioError;
-pub use random_transfers::{Direction, RandomTransfers, TransferMode};
+use chain_support::{
+    send_managed_xt, send_tracked_xt, with_event_listening, ChainScenarioKind,
+    ChainScenarioRegistry, NodeConnection, SubmissionError, TransferEvent, WaitFor,
+};
+use common::{LatencySample, ScenarioError};
+pub use constant_load::{ConstantLoad, LoadProfile};
+pub use random_transfers::{
+    get_random_delays, Direction, RandomTransfers, TransferMode, MILLIS_PER_TRANSACTION,
+};
 pub use round_robin::RoundRobin;
 pub use simple_transfer::SimpleTransfer;
 
+mod constant_load;
 mod random_transfers;
 mod round_robin;
 mod simple_transfer;
 
+/// Registers all the transfer scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "SimpleTransfer",
+        &[
+            "transfer_value: tokens sent in every run",
+            "sender_pool (optional): account pool of the sender, by default `SimpleTransferSender`",
+            "wait_for (optional): `Ready`, `InBlock` or `Finalized` (default)",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<SimpleTransfer>()?)),
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "RoundRobin",
+        &[
+            "passes: how many times the robin is passed (at most the pool size)",
+            "robin_value: tokens passed",
+            "pool (optional): account pool, by default `RoundRobin`",
+            "wait_for (optional): `Ready`, `InBlock` or `Finalized` (default)",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<RoundRobin>()?)),
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "RandomTransfers",
+        &[
+            "direction: `OneToMany`, `ManyToOne` or `ManyToMany`",
+            "transfer_mode: `Sequential`, `Concurrent`, `Batched`, `{ WithDelay = <interval> }` or `{ Span = <interval> }`",
+            "transfers: number of transfers in every run",
+            "transfer_value: tokens sent in every transfer",
+            "pool (optional): account pool, by default `RandomTransfer`",
+            "wait_for (optional): `Ready`, `InBlock` or `Finalized` (default)",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<RandomTransfers>()?)),
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "ConstantLoad",
+        &[
+            "tps: target rate (transfers per second)",
+            "duration: for how long the load is sustained",
+            "profile: `Steady`, `{ RampUp = <interval> }` or `{ Spike = { every, lasting, multiplier } }`",
+            "transfer_value: tokens sent in every transfer",
+            "pool (optional): account pool, by default `RandomTransfer`",
+            "wait_for (optional): `Ready`, `InBlock` or `Finalized` (default)",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<ConstantLoad>()?)),
+    ))
+}
+
+/// `Balances::transfer` of `amount` to `target`.
+fn transfer_call(
+    connection: &NodeConnection,
+    target: &AccountId,
+    amount: u128,
+) -> impl Encode + Clone {
+    compose_call!(
+        connection.as_connection().metadata,
+        "Balances",
+        "transfer",
+        GenericAddress::Id(target.clone()),
+        Compact(amount)
+    )
+}
+
+/// Sends a single transfer, without any retries or event confirmation.
+async fn send_transfer(
+    connection: &NodeConnection,
+    source: &KeyPair,
+    target: &AccountId,
+    amount: u128,
+    wait_for: WaitFor,
+) -> AnyResult<LatencySample> {
+    send_managed_xt(
+        connection,
+        source,
+        transfer_call(connection, target, amount),
+        "Balances::transfer",
+        wait_for.into(),
+    )
+    .await
+}
+
+/// Sends the transfer, retrying (with a fresh nonce) when an attempt fails.
+///
+/// A failed attempt might still have been included (e.g. only watching it has failed), and
+/// sending it again would transfer `amount` twice. So before every retry, the blocks since the
+/// first attempt are searched for the previous attempts. An attempt that has landed in the future
+/// queue stays there and can be included once the gap is filled, so after it, nothing more is
+/// sent: it is only looked for.
 async fn loop_transfer(
-    connection: &SignedConnection,
+    connection: &NodeConnection,
+    source: &KeyPair,
     target: &AccountId,
     amount: u128,
+    wait_for: WaitFor,
 ) -> AnyResult<()> {
+    let account = account_from_keypair(source);
+    let since = connection.best_block_number().await?;
+    let mut attempts = vec![];
+    let mut stuck = false;
     for _ in 0..5 {
-        let xt = connection
-            .as_connection()
-            .balance_transfer(GenericAddress::Id(target.clone()), amount);
-        if try_send_xt(connection, xt, Some("transfer"), XtStatus::Finalized).is_ok() {
-            return Ok(());
+        if !stuck {
+            let nonce = connection.next_nonce(&account).await?;
+            let xt = connection.sign_with_nonce(
+                source,
+                transfer_call(connection, target, amount),
+                nonce,
+            );
+            attempts.push(xt.hex_encode());
+            match send_tracked_xt(connection, xt, "Balances::transfer", wait_for.into()).await {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    connection.resync_nonce(&account).await;
+                    stuck = matches!(
+                        e.downcast_ref::<SubmissionError>(),
+                        Some(SubmissionError::NonceGap)
+                    );
+                }
+            }
         }
 
         sleep(Duration::from_millis(500)).await;
+        if connection.find_extrinsic(since, &attempts).await?.is_some() {
+            return Ok(());
+        }
     }
     Err(ScenarioError::CannotSendExtrinsic.into())
 }
 
-pub async fn try_transfer<C: AnyConnection>(
-    connection: &C,
+/// Transfers `amount` from `source` to `target`. Unless `wait_for` is `WaitFor::Ready`, the
+/// transfer is confirmed by observing `Transfer` event.
+pub async fn try_transfer(
+    connection: &NodeConnection,
     source: &KeyPair,
     target: &AccountId,
     amount: u128,
+    wait_for: WaitFor,
 ) -> AnyResult<()> {
-    let connection = SignedConnection::from_any_connection(connection, source.clone());
+    // There is no event to wait for until the extrinsic is included in some block.
+    if wait_for == WaitFor::Ready {
+        return loop_transfer(connection, source, target, amount, wait_for).await;
+    }
+
     let expected_event =
         TransferEvent::from_relevant_fields(account_from_keypair(source), target.clone(), amount);
 
-    with_event_listening(&connection, expected_event, Duration::from_secs(1), async {
-        loop_transfer(&connection, target, amount).await
+    with_event_listening(connection, expected_event, Duration::from_secs(1), async {
+        loop_transfer(connection, source, target, amount, wait_for).await
     })
     .await
     .map(|_| ())
@@ -1,37 +1,36 @@
 use std::{collections::HashMap, time::Duration};
 
-use aleph_client::{
-    substrate_api_client, try_send_xt, AnyConnection, Connection, KeyPair, SignedConnection,
-};
+use aleph_client::{substrate_api_client, AnyConnection, KeyPair};
 use anyhow::Result as AnyResult;
 use codec::{Compact, Decode};
-use rand::{distributions::{Distribution, Uniform}, prelude::IteratorRandom, thread_rng, Rng};
-use serde::Deserialize;
-use substrate_api_client::{
-    compose_call, compose_extrinsic, AccountId, GenericAddress, Pair, XtStatus,
+use futures::future::join_all;
+use rand::{
+    distributions::{Distribution, Uniform},
+    prelude::IteratorRandom,
+    thread_rng, Rng,
 };
+use serde::Deserialize;
+use substrate_api_client::{compose_call, AccountId, GenericAddress, Pair};
 use tokio::time::sleep;
 
-use chain_support::{keypair_derived_from_seed, real_amount, with_event_listening, Event};
+use chain_support::{
+    real_amount, send_managed_xt, with_event_listening, Accounts, Event, NodeConnection, Preflight,
+    Requirements, WaitFor,
+};
 use common::{parse_interval, Scenario, ScenarioError, ScenarioLogging};
 
 use crate::try_transfer;
 
-/// We operate on an account pool based on this seed. The final seeds will have
-/// a form of `RANDOM_TRANSFER_SEED{i: usize}`.
-const RANDOM_TRANSFER_SEED: &str = "//RandomTransfer";
-
-/// We expect that there are as many endowed accounts (of seed phrases: `RANDOM_TRANSFER_SEED{i}`,
-/// where `i` is from 0 to this value (exclusively)).
-const AVAILABLE_ACCOUNTS: usize = 100;
-
-/// Returns keypair of the common account with index `idx`.
-fn compute_keypair(idx: usize) -> KeyPair {
-    keypair_derived_from_seed(format!("{}{}", RANDOM_TRANSFER_SEED, idx))
+/// Account pool used when no other is configured.
+pub(crate) fn default_pool() -> String {
+    "RandomTransfer".to_string()
 }
 
+/// How much time a single transfer is expected to take within `TransferMode::Span`.
+pub const MILLIS_PER_TRANSACTION: u128 = 1_000;
+
 /// returns vec of length `delay_count` with random delays that sum up to `target`.
-fn get_random_delays(target: u128, delay_count: usize) -> Vec<u128> {
+pub fn get_random_delays(target: u128, delay_count: usize) -> Vec<u128> {
     let mut rng = thread_rng();
 
     let between = Uniform::from(0..target);
@@ -62,11 +61,41 @@ pub enum Direction {
     ManyToMany,
 }
 
+impl Direction {
+    /// Returns an iterator over all possible (sender, receiver) pairs of indices within a pool of
+    /// `pool_size` accounts.
+    pub fn pairs(&self, pool_size: usize) -> impl Iterator<Item = (usize, usize)> {
+        let range = 0..pool_size;
+        // Have to use ugly `Box` with annotation because arms return different `Map<_>` objects
+        // (different closures => different types).
+        let unfiltered: Box<dyn Iterator<Item = (usize, usize)>> = match self {
+            Direction::OneToMany => {
+                let sender = thread_rng().gen_range(range.clone());
+                Box::new(range.map(move |receiver| (sender, receiver)))
+            }
+            Direction::ManyToOne => {
+                let receiver = thread_rng().gen_range(range.clone());
+                Box::new(range.map(move |sender| (sender, receiver)))
+            }
+            Direction::ManyToMany => Box::new(
+                range
+                    .clone()
+                    .flat_map(move |r| range.clone().map(move |s| (s, r))),
+            ),
+        };
+        unfiltered.filter(|(s, r)| s != r)
+    }
+}
+
 /// Describes whether transfers should be submitted as independent extrinsics
 /// or in a batch.
+///
+/// `Concurrent` submits all the transfers at once, without waiting for the previous ones. Nonces
+/// are assigned by the nonce manager, so this works also when all of them have a common sender.
 #[derive(Clone, Debug, Deserialize)]
 pub enum TransferMode {
     Sequential,
+    Concurrent,
     Batched,
     #[serde(deserialize_with = "parse_interval")]
     WithDelay(Duration),
@@ -86,9 +115,12 @@ pub enum TransferMode {
 /// - `ManyToMany`: `transfers` random pairs are chosen as (sender, receiver); then every
 ///   sender sends `transfer_fraction` of their balances to their corresponding receiver
 ///
-/// Depending on `granularity`, transfers are submitted sequentially or in a batch.
+/// Depending on `transfer_mode`, transfers are submitted sequentially, concurrently or in a batch.
 #[derive(Clone, Debug, Deserialize)]
 pub struct RandomTransfers {
+    /// Account pool within which transfers are made. By default, `RandomTransfer`.
+    #[serde(default = "default_pool")]
+    pool: String,
     /// What type of traffic should be made.
     direction: Direction,
     /// How to submit extrinsics.
@@ -100,6 +132,9 @@ pub struct RandomTransfers {
     transfers: usize,
     /// How many tokens should be transferred (in a single transfer).
     transfer_value: u64,
+    /// Which status of every extrinsic should be awaited. By default, `Finalized`.
+    #[serde(default)]
+    wait_for: WaitFor,
 }
 
 /// Represents a single sender-receiver pair.
@@ -112,41 +147,17 @@ struct TransferPair {
 }
 
 impl RandomTransfers {
-    /// Returns an iterator over all possible (sender, receiver) pairs
-    /// corresponding to `self.direction`.
-    fn generate_pairs(&self) -> impl Iterator<Item = (usize, usize)> {
-        let range = 0..AVAILABLE_ACCOUNTS;
-        // Have to use ugly `Box` with annotation because arms return different `Map<_>` objects
-        // (different closures => different types).
-        let unfiltered: Box<dyn Iterator<Item = (usize, usize)>> = match self.direction {
-            Direction::OneToMany => {
-                let sender = thread_rng().gen_range(range.clone());
-                Box::new(range.map(move |receiver| (sender, receiver)))
-            }
-            Direction::ManyToOne => {
-                let receiver = thread_rng().gen_range(range.clone());
-                Box::new(range.map(move |sender| (sender, receiver)))
-            }
-            Direction::ManyToMany => Box::new(
-                range
-                    .clone()
-                    .flat_map(move |r| range.clone().map(move |s| (s, r))),
-            ),
-        };
-        unfiltered.filter(|(s, r)| s != r)
-    }
-
     /// Returns a vector of `self.transfers` random (sender, receiver) pairs corresponding
     /// to `self.direction`.
-    fn designate_pairs(&self) -> Vec<TransferPair> {
-        let possibilities = self.generate_pairs();
+    fn designate_pairs(&self, accounts: &Accounts) -> Vec<TransferPair> {
+        let possibilities = self.direction.pairs(accounts.size());
         let mut generator = thread_rng();
         let index_pairs = possibilities.choose_multiple(&mut generator, self.transfers);
 
         let keypairs: HashMap<usize, KeyPair> = index_pairs
             .iter()
             .flat_map(|pair| [pair.0, pair.1])
-            .map(|i| (i, compute_keypair(i)))
+            .map(|i| (i, accounts.keypair(i)))
             .collect();
 
         index_pairs
@@ -162,7 +173,7 @@ impl RandomTransfers {
 
     async fn send_transfer(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         transfer_pair: TransferPair,
         logger: &ScenarioLogging,
     ) -> AnyResult<()> {
@@ -183,13 +194,14 @@ impl RandomTransfers {
             &sender,
             &receiver,
             real_amount(&self.transfer_value),
+            self.wait_for,
         )
         .await
     }
 
     async fn send_sequentially(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         pairs: Vec<TransferPair>,
         logger: &ScenarioLogging,
     ) -> AnyResult<()> {
@@ -197,21 +209,41 @@ impl RandomTransfers {
             .await
     }
 
+    async fn send_concurrently(
+        &self,
+        connection: &NodeConnection,
+        pairs: Vec<TransferPair>,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let results = join_all(
+            pairs
+                .into_iter()
+                .map(|pair| self.send_transfer(connection, pair, logger)),
+        )
+        .await;
+
+        for result in results {
+            logger.log_result(result)?;
+        }
+        logger.debug(format!("Completed {} transfers.", self.transfers));
+
+        Ok(())
+    }
+
     async fn send_in_batch(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         pairs: Vec<TransferPair>,
         logger: &ScenarioLogging,
     ) -> AnyResult<()> {
-        // `xts` is built in good old imperative way, because it requires async, fallible call
-        // for computing transfer value, which is not so nice to be used within `map()`.
+        let metadata = connection.as_connection().metadata;
         let mut xts = Vec::new();
         for transfer_pair in pairs.clone() {
             let TransferPair {
-                sender,
                 sender_id,
                 receiver,
                 receiver_id,
+                ..
             } = transfer_pair;
 
             logger.debug(format!(
@@ -219,9 +251,6 @@ impl RandomTransfers {
                 sender_id, receiver_id
             ));
 
-            let metadata = SignedConnection::from_any_connection(connection, sender)
-                .as_connection()
-                .metadata;
             xts.push(compose_call!(
                 metadata,
                 "Balances",
@@ -231,25 +260,33 @@ impl RandomTransfers {
             ));
         }
 
-        // `self.connection` may not be signed, but somebody has to pay for submitting
-        let connection = SignedConnection::from_any_connection(connection, pairs[0].sender.clone());
-        let xt = compose_extrinsic!(connection.as_connection(), "Utility", "batch", xts);
-
-        let batch_result = with_event_listening(
-            &connection,
-            BatchCompleted {},
-            Duration::from_secs(1),
-            async {
-                try_send_xt(
-                    &connection,
-                    xt,
-                    Some("Sending transfers in batch"),
-                    XtStatus::Finalized,
-                )
-                .map_err(|_| ScenarioError::CannotSendExtrinsic.into())
-            },
-        )
-        .await;
+        let call = compose_call!(metadata, "Utility", "batch", xts);
+        let send_batch = async {
+            send_managed_xt(
+                connection,
+                // somebody has to pay for submitting
+                &pairs[0].sender,
+                call,
+                "Utility::batch",
+                self.wait_for.into(),
+            )
+            .await
+            .map_err(|_| anyhow::Error::from(ScenarioError::CannotSendExtrinsic))
+        };
+
+        // There is no event to wait for until the extrinsic is included in some block.
+        let batch_result = if self.wait_for == WaitFor::Ready {
+            send_batch.await.map(|_| ())
+        } else {
+            with_event_listening(
+                connection,
+                BatchCompleted {},
+                Duration::from_secs(1),
+                send_batch,
+            )
+            .await
+            .map(|_| ())
+        };
 
         logger.log_result(batch_result)?;
 
@@ -259,7 +296,7 @@ impl RandomTransfers {
     async fn send_with_delay(
         &self,
         delay: Duration,
-        connection: &Connection,
+        connection: &NodeConnection,
         pairs: Vec<TransferPair>,
         logger: &ScenarioLogging,
     ) -> AnyResult<()> {
@@ -270,11 +307,10 @@ impl RandomTransfers {
     async fn send_within_span(
         &self,
         span: Duration,
-        connection: &Connection,
+        connection: &NodeConnection,
         pairs: Vec<TransferPair>,
         logger: &ScenarioLogging,
     ) -> AnyResult<()> {
-        const MILLIS_PER_TRANSACTION: u128 = 1_000;
         let time_needed_to_send_all = MILLIS_PER_TRANSACTION * pairs.len() as u128;
 
         if span.as_millis() < time_needed_to_send_all {
@@ -295,7 +331,7 @@ impl RandomTransfers {
         &self,
         pairs: I,
         logger: &ScenarioLogging,
-        connection: &Connection,
+        connection: &NodeConnection,
     ) -> AnyResult<()> {
         for (idx, (transfer_pair, delay)) in pairs.into_iter().enumerate() {
             let transfer_result = self.send_transfer(connection, transfer_pair, logger).await;
@@ -317,12 +353,47 @@ impl RandomTransfers {
     }
 }
 
+impl Preflight for RandomTransfers {
+    fn requirements(&self) -> Requirements {
+        // With a single sender (or receiver), every transfer needs a distinct counterpart.
+        let pool_size = match self.direction {
+            Direction::OneToMany | Direction::ManyToOne => self.transfers + 1,
+            Direction::ManyToMany => 2,
+        };
+        let requirements = Requirements::default()
+            .call("Balances", "transfer")
+            .pool(&self.pool, pool_size)
+            .ensure(self.transfers > 0, "At least 1 transfer is needed");
+
+        match self.transfer_mode {
+            TransferMode::Batched => requirements.call("Utility", "batch"),
+            TransferMode::Span(span) => requirements.ensure(
+                span.as_millis() >= MILLIS_PER_TRANSACTION * self.transfers as u128,
+                format!(
+                    "Span of {}ms is too short for {} transfers ({}ms each)",
+                    span.as_millis(),
+                    self.transfers,
+                    MILLIS_PER_TRANSACTION
+                ),
+            ),
+            _ => requirements,
+        }
+    }
+}
+
 #[async_trait::async_trait]
-impl Scenario<Connection> for RandomTransfers {
-    async fn play(&mut self, connection: &Connection, logger: &ScenarioLogging) -> AnyResult<()> {
-        let pairs = self.designate_pairs();
+impl Scenario<NodeConnection> for RandomTransfers {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let accounts = connection.accounts(&self.pool)?;
+        accounts.ensure_size(2)?;
+        let pairs = self.designate_pairs(&accounts);
         match self.transfer_mode {
             TransferMode::Sequential => self.send_sequentially(connection, pairs, logger).await,
+            TransferMode::Concurrent => self.send_concurrently(connection, pairs, logger).await,
             TransferMode::Batched => self.send_in_batch(connection, pairs, logger).await,
             TransferMode::WithDelay(delay) => {
                 self.send_with_delay(delay, connection, pairs, logger).await
@@ -1,30 +1,36 @@
-use aleph_client::{account_from_keypair, substrate_api_client, Connection, KeyPair};
+use aleph_client::{substrate_api_client, KeyPair};
 use anyhow::Result as AnyResult;
 use rand::random;
 use serde::Deserialize;
 use substrate_api_client::AccountId;
 
-use chain_support::{keypair_derived_from_seed, real_amount};
+use chain_support::{real_amount, NodeConnection, Preflight, Requirements, WaitFor};
 use common::{Scenario, ScenarioLogging};
 
 use crate::try_transfer;
 
-const ROUND_ROBIN_SEED: &str = "//RoundRobin";
+fn default_pool() -> String {
+    "RoundRobin".to_string()
+}
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct RoundRobin {
+    /// Account pool passing the robin. It should have at least `passes` accounts. By default,
+    /// `RoundRobin`.
+    #[serde(default = "default_pool")]
+    pool: String,
     passes: usize,
     robin_value: u64,
+    /// Which status of every pass should be awaited before passing robin further. By default,
+    /// `Finalized`.
+    #[serde(default)]
+    wait_for: WaitFor,
 }
 
 impl RoundRobin {
-    fn account(id: usize) -> KeyPair {
-        keypair_derived_from_seed(&*format!("{}{}", ROUND_ROBIN_SEED, id))
-    }
-
     async fn pass_robin(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         sender: KeyPair,
         receiver: AccountId,
         logger: &ScenarioLogging,
@@ -34,6 +40,7 @@ impl RoundRobin {
             &sender,
             &receiver,
             real_amount(&self.robin_value) + random::<u32>() as u128,
+            self.wait_for,
         )
         .await;
 
@@ -41,17 +48,33 @@ impl RoundRobin {
     }
 }
 
+impl Preflight for RoundRobin {
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+            .call("Balances", "transfer")
+            .pool(&self.pool, self.passes)
+            .ensure(self.passes >= 2, "At least 2 passes are needed")
+    }
+}
+
 #[async_trait::async_trait]
-impl Scenario<Connection> for RoundRobin {
-    async fn play(&mut self, connection: &Connection, logger: &ScenarioLogging) -> AnyResult<()> {
+impl Scenario<NodeConnection> for RoundRobin {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
         logger.info("Starting scenario");
 
+        let accounts = connection.accounts(&self.pool)?;
+        logger.log_result(accounts.ensure_size(self.passes))?;
+
         for sender_idx in 0..self.passes {
             let receiver_idx = (sender_idx + 1) % self.passes;
-            let sender = Self::account(sender_idx);
-            let receiver = Self::account(receiver_idx);
+            let sender = accounts.keypair(sender_idx);
+            let receiver = accounts.account(receiver_idx);
 
-            self.pass_robin(connection, sender, account_from_keypair(&receiver), logger)
+            self.pass_robin(connection, sender, receiver, logger)
                 .await?;
 
             logger.debug(&*format!(
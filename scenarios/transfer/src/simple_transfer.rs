@@ -1,31 +1,39 @@
-use aleph_client::{account_from_keypair, substrate_api_client, Connection, KeyPair};
+use aleph_client::{account_from_keypair, substrate_api_client, KeyPair};
 use anyhow::Result as AnyResult;
 use rand::random;
 use serde::Deserialize;
 use substrate_api_client::AccountId;
 
-use chain_support::{keypair_derived_from_seed, real_amount};
+use chain_support::{real_amount, NodeConnection, Preflight, Requirements, WaitFor};
 use common::{Scenario, ScenarioLogging};
 
 use crate::try_transfer;
 
-/// This account should be included in the endowment list. The amount should be
-/// proportional to the `transfer_value` props parameter.
-const SENDER_SEED: &str = "//SimpleTransferSender";
 const RECEIVER_SEED: &str = "//SimpleTransferReceiver";
 
+fn default_sender_pool() -> String {
+    "SimpleTransferSender".to_string()
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct SimpleTransfer {
+    /// Account pool of the sender (only its first account is used). Its balance should be
+    /// proportional to `transfer_value`. By default, `SimpleTransferSender`.
+    #[serde(default = "default_sender_pool")]
+    sender_pool: String,
     transfer_value: u64,
+    /// Which status of the transfer should be awaited. By default, `Finalized`.
+    #[serde(default)]
+    wait_for: WaitFor,
 }
 
 impl SimpleTransfer {
-    fn sender() -> KeyPair {
-        keypair_derived_from_seed(SENDER_SEED)
+    fn sender(&self, connection: &NodeConnection) -> AnyResult<KeyPair> {
+        Ok(connection.accounts(&self.sender_pool)?.keypair(0))
     }
 
-    fn receiver() -> AccountId {
-        account_from_keypair(&keypair_derived_from_seed(RECEIVER_SEED))
+    fn receiver(connection: &NodeConnection) -> AccountId {
+        account_from_keypair(&connection.derive_keypair(RECEIVER_SEED))
     }
 
     fn transfer_value(&self) -> u128 {
@@ -33,16 +41,30 @@ impl SimpleTransfer {
     }
 }
 
+impl Preflight for SimpleTransfer {
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+            .call("Balances", "transfer")
+            .pool(&self.sender_pool, 1)
+    }
+}
+
 #[async_trait::async_trait]
-impl Scenario<Connection> for SimpleTransfer {
-    async fn play(&mut self, connection: &Connection, logger: &ScenarioLogging) -> AnyResult<()> {
+impl Scenario<NodeConnection> for SimpleTransfer {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
         logger.info("Ready to go");
 
+        let sender = self.sender(connection)?;
         let transfer_result = try_transfer(
             connection,
-            &Self::sender(),
-            &Self::receiver(),
+            &sender,
+            &Self::receiver(connection),
             self.transfer_value(),
+            self.wait_for,
         )
         .await;
         logger.log_result(transfer_result)?;
@@ -0,0 +1,47 @@
+//! Here we have 'copies' of the events from `pallet_treasury` (and `pallet_sudo`), since we need
+//! `Event` trait implemented for them.
+use aleph_client::substrate_api_client::{AccountId, Balance};
+use codec::Decode;
+use sp_runtime::DispatchError;
+
+use chain_support::Event;
+
+use crate::ProposalIndex;
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Treasury"]
+pub struct Proposed {
+    pub proposal_index: ProposalIndex,
+}
+
+/// Emitted at the beginning of every spend period, with the funds available for approved
+/// proposals.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Treasury"]
+pub struct Spending {
+    pub budget_remaining: Balance,
+}
+
+/// Emitted when an approved proposal is paid out (and its bond is returned).
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Treasury"]
+pub struct Awarded {
+    pub proposal_index: ProposalIndex,
+    pub award: Balance,
+    pub account: AccountId,
+}
+
+/// Emitted by `reject_proposal`, with the slashed bond.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Treasury"]
+pub struct Rejected {
+    pub proposal_index: ProposalIndex,
+    pub slashed: Balance,
+}
+
+/// Emitted by `sudo`, with the result of the dispatched call.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Sudo"]
+pub struct Sudid {
+    pub sudo_result: Result<(), DispatchError>,
+}
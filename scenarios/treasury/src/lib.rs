@@ -0,0 +1,93 @@
+use aleph_client::{
+    substrate_api_client::{AccountId, Balance},
+    BlockNumber,
+};
+use anyhow::Result as AnyResult;
+use sp_runtime::DispatchError;
+use thiserror::Error;
+
+use chain_support::{ChainScenarioKind, ChainScenarioRegistry};
+pub use treasury::{Decision, Treasury};
+
+mod events;
+mod storage;
+mod treasury;
+
+type ProposalIndex = u32;
+
+/// Gathers all possible errors from this crate.
+#[derive(Debug, Error)]
+pub enum TreasuryError {
+    #[error("💰❌ Constant `{0}` cannot be read from the metadata.")]
+    MissingConstant(&'static str),
+    #[error(
+        "💰❌ Spend period lasts {spend_period} blocks, which is more than {max} blocks we are \
+        willing to wait."
+    )]
+    SpendPeriodTooLong {
+        spend_period: BlockNumber,
+        max: BlockNumber,
+    },
+    #[error("💰❌ `{call}` dispatched with sudo has failed with `{error:?}`.")]
+    SudoFailed {
+        call: &'static str,
+        error: DispatchError,
+    },
+    #[error("💰❌ Proposal #{0} is not stored as it has been proposed.")]
+    UnexpectedProposal(ProposalIndex),
+    #[error("💰❌ Proposal #{0} is still stored after it has been closed.")]
+    ProposalNotRemoved(ProposalIndex),
+    #[error(
+        "💰❌ After `{step}`, reserved balance of `{account:?}` has changed by {found} instead of \
+        {expected}."
+    )]
+    UnexpectedReserve {
+        step: &'static str,
+        account: AccountId,
+        found: i128,
+        expected: i128,
+    },
+    #[error(
+        "💰❌ Proposal #{proposal_index} has been rejected with {found} slashed instead of the \
+        bond of {expected}."
+    )]
+    UnexpectedSlash {
+        proposal_index: ProposalIndex,
+        found: Balance,
+        expected: Balance,
+    },
+    #[error(
+        "💰❌ Proposal #{proposal_index} has awarded {award} to `{account:?}` instead of {value} to \
+        `{beneficiary:?}`."
+    )]
+    UnexpectedAward {
+        proposal_index: ProposalIndex,
+        award: Balance,
+        account: AccountId,
+        value: Balance,
+        beneficiary: AccountId,
+    },
+    #[error("💰❌ Beneficiary `{account:?}` has received {found} instead of {expected}.")]
+    UnexpectedPayout {
+        account: AccountId,
+        found: i128,
+        expected: i128,
+    },
+}
+
+/// Registers all the treasury scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "Treasury",
+        &[
+            "sudo: where the seed phrase of the sudo account is kept (like `{ Env = \
+            \"SUDO_SEED\" }`)",
+            "proposal_value: tokens requested in every proposal",
+            "decisions (optional): subset of `Approve` and `Reject`, by default both",
+            "max_spend_period (optional): longest spend period (in blocks) to wait for, by \
+            default 600",
+            "pool (optional): account pool of proposers and beneficiaries, by default `Treasury`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Treasury>()?)),
+    ))
+}
@@ -0,0 +1,56 @@
+//! Here we have 'copies' of the storage items of `pallet_treasury` (together with reading them),
+//! so that we can check the effects of the calls.
+use aleph_client::substrate_api_client::{AccountId, Balance};
+use anyhow::Result as AnyResult;
+use codec::Decode;
+
+use chain_support::NodeConnection;
+
+use crate::ProposalIndex;
+
+/// Copy of `pallet_treasury::Proposal`.
+#[derive(Clone, Debug, PartialEq, Eq, Decode)]
+pub struct Proposal {
+    pub proposer: AccountId,
+    pub value: Balance,
+    pub beneficiary: AccountId,
+    pub bond: Balance,
+}
+
+/// Proposal `index`, if it is still open (proposed or approved, but not paid out yet).
+pub async fn proposal(
+    connection: &NodeConnection,
+    index: ProposalIndex,
+) -> AnyResult<Option<Proposal>> {
+    connection
+        .read_blocking(move |api| Ok(api.get_storage_map("Treasury", "Proposals", index, None)?))
+        .await
+}
+
+/// Indices of the approved proposals, waiting for the next spend period.
+pub async fn approvals(connection: &NodeConnection) -> AnyResult<Vec<ProposalIndex>> {
+    connection
+        .read_blocking(|api| {
+            Ok(api
+                .get_storage_value("Treasury", "Approvals", None)?
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/// Reserved balance of `who`.
+pub async fn reserved_balance(connection: &NodeConnection, who: &AccountId) -> AnyResult<Balance> {
+    let who = who.clone();
+    connection
+        .read_blocking(move |api| Ok(api.get_account_data(&who)?.map_or(0, |data| data.reserved)))
+        .await
+}
+
+/// Account of the treasury pot, derived from `pallet_id` in the same way as
+/// `PalletId::into_account` does it.
+pub fn pot_account(pallet_id: [u8; 8]) -> AccountId {
+    let mut bytes = [0u8; 32];
+    bytes[..4].copy_from_slice(b"modl");
+    bytes[4..12].copy_from_slice(&pallet_id);
+    AccountId::from(bytes)
+}
@@ -0,0 +1,504 @@
+use std::time::Duration;
+
+use aleph_client::{
+    account_from_keypair, keypair_from_string,
+    substrate_api_client::{compose_call, AccountId, Balance, GenericAddress, XtStatus},
+    AnyConnection, BlockNumber, KeyPair,
+};
+use anyhow::{ensure, Result as AnyResult};
+use codec::{Compact, Decode, Encode};
+use rand::{seq::SliceRandom, thread_rng};
+use serde::Deserialize;
+
+use chain_support::{
+    free_balance, real_amount, send_located_xt, send_managed_xt, with_event_matching,
+    NodeConnection, Preflight, Requirements, SecretSource, XtLocation, EVENT_TIMEOUT,
+};
+use common::{Scenario, ScenarioLogging};
+
+use crate::{
+    events::{Awarded, Proposed, Rejected, Spending, Sudid},
+    storage::{approvals, pot_account, proposal, reserved_balance, Proposal},
+    ProposalIndex, TreasuryError,
+};
+
+/// How long we are willing to wait for a single block, when waiting for the spend period.
+const BLOCK_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+/// Denominator of `Permill`.
+const MILLION: Balance = 1_000_000;
+
+fn all_decisions() -> Vec<Decision> {
+    vec![Decision::Approve, Decision::Reject]
+}
+
+fn default_max_spend_period() -> BlockNumber {
+    600
+}
+
+chain_support::default_pool!("Treasury");
+
+/// What happens to a proposal after it has been submitted.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Decision {
+    /// `approve_proposal` and waiting for the next spend period, when the proposal is paid out.
+    Approve,
+    /// `reject_proposal`, which slashes the bond.
+    Reject,
+}
+
+/// Constants of `pallet_treasury` (and the existential deposit, which the pot must keep).
+#[derive(Clone, Copy, Debug, Default)]
+struct Constants {
+    /// `ProposalBond` in parts per million.
+    bond: u32,
+    bond_minimum: Balance,
+    bond_maximum: Option<Balance>,
+    spend_period: BlockNumber,
+    pallet_id: [u8; 8],
+    existential_deposit: Balance,
+}
+
+impl Constants {
+    /// Bond reserved for a proposal of `value`, computed just like in `pallet_treasury` (`Permill`
+    /// multiplication rounds to the nearest, preferring down).
+    fn bond(&self, value: Balance) -> Balance {
+        let parts = self.bond as Balance;
+        let remainder = value % MILLION * parts;
+        let rounding = Balance::from(remainder % MILLION > MILLION / 2);
+        let bond = value / MILLION * parts + remainder / MILLION + rounding;
+
+        let bond = bond.max(self.bond_minimum);
+        self.bond_maximum.map_or(bond, |maximum| bond.min(maximum))
+    }
+
+    /// The first block after `block` in which approved proposals are paid out.
+    fn next_spend(&self, block: BlockNumber) -> BlockNumber {
+        (block / self.spend_period + 1) * self.spend_period
+    }
+}
+
+/// Reads constant `name` of `pallet`.
+fn constant<T: Decode, C: AnyConnection>(
+    connection: &C,
+    pallet: &'static str,
+    name: &'static str,
+) -> AnyResult<T> {
+    connection
+        .as_connection()
+        .get_constant::<T>(pallet, name)
+        .map_err(|_| TreasuryError::MissingConstant(name).into())
+}
+
+/// Checks that reserved balance of `account` has changed by `expected` after `step`.
+fn check_reserve(
+    step: &'static str,
+    account: &AccountId,
+    before: Balance,
+    after: Balance,
+    expected: i128,
+) -> AnyResult<()> {
+    let found = after as i128 - before as i128;
+    ensure!(
+        found == expected,
+        TreasuryError::UnexpectedReserve {
+            step,
+            account: account.clone(),
+            found,
+            expected,
+        }
+    );
+    Ok(())
+}
+
+/// A scenario that goes through the lifecycle of treasury spend proposals.
+///
+/// Every run takes two random accounts of the pool (a proposer and a beneficiary). The proposer
+/// submits a proposal of `proposal_value`, which is then (depending on the decision chosen
+/// randomly from `decisions`) either rejected or approved with `Sudo::sudo`. An approved proposal
+/// is awaited until the next spend period, when it should be paid out.
+///
+/// `Proposed`, `Rejected`, `Spending` and `Awarded` events are checked, as well as the bond of the
+/// proposer: it should be reserved when proposing, slashed when rejected and returned when paid
+/// out. The beneficiary should receive exactly `proposal_value`.
+///
+/// Before approving, the proposer tops up the treasury pot if it could not afford all the approved
+/// proposals (ours included).
+///
+/// `ApproveOrigin` and `RejectOrigin` of the runtime must accept root.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Treasury {
+    /// Where the seed phrase of the sudo account is kept.
+    sudo: SecretSource,
+    /// Tokens requested in every proposal.
+    proposal_value: u64,
+    /// Decisions chosen from. By default, both.
+    #[serde(default = "all_decisions")]
+    decisions: Vec<Decision>,
+    /// Longest spend period (in blocks) we are willing to wait for. By default, 600.
+    #[serde(default = "default_max_spend_period")]
+    max_spend_period: BlockNumber,
+    /// Account pool of proposers and beneficiaries. By default, `Treasury`.
+    #[serde(default = "default_pool")]
+    pool: String,
+    /// Constants of the pallet (known after setup).
+    #[serde(skip)]
+    constants: Constants,
+}
+
+impl Treasury {
+    fn sudo_keypair(&self) -> AnyResult<KeyPair> {
+        Ok(keypair_from_string(&self.sudo.read()?))
+    }
+
+    /// Dispatches `call` (named `label`) with `Sudo::sudo` and checks its result. Returns where
+    /// the extrinsic has landed.
+    ///
+    /// Other scenarios may use the sudo key at the same time and `Sudid` does not say which call
+    /// it reports, so the event is taken only from this very extrinsic.
+    async fn sudo<Call: Encode + Clone>(
+        sudo: &KeyPair,
+        connection: &NodeConnection,
+        call: Call,
+        label: &'static str,
+    ) -> AnyResult<XtLocation> {
+        let call = compose_call!(connection.as_connection().metadata, "Sudo", "sudo", call);
+        let (_, location) = send_located_xt(connection, sudo, call, "Sudo::sudo").await?;
+        let event = connection
+            .extrinsic_event(location, |_: &Sudid| true)
+            .await?;
+        event
+            .sudo_result
+            .map_err(|error| TreasuryError::SudoFailed { call: label, error })?;
+        Ok(location)
+    }
+
+    async fn propose(
+        &self,
+        connection: &NodeConnection,
+        proposer: &KeyPair,
+        beneficiary: &AccountId,
+    ) -> AnyResult<ProposalIndex> {
+        let proposer_account = account_from_keypair(proposer);
+        let value = real_amount(&self.proposal_value);
+        let before = reserved_balance(connection, &proposer_account).await?;
+
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Treasury",
+            "propose_spend",
+            Compact(value),
+            GenericAddress::Id(beneficiary.clone())
+        );
+        // `Proposed` carries only the index, so it is taken from this very extrinsic.
+        let (_, location) =
+            send_located_xt(connection, proposer, call, "Treasury::propose_spend").await?;
+        let event = connection
+            .extrinsic_event(location, |_: &Proposed| true)
+            .await?;
+        let index = event.proposal_index;
+
+        let bond = self.constants.bond(value);
+        let expected = Proposal {
+            proposer: proposer_account.clone(),
+            value,
+            beneficiary: beneficiary.clone(),
+            bond,
+        };
+        ensure!(
+            proposal(connection, index).await? == Some(expected),
+            TreasuryError::UnexpectedProposal(index)
+        );
+        check_reserve(
+            "propose_spend",
+            &proposer_account,
+            before,
+            reserved_balance(connection, &proposer_account).await?,
+            bond as i128,
+        )?;
+        Ok(index)
+    }
+
+    async fn reject(
+        &self,
+        connection: &NodeConnection,
+        index: ProposalIndex,
+        proposer: &AccountId,
+    ) -> AnyResult<()> {
+        let bond = self.constants.bond(real_amount(&self.proposal_value));
+        let before = reserved_balance(connection, proposer).await?;
+
+        let sudo = self.sudo_keypair()?;
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Treasury",
+            "reject_proposal",
+            Compact(index)
+        );
+        let (_, event) = with_event_matching(
+            connection,
+            move |event: &Rejected| event.proposal_index == index,
+            EVENT_TIMEOUT,
+            Self::sudo(&sudo, connection, call, "Treasury::reject_proposal"),
+        )
+        .await?;
+
+        ensure!(
+            event.slashed == bond,
+            TreasuryError::UnexpectedSlash {
+                proposal_index: index,
+                found: event.slashed,
+                expected: bond,
+            }
+        );
+        ensure!(
+            proposal(connection, index).await?.is_none(),
+            TreasuryError::ProposalNotRemoved(index)
+        );
+        check_reserve(
+            "reject_proposal",
+            proposer,
+            before,
+            reserved_balance(connection, proposer).await?,
+            -(bond as i128),
+        )
+    }
+
+    /// Makes sure that the pot can afford all the approved proposals and `value` more.
+    async fn fund_pot(
+        &self,
+        connection: &NodeConnection,
+        funder: &KeyPair,
+        value: Balance,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let mut needed = value + self.constants.existential_deposit;
+        for index in approvals(connection).await? {
+            needed += proposal(connection, index)
+                .await?
+                .map_or(0, |proposal| proposal.value);
+        }
+        let pot = pot_account(self.constants.pallet_id);
+        let available = free_balance(connection, &pot).await?;
+        if available >= needed {
+            return Ok(());
+        }
+
+        logger.debug(format!("Topping up the pot with {}", needed - available));
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Balances",
+            "transfer",
+            GenericAddress::Id(pot),
+            Compact(needed - available)
+        );
+        send_managed_xt(
+            connection,
+            funder,
+            call,
+            "Balances::transfer",
+            XtStatus::Finalized,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Approves proposal `index` and waits until it is paid out.
+    async fn approve(
+        &self,
+        connection: &NodeConnection,
+        index: ProposalIndex,
+        proposer: &KeyPair,
+        beneficiary: &AccountId,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let proposer_account = account_from_keypair(proposer);
+        let value = real_amount(&self.proposal_value);
+        let bond = self.constants.bond(value);
+        self.fund_pot(connection, proposer, value, logger).await?;
+        let reserved_before = reserved_balance(connection, &proposer_account).await?;
+        let free_before = free_balance(connection, beneficiary).await?;
+
+        let sudo = self.sudo_keypair()?;
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Treasury",
+            "approve_proposal",
+            Compact(index)
+        );
+        let approve_and_wait = async {
+            let approved_at = Self::sudo(&sudo, connection, call, "Treasury::approve_proposal")
+                .await?
+                .block;
+            let spend_at = self.constants.next_spend(approved_at);
+            logger.debug(format!(
+                "Approved at #{}, waiting for the spend period at #{}",
+                approved_at, spend_at
+            ));
+            connection.wait_for_block(spend_at, BLOCK_TIME_LIMIT).await
+        };
+        let ((_, awarded), spending) = with_event_matching(
+            connection,
+            |_: &Spending| true,
+            EVENT_TIMEOUT,
+            with_event_matching(
+                connection,
+                move |event: &Awarded| event.proposal_index == index,
+                EVENT_TIMEOUT,
+                approve_and_wait,
+            ),
+        )
+        .await?;
+        logger.debug(format!(
+            "Spending with {} remaining",
+            spending.budget_remaining
+        ));
+
+        ensure!(
+            awarded.award == value && awarded.account == *beneficiary,
+            TreasuryError::UnexpectedAward {
+                proposal_index: index,
+                award: awarded.award,
+                account: awarded.account,
+                value,
+                beneficiary: beneficiary.clone(),
+            }
+        );
+        ensure!(
+            proposal(connection, index).await?.is_none(),
+            TreasuryError::ProposalNotRemoved(index)
+        );
+        check_reserve(
+            "approve_proposal",
+            &proposer_account,
+            reserved_before,
+            reserved_balance(connection, &proposer_account).await?,
+            -(bond as i128),
+        )?;
+
+        let found = free_balance(connection, beneficiary).await? as i128 - free_before as i128;
+        ensure!(
+            found == value as i128,
+            TreasuryError::UnexpectedPayout {
+                account: beneficiary.clone(),
+                found,
+                expected: value as i128,
+            }
+        );
+        Ok(())
+    }
+}
+
+impl Preflight for Treasury {
+    fn requirements(&self) -> Requirements {
+        let mut requirements = Requirements::default()
+            .call("Treasury", "propose_spend")
+            .call("Sudo", "sudo")
+            .pool(&self.pool, 2)
+            .ensure(!self.decisions.is_empty(), "There should be some decisions");
+        for decision in &self.decisions {
+            requirements = match decision {
+                Decision::Approve => requirements
+                    .call("Treasury", "approve_proposal")
+                    .call("Balances", "transfer"),
+                Decision::Reject => requirements.call("Treasury", "reject_proposal"),
+            };
+        }
+        requirements
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for Treasury {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let keypairs = connection.accounts(&self.pool)?.random_keypairs(2)?;
+        let (proposer, beneficiary) = (&keypairs[0], account_from_keypair(&keypairs[1]));
+        let proposer_account = account_from_keypair(proposer);
+        let decision = *self
+            .decisions
+            .choose(&mut thread_rng())
+            .expect("There are some decisions");
+
+        let index = logger.log_result(self.propose(connection, proposer, &beneficiary).await)?;
+        logger.info(format!(
+            "Proposal #{} by {}, decision: {:?}",
+            index, proposer_account, decision
+        ));
+
+        let result = match decision {
+            Decision::Approve => {
+                self.approve(connection, index, proposer, &beneficiary, logger)
+                    .await
+            }
+            Decision::Reject => self.reject(connection, index, &proposer_account).await,
+        };
+        logger.log_result(result)?;
+        logger.info(format!("Proposal #{} ({:?}) confirmed", index, decision));
+        Ok(())
+    }
+
+    /// Reads the constants and makes sure that we can wait for the spend period.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        _logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        self.constants = Constants {
+            bond: constant(connection, "Treasury", "ProposalBond")?,
+            bond_minimum: constant(connection, "Treasury", "ProposalBondMinimum")?,
+            bond_maximum: constant(connection, "Treasury", "ProposalBondMaximum")?,
+            spend_period: constant(connection, "Treasury", "SpendPeriod")?,
+            pallet_id: constant(connection, "Treasury", "PalletId")?,
+            existential_deposit: constant(connection, "Balances", "ExistentialDeposit")?,
+        };
+
+        let spend_period = self.constants.spend_period;
+        let approving = self
+            .decisions
+            .iter()
+            .any(|decision| matches!(decision, Decision::Approve));
+        ensure!(
+            !approving || spend_period <= self.max_spend_period,
+            TreasuryError::SpendPeriodTooLong {
+                spend_period,
+                max: self.max_spend_period,
+            }
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::treasury::Constants;
+
+    #[test]
+    fn bond_is_rounded_and_bounded_like_in_the_pallet() {
+        let constants = Constants {
+            // 5%
+            bond: 50_000,
+            bond_minimum: 10,
+            bond_maximum: Some(1_000),
+            ..Constants::default()
+        };
+
+        assert_eq!(10, constants.bond(100));
+        assert_eq!(50, constants.bond(1_000));
+        // 5% of 1_010 is 50.5, which is rounded down; 50.6 is rounded up.
+        assert_eq!(50, constants.bond(1_010));
+        assert_eq!(51, constants.bond(1_012));
+        assert_eq!(1_000, constants.bond(1_000_000));
+        assert_eq!(
+            2_000,
+            Constants {
+                bond_maximum: None,
+                ..constants
+            }
+            .bond(40_000)
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use aleph_client::{account_from_keypair, substrate_api_client::AccountId, AnyConnection, KeyPair};
+use anyhow::{ensure, Result as AnyResult};
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use serde::Deserialize;
+
+use chain_support::{
+    free_balances, real_amount, send_located_xt, NodeConnection, Preflight, Requirements,
+};
+use common::{Scenario, ScenarioLogging};
+
+use crate::{
+    calls::{derivative_account, BalanceChanges, BatchEvent, BatchKind, Call},
+    events::{BatchCompleted, BatchCompletedWithErrors, BatchInterrupted, ItemFailed},
+    UtilityError,
+};
+
+/// How many derivative accounts of every signer are used.
+const DERIVATIVES: u16 = 4;
+
+fn default_calls() -> usize {
+    4
+}
+
+chain_support::default_pool!("UtilityBatches");
+
+fn all_cases() -> Vec<Case> {
+    vec![
+        Case::BatchAll,
+        Case::Batch,
+        Case::ForceBatch,
+        Case::Nested,
+        Case::AsDerivative,
+    ]
+}
+
+/// What kind of batch is sent in a single run.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Case {
+    /// `batch_all` with one forbidden call: nothing should be transferred.
+    BatchAll,
+    /// `batch` with one forbidden call: only the transfers before it should succeed.
+    Batch,
+    /// `force_batch` with some forbidden calls: all the transfers should succeed.
+    ForceBatch,
+    /// `batch` or `force_batch` of `batch_all`s, some of them with a forbidden call.
+    Nested,
+    /// `batch` funding a derivative account and then transferring from it with `as_derivative`,
+    /// until a forbidden call.
+    AsDerivative,
+}
+
+/// A scenario that sends `pallet_utility` batches mixing valid and invalid calls.
+///
+/// Every run takes a random account of the pool and sends one of `cases` (chosen randomly) with
+/// `calls` calls. Valid calls are transfers of `transfer_value` to other accounts of the pool,
+/// invalid ones are `Balances::force_transfer`s, which fail with `BadOrigin`.
+///
+/// The outcome is simulated beforehand. Then, the event ending the batch (`BatchCompleted`,
+/// `BatchInterrupted` or `ItemFailed` with `BatchCompletedWithErrors`) is looked for among the
+/// events of the extrinsic and the balances of all the involved accounts (but the sender, who pays
+/// fees) are compared with the simulation.
+#[derive(Clone, Debug, Deserialize)]
+pub struct UtilityBatches {
+    /// Tokens sent in every transfer.
+    transfer_value: u64,
+    /// How many calls are put into the outermost batch. By default, 4.
+    #[serde(default = "default_calls")]
+    calls: usize,
+    /// Cases chosen from. By default, all of them.
+    #[serde(default = "all_cases")]
+    cases: Vec<Case>,
+    /// Account pool of the senders and receivers. By default, `UtilityBatches`.
+    #[serde(default = "default_pool")]
+    pool: String,
+}
+
+/// `kind` of `count` transfers to random `receivers`, with forbidden calls at `failing` positions.
+fn flat(
+    kind: BatchKind,
+    count: usize,
+    receivers: &[AccountId],
+    failing: &[usize],
+    rng: &mut impl Rng,
+) -> Call {
+    let calls = (0..count)
+        .map(|position| {
+            let to = receivers
+                .choose(rng)
+                .expect("There are some receivers")
+                .clone();
+            if failing.contains(&position) {
+                Call::Forbidden(to)
+            } else {
+                Call::Transfer(to)
+            }
+        })
+        .collect();
+    Call::Batch(kind, calls)
+}
+
+impl UtilityBatches {
+    /// Composes the outermost call for `case`.
+    fn compose(&self, case: Case, sender: &AccountId, receivers: &[AccountId]) -> Call {
+        let mut rng = thread_rng();
+        let first_failing = rng.gen_range(0..self.calls);
+        match case {
+            Case::BatchAll => flat(
+                BatchKind::BatchAll,
+                self.calls,
+                receivers,
+                &[first_failing],
+                &mut rng,
+            ),
+            Case::Batch => flat(
+                BatchKind::Batch,
+                self.calls,
+                receivers,
+                &[first_failing],
+                &mut rng,
+            ),
+            Case::ForceBatch => {
+                let mut failing = vec![first_failing];
+                failing.extend((0..self.calls).filter(|_| rng.gen_bool(0.3)));
+                flat(
+                    BatchKind::ForceBatch,
+                    self.calls,
+                    receivers,
+                    &failing,
+                    &mut rng,
+                )
+            }
+            Case::Nested => {
+                let outer = *[BatchKind::Batch, BatchKind::ForceBatch]
+                    .choose(&mut rng)
+                    .expect("There are some kinds");
+                let inner = (0..self.calls)
+                    .map(|position| {
+                        let failing = if position == first_failing || rng.gen_bool(0.3) {
+                            vec![1]
+                        } else {
+                            vec![]
+                        };
+                        flat(BatchKind::BatchAll, 2, receivers, &failing, &mut rng)
+                    })
+                    .collect();
+                Call::Batch(outer, inner)
+            }
+            Case::AsDerivative => {
+                let index = rng.gen_range(0..DERIVATIVES);
+                let derivative = derivative_account(sender, index);
+                // The derivative account gets twice as much as it sends before the failure.
+                let mut calls = vec![Call::Transfer(derivative); 2];
+                if let Call::Batch(_, from_derivative) =
+                    flat(BatchKind::Batch, self.calls, receivers, &[1], &mut rng)
+                {
+                    calls.extend(
+                        from_derivative
+                            .into_iter()
+                            .map(|call| Call::AsDerivative(index, Box::new(call))),
+                    );
+                }
+                Call::Batch(BatchKind::Batch, calls)
+            }
+        }
+    }
+
+    /// Sends `call` from `sender` and checks that it has emitted `event` (if any).
+    ///
+    /// Batch events do not say whose batch they end, so only the events of this very extrinsic
+    /// are taken into account.
+    async fn send(
+        &self,
+        connection: &NodeConnection,
+        sender: &KeyPair,
+        call: &Call,
+        event: Option<BatchEvent>,
+    ) -> AnyResult<()> {
+        let label = match call {
+            Call::Batch(kind, _) => kind.label(),
+            _ => unreachable!("The outermost call is always a batch"),
+        };
+        let encoded = call.encode_with(
+            &connection.as_connection(),
+            real_amount(&self.transfer_value),
+        );
+        let (_, location) = send_located_xt(connection, sender, encoded, label).await?;
+
+        match event {
+            // Reverted `batch_all` emits no event of its own.
+            None => Ok(()),
+            Some(BatchEvent::Completed) => connection
+                .extrinsic_event(location, |_: &BatchCompleted| true)
+                .await
+                .map(|_| ()),
+            Some(BatchEvent::Interrupted(index, error)) => connection
+                .extrinsic_event(location, |event: &BatchInterrupted| {
+                    event.index == index && event.error == error
+                })
+                .await
+                .map(|_| ()),
+            Some(BatchEvent::CompletedWithErrors(errors)) => {
+                connection
+                    .extrinsic_event(location, |event: &ItemFailed| event.error == errors[0])
+                    .await?;
+                connection
+                    .extrinsic_event(location, |_: &BatchCompletedWithErrors| true)
+                    .await
+                    .map(|_| ())
+            }
+        }
+    }
+
+    /// Checks that the balance of `account` has changed by `expected` in `case`.
+    fn check_change(case: Case, account: AccountId, found: i128, expected: i128) -> AnyResult<()> {
+        ensure!(
+            found == expected,
+            UtilityError::UnexpectedBalanceChange {
+                case,
+                account,
+                found,
+                expected,
+            }
+        );
+        Ok(())
+    }
+
+    async fn balances(
+        connection: &NodeConnection,
+        changes: &BalanceChanges,
+    ) -> AnyResult<Vec<(AccountId, i128)>> {
+        let accounts = changes.keys().cloned().collect::<Vec<_>>();
+        let balances = free_balances(connection, &accounts).await?;
+        Ok(accounts
+            .into_iter()
+            .zip(balances.into_iter().map(|balance| balance as i128))
+            .collect())
+    }
+}
+
+impl Preflight for UtilityBatches {
+    fn requirements(&self) -> Requirements {
+        let mut requirements = Requirements::default()
+            .call("Balances", "transfer")
+            .call("Balances", "force_transfer")
+            .pool(&self.pool, 2)
+            .ensure(
+                self.calls >= 2,
+                "There should be at least 2 calls in a batch",
+            )
+            .ensure(!self.cases.is_empty(), "There should be some cases");
+        for case in &self.cases {
+            requirements = match case {
+                Case::BatchAll => requirements.call("Utility", "batch_all"),
+                Case::Batch => requirements.call("Utility", "batch"),
+                Case::ForceBatch => requirements.call("Utility", "force_batch"),
+                Case::Nested => requirements
+                    .call("Utility", "batch")
+                    .call("Utility", "batch_all")
+                    .call("Utility", "force_batch"),
+                Case::AsDerivative => requirements
+                    .call("Utility", "batch")
+                    .call("Utility", "as_derivative"),
+            };
+        }
+        requirements
+    }
+}
+
+#[async_trait::async_trait]
+impl Scenario<NodeConnection> for UtilityBatches {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let accounts = connection.accounts(&self.pool)?;
+        let sender = accounts.random_keypair();
+        let sender_account = account_from_keypair(&sender);
+        let receivers = accounts
+            .accounts()
+            .into_iter()
+            .filter(|account| *account != sender_account)
+            .collect::<Vec<_>>();
+
+        let case = *self
+            .cases
+            .choose(&mut thread_rng())
+            .expect("There are some cases");
+        let call = self.compose(case, &sender_account, &receivers);
+        let (changes, event) = call.simulate(&sender_account, real_amount(&self.transfer_value));
+        logger.debug(format!(
+            "{:?} from {}: expecting {:?}",
+            case, sender_account, event
+        ));
+
+        let before = logger.log_result(Self::balances(connection, &changes).await)?;
+        logger.log_result(self.send(connection, &sender, &call, event).await)?;
+        let after = logger.log_result(Self::balances(connection, &changes).await)?;
+
+        for ((account, before), (_, after)) in before.into_iter().zip(after) {
+            let expected = changes[&account];
+            logger.log_result(Self::check_change(case, account, after - before, expected))?;
+        }
+        logger.info(format!("{:?} from {} confirmed", case, sender_account));
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+//! Calls put into batches, together with a simulation of their outcome, so that the scenario knows
+//! what to expect from the chain.
+use std::collections::BTreeMap;
+
+use aleph_client::{
+    substrate_api_client::{compose_call, AccountId, Balance, GenericAddress},
+    Connection,
+};
+use codec::{Compact, Encode, Output};
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    DispatchError,
+};
+
+/// Expected balance changes of all the accounts involved in the calls, except for the signer (who
+/// also pays fees).
+pub type BalanceChanges = BTreeMap<AccountId, i128>;
+
+/// A call that has already been encoded. It is encoded as it is (without length prefix), so that
+/// calls of different types can be put into the same batch.
+#[derive(Clone, Debug)]
+pub struct EncodedCall(Vec<u8>);
+
+impl Encode for EncodedCall {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+/// A call of `pallet_utility` dispatching a list of calls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchKind {
+    /// Stops at the first failure, keeping the effects of the calls before.
+    Batch,
+    /// Stops at the first failure and reverts everything.
+    BatchAll,
+    /// Dispatches all the calls, reverting only the failed ones.
+    ForceBatch,
+}
+
+impl BatchKind {
+    fn call_name(&self) -> &'static str {
+        match self {
+            BatchKind::Batch => "batch",
+            BatchKind::BatchAll => "batch_all",
+            BatchKind::ForceBatch => "force_batch",
+        }
+    }
+
+    /// Label of the corresponding extrinsic (for latency tracking).
+    pub fn label(&self) -> &'static str {
+        match self {
+            BatchKind::Batch => "Utility::batch",
+            BatchKind::BatchAll => "Utility::batch_all",
+            BatchKind::ForceBatch => "Utility::force_batch",
+        }
+    }
+}
+
+/// The event that a batch emits at the end.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchEvent {
+    /// `BatchCompleted`.
+    Completed,
+    /// `BatchInterrupted` with the index of the failed call and its error.
+    Interrupted(u32, DispatchError),
+    /// `BatchCompletedWithErrors`, preceded by `ItemFailed` with every of these errors.
+    CompletedWithErrors(Vec<DispatchError>),
+}
+
+/// A call put into a batch.
+#[derive(Clone, Debug)]
+pub enum Call {
+    /// `Balances::transfer` to the account. Always succeeds.
+    Transfer(AccountId),
+    /// `Balances::force_transfer` to the account. It requires root, so it always fails with
+    /// `BadOrigin`.
+    Forbidden(AccountId),
+    /// `batch`, `batch_all` or `force_batch` of the calls.
+    Batch(BatchKind, Vec<Call>),
+    /// `Utility::as_derivative` of the call, with the index of the derivative account.
+    AsDerivative(u16, Box<Call>),
+}
+
+/// Computes the derivative account in the same way as
+/// `pallet_utility::Pallet::derivative_account_id`.
+pub fn derivative_account(account: &AccountId, index: u16) -> AccountId {
+    let entropy = BlakeTwo256::hash_of(&(*b"modlpy/utilisuba", account, index));
+    AccountId::from(entropy.to_fixed_bytes())
+}
+
+impl Call {
+    /// Encodes the call, with every transfer sending `value`.
+    pub fn encode_with(&self, connection: &Connection, value: Balance) -> EncodedCall {
+        let metadata = &connection.metadata;
+        let encoded = match self {
+            Call::Transfer(to) => compose_call!(
+                metadata,
+                "Balances",
+                "transfer",
+                GenericAddress::Id(to.clone()),
+                Compact(value)
+            )
+            .encode(),
+            // The source does not matter, as the origin is checked first.
+            Call::Forbidden(to) => compose_call!(
+                metadata,
+                "Balances",
+                "force_transfer",
+                GenericAddress::Id(to.clone()),
+                GenericAddress::Id(to.clone()),
+                Compact(value)
+            )
+            .encode(),
+            Call::Batch(kind, calls) => compose_call!(
+                metadata,
+                "Utility",
+                kind.call_name(),
+                calls
+                    .iter()
+                    .map(|call| call.encode_with(connection, value))
+                    .collect::<Vec<_>>()
+            )
+            .encode(),
+            Call::AsDerivative(index, call) => compose_call!(
+                metadata,
+                "Utility",
+                "as_derivative",
+                *index,
+                call.encode_with(connection, value)
+            )
+            .encode(),
+        };
+        EncodedCall(encoded)
+    }
+
+    /// Simulates dispatching the call by `signer`, with every transfer sending `value`.
+    ///
+    /// Returns the expected balance changes (see `BalanceChanges`) and the event emitted at the
+    /// end (if the call is a batch which has not been reverted).
+    pub fn simulate(
+        &self,
+        signer: &AccountId,
+        value: Balance,
+    ) -> (BalanceChanges, Option<BatchEvent>) {
+        let mut changes = BalanceChanges::new();
+        self.involve(signer, signer, &mut changes);
+        let (_, event) = self.dispatch(signer, signer, value as i128, &mut changes);
+        (changes, event)
+    }
+
+    /// Records (with no change yet) all the accounts involved in the call.
+    fn involve(&self, origin: &AccountId, signer: &AccountId, changes: &mut BalanceChanges) {
+        match self {
+            Call::Transfer(to) | Call::Forbidden(to) => {
+                for account in [origin, to] {
+                    if account != signer {
+                        changes.entry(account.clone()).or_insert(0);
+                    }
+                }
+            }
+            Call::Batch(_, calls) => calls
+                .iter()
+                .for_each(|call| call.involve(origin, signer, changes)),
+            Call::AsDerivative(index, call) => {
+                call.involve(&derivative_account(origin, *index), signer, changes)
+            }
+        }
+    }
+
+    fn dispatch(
+        &self,
+        origin: &AccountId,
+        signer: &AccountId,
+        value: i128,
+        changes: &mut BalanceChanges,
+    ) -> (Result<(), DispatchError>, Option<BatchEvent>) {
+        match self {
+            Call::Transfer(to) => {
+                for (account, change) in [(to, value), (origin, -value)] {
+                    if account != signer {
+                        *changes.entry(account.clone()).or_insert(0) += change;
+                    }
+                }
+                (Ok(()), None)
+            }
+            Call::Forbidden(_) => (Err(DispatchError::BadOrigin), None),
+            Call::Batch(kind, calls) => {
+                Self::dispatch_batch(*kind, calls, origin, signer, value, changes)
+            }
+            Call::AsDerivative(index, call) => {
+                let derivative = derivative_account(origin, *index);
+                (call.dispatch(&derivative, signer, value, changes).0, None)
+            }
+        }
+    }
+
+    fn dispatch_batch(
+        kind: BatchKind,
+        calls: &[Call],
+        origin: &AccountId,
+        signer: &AccountId,
+        value: i128,
+        changes: &mut BalanceChanges,
+    ) -> (Result<(), DispatchError>, Option<BatchEvent>) {
+        let before = changes.clone();
+        let mut errors = Vec::new();
+        for (index, call) in calls.iter().enumerate() {
+            // Our calls fail before changing anything, so only whole batches need to be reverted.
+            let (result, _) = call.dispatch(origin, signer, value, changes);
+            let error = match result {
+                Ok(()) => continue,
+                Err(error) => error,
+            };
+            match kind {
+                BatchKind::Batch => {
+                    return (Ok(()), Some(BatchEvent::Interrupted(index as u32, error)))
+                }
+                BatchKind::BatchAll => {
+                    *changes = before;
+                    return (Err(error), None);
+                }
+                BatchKind::ForceBatch => errors.push(error),
+            }
+        }
+
+        let event = if errors.is_empty() {
+            BatchEvent::Completed
+        } else {
+            BatchEvent::CompletedWithErrors(errors)
+        };
+        (Ok(()), Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aleph_client::substrate_api_client::AccountId;
+    use sp_runtime::DispatchError;
+
+    use crate::calls::{derivative_account, BalanceChanges, BatchEvent, BatchKind, Call};
+
+    fn account(byte: u8) -> AccountId {
+        AccountId::from([byte; 32])
+    }
+
+    #[test]
+    fn nested_batches_are_reverted_separately() {
+        let signer = account(0);
+        let (alice, bob) = (account(1), account(2));
+        let call = Call::Batch(
+            BatchKind::ForceBatch,
+            vec![
+                Call::Batch(
+                    BatchKind::BatchAll,
+                    vec![Call::Transfer(alice.clone()), Call::Transfer(bob.clone())],
+                ),
+                Call::Batch(
+                    BatchKind::BatchAll,
+                    vec![Call::Transfer(alice.clone()), Call::Forbidden(bob.clone())],
+                ),
+            ],
+        );
+
+        let (changes, event) = call.simulate(&signer, 10);
+
+        assert_eq!(
+            Some(BatchEvent::CompletedWithErrors(vec![
+                DispatchError::BadOrigin
+            ])),
+            event
+        );
+        assert_eq!(BalanceChanges::from([(alice, 10), (bob, 10)]), changes);
+    }
+
+    #[test]
+    fn derivative_transfers_are_kept_until_interruption() {
+        let signer = account(0);
+        let receiver = account(1);
+        let derivative = derivative_account(&signer, 3);
+        let call = Call::Batch(
+            BatchKind::Batch,
+            vec![
+                Call::Transfer(derivative.clone()),
+                Call::AsDerivative(3, Box::new(Call::Transfer(receiver.clone()))),
+                Call::AsDerivative(3, Box::new(Call::Forbidden(receiver.clone()))),
+                Call::AsDerivative(3, Box::new(Call::Transfer(receiver.clone()))),
+            ],
+        );
+
+        let (changes, event) = call.simulate(&signer, 10);
+
+        assert_eq!(
+            Some(BatchEvent::Interrupted(2, DispatchError::BadOrigin)),
+            event
+        );
+        assert_eq!(
+            BalanceChanges::from([(derivative, 0), (receiver, 10)]),
+            changes
+        );
+    }
+}
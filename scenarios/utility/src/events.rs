@@ -0,0 +1,30 @@
+//! Here we have 'copies' of the events from `pallet_utility`, since we need `Event` trait
+//! implemented for them.
+use codec::Decode;
+use sp_runtime::DispatchError;
+
+use chain_support::Event;
+
+/// Emitted by `batch` when some call fails. `index` is the position of the failed call.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Utility"]
+pub struct BatchInterrupted {
+    pub index: u32,
+    pub error: DispatchError,
+}
+
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Utility"]
+pub struct BatchCompleted;
+
+/// Emitted by `force_batch` when some of the calls failed.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Utility"]
+pub struct BatchCompletedWithErrors;
+
+/// Emitted by `force_batch` for every failed call.
+#[derive(Clone, Debug, Decode, Event)]
+#[pallet = "Utility"]
+pub struct ItemFailed {
+    pub error: DispatchError,
+}
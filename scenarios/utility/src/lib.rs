@@ -0,0 +1,40 @@
+use aleph_client::substrate_api_client::AccountId;
+use anyhow::Result as AnyResult;
+use thiserror::Error;
+
+pub use batches::{Case, UtilityBatches};
+use chain_support::{ChainScenarioKind, ChainScenarioRegistry};
+
+mod batches;
+mod calls;
+mod events;
+
+/// Gathers all possible errors from this crate.
+#[derive(Debug, Error)]
+pub enum UtilityError {
+    #[error(
+        "🧰❌ In case {case:?}, balance of `{account:?}` has changed by {found} instead of \
+        {expected}."
+    )]
+    UnexpectedBalanceChange {
+        case: Case,
+        account: AccountId,
+        found: i128,
+        expected: i128,
+    },
+}
+
+/// Registers all the utility scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "UtilityBatches",
+        &[
+            "transfer_value: tokens sent in every transfer",
+            "calls (optional): how many calls are put into the outermost batch, by default 4",
+            "cases (optional): list of `BatchAll`, `Batch`, `ForceBatch`, `Nested` and \
+            `AsDerivative`, by default all of them",
+            "pool (optional): account pool of senders and receivers, by default `UtilityBatches`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<UtilityBatches>()?)),
+    ))
+}
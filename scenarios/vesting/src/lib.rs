@@ -1,9 +1,26 @@
-// Needed for `do_async!`.
-#![feature(fn_traits)]
+use anyhow::Result as AnyResult;
 
+use chain_support::{ChainScenarioKind, ChainScenarioRegistry};
 pub use schedules_merging::SchedulesMerging;
 pub use vesting::Vest;
 
 mod events;
 mod schedules_merging;
 mod vesting;
+
+/// Registers all the vesting scenarios.
+pub fn register(registry: &mut ChainScenarioRegistry) -> AnyResult<()> {
+    registry.register(ChainScenarioKind::new(
+        "VestingSchedulesMerging",
+        &[],
+        |_, _, _| Ok(Box::new(SchedulesMerging::default())),
+    ))?;
+    registry.register(ChainScenarioKind::new(
+        "VestingVest",
+        &[
+            "vest_kind: `Vest` or `VestOther`",
+            "source_pool (optional): account pool of the source, by default `VestSource` or `VestOtherSource`",
+        ],
+        |config, _, _| Ok(Box::new(config.try_into::<Vest>()?)),
+    ))
+}
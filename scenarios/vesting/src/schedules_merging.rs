@@ -1,44 +1,30 @@
 use std::time::Duration;
 
 use aleph_client::{
-    account_from_keypair, get_schedules, merge_schedules,
-    substrate_api_client::{AccountId, Balance},
-    vested_transfer, AnyConnection, BlockNumber, Connection, KeyPair, SignedConnection,
-    VestingSchedule,
+    account_from_keypair, get_schedules,
+    substrate_api_client::{compose_call, AccountId, Balance, GenericAddress, XtStatus},
+    AnyConnection, BlockNumber, KeyPair, VestingSchedule,
 };
 use anyhow::{ensure, Result as AnyResult};
 use codec::Decode;
-use rand::random;
 use thiserror::Error;
 
-use chain_support::{do_async, keypair_derived_from_seed, with_event_listening};
+use chain_support::{
+    send_managed_xt, with_event_listening, NodeConnection, Preflight, Requirements,
+};
 use common::{Scenario, ScenarioLogging};
 
 use crate::events::VestingUpdated;
 
-/// We operate on an account pool based on this seed. The final seeds will have a form of
-/// `ACCOUNT_SEED{i: usize}`.
-const ACCOUNT_SEED: &str = "//VestingSchedulesMerging";
-
-/// We expect that there are as many endowed accounts (of seed phrases: `ACCOUNT_SEED{i}`, where
-/// `i` is from 0 to this value (exclusively)).
-///
-/// This should not be less than `MAX_VESTING_SCHEDULES` constant (of pallet vesting).
-const AVAILABLE_ACCOUNTS: usize = 50;
-
-/// Returns keypair of the common account with index `idx`.
-fn compute_keypair(idx: usize) -> KeyPair {
-    keypair_derived_from_seed(format!("{}{}", ACCOUNT_SEED, idx))
-}
-
-/// Returns keypair of some random account from common pool with index `idx`.
-fn get_random_keypair() -> KeyPair {
-    compute_keypair(random::<usize>() % AVAILABLE_ACCOUNTS)
-}
+/// We operate on this account pool. It should have at least `MAX_VESTING_SCHEDULES` (constant of
+/// pallet vesting) accounts.
+const POOL: &str = "VestingSchedulesMerging";
 
 /// Possible errors from this module.
 #[derive(Debug, Error)]
 pub enum SchedulesMergingError {
+    #[error("🦺❌ Constant `Vesting::{0}` is not present in the runtime.")]
+    MissingConstant(&'static str),
     #[error("🦺❌ This scenario does not make sense when `MAX_VESTING_SCHEDULES` is less than 2.")]
     LimitTooLow,
     #[error("🦺❌ Couldn't reach `MAX_VESTING_SCHEDULES` for `{0:?}`.")]
@@ -73,39 +59,29 @@ pub enum SchedulesMergingError {
 ///  2. We perform at most `MaxVestingSchedules` vested transfers to receiver so that no other
 ///     vested transfer can succeed. If receiver already had some schedules, we just meet the limit.
 ///  3. Receiver merges all current schedules, exposing itself for further transfers.
-#[derive(Clone)]
+///
+/// Both constants are read from the runtime during setup, which fails if either of them is missing,
+/// `MaxVestingSchedules` is less than 2 or the pool has fewer accounts.
+#[derive(Clone, Default)]
 pub struct SchedulesMerging {
-    /// Corresponds to `MaxVestingSchedules` constant.
+    /// Corresponds to `MaxVestingSchedules` constant (read during setup).
     schedules_limit: usize,
-    /// Corresponds to `MinVestedTransfer` constant.
+    /// Corresponds to `MinVestedTransfer` constant (read during setup).
     transfer_value: Balance,
 }
 
 impl SchedulesMerging {
     /// Auxiliary method for reading pallet constant `constant` from `connection` metadata.
-    fn get_pallet_constant<C: AnyConnection, T: Decode>(
-        connection: &C,
+    async fn get_pallet_constant<T: Decode + Send + 'static>(
+        connection: &NodeConnection,
         constant: &'static str,
-    ) -> T {
+    ) -> AnyResult<T> {
         connection
-            .as_connection()
-            .get_constant::<T>("Vesting", constant)
-            .unwrap_or_else(|_| panic!("Constant `Vesting::{}` should be present", constant))
-    }
-
-    /// Constructs new `SchedulesMerging` object.
-    ///
-    /// Fails if either `MaxVestingSchedules` or `MinVestedTransfer` cannot be read from metadata,
-    /// or `MaxVestingSchedules` is less than 2.
-    pub fn new<C: AnyConnection>(connection: &C) -> AnyResult<Self> {
-        let schedules_limit: u32 = Self::get_pallet_constant(connection, "MaxVestingSchedules");
-        ensure!(schedules_limit >= 2, SchedulesMergingError::LimitTooLow);
-        let transfer_value = Self::get_pallet_constant(connection, "MinVestedTransfer");
-
-        Ok(Self {
-            schedules_limit: schedules_limit as usize,
-            transfer_value,
-        })
+            .read_blocking(move |api| {
+                api.get_constant::<T>("Vesting", constant)
+                    .map_err(|_| SchedulesMergingError::MissingConstant(constant).into())
+            })
+            .await
     }
 
     /// Every vested transfer will be of this form, i.e. the minimum amount of balance
@@ -114,29 +90,45 @@ impl SchedulesMerging {
         VestingSchedule::new(self.transfer_value, 1u128, BlockNumber::MAX)
     }
 
-    /// Performs vested transfer from `ACCOUNT_SEED{sender_idx}` to `receiver`.
+    /// Performs vested transfer from the account `sender_idx` of `POOL` to `receiver`.
     async fn transfer(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         receiver: &AccountId,
         sender_idx: usize,
     ) -> AnyResult<()> {
-        let sender = compute_keypair(sender_idx);
-        let connection = SignedConnection::from_any_connection(connection, sender);
-        let schedule = self.get_common_schedule();
-        do_async!(vested_transfer, connection, receiver, schedule)?
+        let sender = connection.accounts(POOL)?.keypair(sender_idx);
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Vesting",
+            "vested_transfer",
+            GenericAddress::Id(receiver.clone()),
+            self.get_common_schedule()
+        );
+        send_managed_xt(
+            connection,
+            &sender,
+            call,
+            "Vesting::vested_transfer",
+            XtStatus::Finalized,
+        )
+        .await
+        .map(|_| ())
     }
 
     /// Reads how many vesting schedules `receiver` has and how much balance there is in summary.
     ///
     /// Returns `Err(_)` only if the read call didn't succeed. In case when the account has no
     /// active schedules or the storage couldn't be decoded, it returns `Ok((0, 0))`.
-    fn get_vesting_info(
+    async fn get_vesting_info(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         receiver: &AccountId,
     ) -> AnyResult<(usize, Balance)> {
-        let schedules = get_schedules(connection, receiver.clone())?;
+        let receiver = receiver.clone();
+        let schedules = connection
+            .read_blocking(move |api| Ok(get_schedules(api, receiver)?))
+            .await?;
         let num_of_schedules = schedules.len();
         let locked = schedules
             .iter()
@@ -148,7 +140,7 @@ impl SchedulesMerging {
     /// `self.schedules_limit` active vesting schedules.
     async fn reach_limit(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         receiver: &AccountId,
         logger: &ScenarioLogging,
     ) -> AnyResult<Balance> {
@@ -157,7 +149,7 @@ impl SchedulesMerging {
             receiver,
         ));
 
-        let (num_of_schedules, locked) = self.get_vesting_info(connection, receiver)?;
+        let (num_of_schedules, locked) = self.get_vesting_info(connection, receiver).await?;
         ensure!(
             num_of_schedules < self.schedules_limit,
             SchedulesMergingError::LimitAlreadyReached(receiver.clone())
@@ -193,7 +185,7 @@ impl SchedulesMerging {
             ));
         }
 
-        let (num_of_schedules, locked) = self.get_vesting_info(connection, receiver)?;
+        let (num_of_schedules, locked) = self.get_vesting_info(connection, receiver).await?;
         ensure!(
             num_of_schedules == self.schedules_limit,
             SchedulesMergingError::ReachingLimitFailure(receiver.clone())
@@ -212,7 +204,7 @@ impl SchedulesMerging {
     /// It is passed here to save requesting the storage.
     async fn merge_schedules(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         receiver: &KeyPair,
         total_locked: Balance,
         logger: &ScenarioLogging,
@@ -227,14 +219,23 @@ impl SchedulesMerging {
             VestingUpdated::from_relevant_fields(receiver_account.clone(), total_locked);
         let timeout = Duration::from_secs(2);
 
-        let connection = SignedConnection::from_any_connection(connection, receiver.clone());
         for i in 1..self.schedules_limit {
-            with_event_listening(&connection, expected_event.clone(), timeout, async {
-                match do_async!(merge_schedules, connection, 0, 1) {
-                    Ok(Ok(())) => Ok(()),
-                    Ok(Err(e)) => Err(e),
-                    Err(e) => Err(e.into()),
-                }
+            let call = compose_call!(
+                connection.as_connection().metadata,
+                "Vesting",
+                "merge_schedules",
+                0u32,
+                1u32
+            );
+            with_event_listening(connection, expected_event.clone(), timeout, async {
+                send_managed_xt(
+                    connection,
+                    receiver,
+                    call,
+                    "Vesting::merge_schedules",
+                    XtStatus::Finalized,
+                )
+                .await
             })
             .await
             .map(|_| ())?;
@@ -251,12 +252,27 @@ impl SchedulesMerging {
     }
 }
 
+impl Preflight for SchedulesMerging {
+    /// `MaxVestingSchedules` is not known before setup, so only the smallest sensible pool is
+    /// required here.
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+            .call("Vesting", "vested_transfer")
+            .call("Vesting", "merge_schedules")
+            .pool(POOL, 2)
+    }
+}
+
 #[async_trait::async_trait]
-impl Scenario<Connection> for SchedulesMerging {
-    async fn play(&mut self, connection: &Connection, logger: &ScenarioLogging) -> AnyResult<()> {
+impl Scenario<NodeConnection> for SchedulesMerging {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
         logger.info("Starting scenario");
 
-        let receiver = get_random_keypair();
+        let receiver = connection.accounts(POOL)?.random_keypair();
         let receiver_account = account_from_keypair(&receiver);
 
         let locked_before_merging = logger.log_result(
@@ -269,7 +285,7 @@ impl Scenario<Connection> for SchedulesMerging {
         )?;
 
         let (num_of_schedules, locked_after_merging) =
-            self.get_vesting_info(connection, &receiver_account)?;
+            self.get_vesting_info(connection, &receiver_account).await?;
         ensure!(
             num_of_schedules == 1,
             SchedulesMergingError::MergingFailureNumber(receiver_account.clone())
@@ -286,4 +302,19 @@ impl Scenario<Connection> for SchedulesMerging {
         logger.info("Successfully finished scenario");
         Ok(())
     }
+
+    /// Reads `MaxVestingSchedules` and `MinVestedTransfer` and checks that the pool is large enough.
+    async fn setup(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
+        let schedules_limit: u32 =
+            Self::get_pallet_constant(connection, "MaxVestingSchedules").await?;
+        ensure!(schedules_limit >= 2, SchedulesMergingError::LimitTooLow);
+        self.schedules_limit = schedules_limit as usize;
+        self.transfer_value = Self::get_pallet_constant(connection, "MinVestedTransfer").await?;
+
+        logger.log_result(connection.accounts(POOL)?.ensure_size(self.schedules_limit))
+    }
 }
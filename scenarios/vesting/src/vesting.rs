@@ -1,19 +1,19 @@
 use crate::events::VestingUpdated;
 use aleph_client::{
-    account_from_keypair, substrate_api_client::AccountId, vest, vest_other, vested_transfer,
-    Connection, KeyPair, SignedConnection, VestingSchedule,
+    account_from_keypair,
+    substrate_api_client::{compose_call, AccountId, GenericAddress, XtStatus},
+    AnyConnection, KeyPair, VestingSchedule,
 };
 use anyhow::Result as AnyResult;
-use chain_support::{do_async, keypair_derived_from_seed, with_event_matching};
+use chain_support::{
+    send_managed_xt, with_event_matching, NodeConnection, Preflight, Requirements,
+};
 use common::{Scenario, ScenarioLogging};
 use rand::random;
 use serde::Deserialize;
 use std::time::Duration;
-use thiserror::Error;
-use tokio::{task::spawn_blocking, time::sleep};
+use tokio::time::sleep;
 
-const SOURCE_VEST_SEED: &str = "//Vest/Source/Vest";
-const SOURCE_VEST_OTHER_SEED: &str = "//Vest/Source/VestOther";
 const RECIPIENT_SEED: &str = "//Vest/Recipient";
 const INITIAL_VESTED: u128 = 1_000_000_000;
 const PER_BLOCK: u128 = 1_000_000;
@@ -26,14 +26,8 @@ pub enum VestKind {
     VestOther,
 }
 
-#[derive(Debug, Error)]
-pub enum VestError {
-    #[error("🦺❌ The height of the current block could not be retrieved from storage.")]
-    NoCurrentBlock,
-}
-
-fn random_recipient() -> KeyPair {
-    keypair_derived_from_seed(format!("{}/{}", RECIPIENT_SEED, random::<u128>()).as_str())
+fn random_recipient(connection: &NodeConnection) -> KeyPair {
+    connection.derive_keypair(format!("{}/{}", RECIPIENT_SEED, random::<u128>()).as_str())
 }
 
 /// A scenario that goes through the vesting process.
@@ -45,61 +39,109 @@ fn random_recipient() -> KeyPair {
 #[derive(Clone, Debug, Deserialize)]
 pub struct Vest {
     vest_kind: VestKind,
+    /// Account pool of the source (only its first account is used). By default, `VestSource` for
+    /// `Vest` and `VestOtherSource` for `VestOther`.
+    #[serde(default)]
+    source_pool: Option<String>,
 }
 
 impl Vest {
-    fn source(&self) -> KeyPair {
-        match self.vest_kind {
-            VestKind::Vest => keypair_derived_from_seed(SOURCE_VEST_SEED),
-            VestKind::VestOther => keypair_derived_from_seed(SOURCE_VEST_OTHER_SEED),
+    fn source_pool(&self) -> &str {
+        match (&self.source_pool, self.vest_kind) {
+            (Some(pool), _) => pool.as_str(),
+            (None, VestKind::Vest) => "VestSource",
+            (None, VestKind::VestOther) => "VestOtherSource",
         }
     }
 
-    async fn current_block(connection: &Connection) -> AnyResult<u32> {
-        let connection = connection.clone();
-        let result =
-            spawn_blocking(move || connection.get_storage_value::<u32>("System", "Number", None))
-                .await??
-                .ok_or(VestError::NoCurrentBlock)?;
-        Ok(result)
+    fn source(&self, connection: &NodeConnection) -> AnyResult<KeyPair> {
+        Ok(connection.accounts(self.source_pool())?.keypair(0))
     }
 
-    async fn vest_action(&self, connection: &Connection, target: &KeyPair) -> AnyResult<()> {
+    async fn current_block(connection: &NodeConnection) -> AnyResult<u32> {
+        connection.best_block_number().await
+    }
+
+    async fn vest_action(&self, connection: &NodeConnection, target: &KeyPair) -> AnyResult<()> {
         match self.vest_kind {
             VestKind::Vest => Self::vest(connection, target).await,
             VestKind::VestOther => {
-                Self::vest_other(connection, self.source(), &account_from_keypair(target)).await
+                Self::vest_other(
+                    connection,
+                    self.source(connection)?,
+                    &account_from_keypair(target),
+                )
+                .await
             }
         }
     }
 
     async fn vest_other(
-        connection: &Connection,
+        connection: &NodeConnection,
         signer: KeyPair,
         target: &AccountId,
     ) -> AnyResult<()> {
-        let connection = SignedConnection::from_any_connection(connection, signer);
-        do_async!(vest_other, connection, target)?
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Vesting",
+            "vest_other",
+            GenericAddress::Id(target.clone())
+        );
+        send_managed_xt(
+            connection,
+            &signer,
+            call,
+            "Vesting::vest_other",
+            XtStatus::Finalized,
+        )
+        .await
+        .map(|_| ())
     }
 
-    async fn vest(connection: &Connection, account: &KeyPair) -> AnyResult<()> {
-        let connection = SignedConnection::from_any_connection(connection, account.clone());
-        do_async!(vest, connection)?
+    async fn vest(connection: &NodeConnection, account: &KeyPair) -> AnyResult<()> {
+        let call = compose_call!(connection.as_connection().metadata, "Vesting", "vest");
+        send_managed_xt(
+            connection,
+            account,
+            call,
+            "Vesting::vest",
+            XtStatus::Finalized,
+        )
+        .await
+        .map(|_| ())
     }
 
     async fn vested_transfer(
         &self,
-        connection: &Connection,
+        connection: &NodeConnection,
         receiver: &AccountId,
         schedule: VestingSchedule,
     ) -> AnyResult<()> {
-        let connection = SignedConnection::from_any_connection(connection, self.source());
-        do_async!(vested_transfer, connection, receiver, schedule)?
+        let call = compose_call!(
+            connection.as_connection().metadata,
+            "Vesting",
+            "vested_transfer",
+            GenericAddress::Id(receiver.clone()),
+            schedule
+        );
+        send_managed_xt(
+            connection,
+            &self.source(connection)?,
+            call,
+            "Vesting::vested_transfer",
+            XtStatus::Finalized,
+        )
+        .await
+        .map(|_| ())
     }
 
-    async fn do_play(&self, connection: &Connection, logger: &ScenarioLogging) -> AnyResult<()> {
+    async fn do_play(
+        &self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
         let current_block = Self::current_block(connection).await?;
-        let recipient = random_recipient();
+        let recipient = random_recipient(connection);
         let recipient_copy = recipient.clone();
 
         logger.info("Setting up with vested_transfer");
@@ -138,9 +180,26 @@ impl Vest {
     }
 }
 
+impl Preflight for Vest {
+    fn requirements(&self) -> Requirements {
+        let vest = match self.vest_kind {
+            VestKind::Vest => "vest",
+            VestKind::VestOther => "vest_other",
+        };
+        Requirements::default()
+            .call("Vesting", "vested_transfer")
+            .call("Vesting", vest)
+            .pool(self.source_pool(), 1)
+    }
+}
+
 #[async_trait::async_trait]
-impl Scenario<Connection> for Vest {
-    async fn play(&mut self, connection: &Connection, logger: &ScenarioLogging) -> AnyResult<()> {
+impl Scenario<NodeConnection> for Vest {
+    async fn play(
+        &mut self,
+        connection: &NodeConnection,
+        logger: &ScenarioLogging,
+    ) -> AnyResult<()> {
         logger.log_result(self.do_play(connection, logger).await)
     }
 }
@@ -1,3 +1,6 @@
+use std::path::PathBuf;
+
+use chain_support::SecretSource;
 use clap::Parser;
 
 #[derive(Debug, Parser, Clone)]
@@ -12,12 +15,41 @@ pub struct CliConfig {
     #[clap(long, default_value = "//Alice")]
     pub phrase: String,
 
-    /// Path to the config file.
-    #[clap(long, default_value = "Config.toml")]
-    pub config_file: String,
+    /// Path to the timetable declaring account pools.
+    #[clap(long, default_value = "../Timetable.toml")]
+    pub timetable: String,
+
+    /// Environment (from the timetable) whose base seed should be used. By default, the first one.
+    #[clap(long)]
+    pub environment: Option<String>,
 
     /// If this flag is set, then initial balances are transferred from sudo account.
     /// Otherwise, they are set with `set_balance` extrinsic.
     #[clap(long)]
     pub transfer: bool,
+
+    /// Env variable with the base seed for endowed accounts. If no secret source is given, the one
+    /// of the environment is used.
+    #[clap(long, group = "secret")]
+    pub secret_env: Option<String>,
+
+    /// File with the base seed for endowed accounts. It must be accessible only by its owner.
+    #[clap(long, group = "secret")]
+    pub secret_file: Option<PathBuf>,
+
+    /// Substrate keystore directory with the base seed for endowed accounts (as an `acco` key).
+    #[clap(long, group = "secret")]
+    pub keystore: Option<PathBuf>,
+}
+
+impl CliConfig {
+    /// Secret source chosen with `--secret-env`, `--secret-file` or `--keystore` (if any).
+    pub fn secret(&self) -> Option<SecretSource> {
+        match (&self.secret_env, &self.secret_file, &self.keystore) {
+            (Some(variable), _, _) => Some(SecretSource::Env(variable.clone())),
+            (_, Some(path), _) => Some(SecretSource::File(path.clone())),
+            (_, _, Some(path)) => Some(SecretSource::Keystore(path.clone())),
+            _ => None,
+        }
+    }
 }
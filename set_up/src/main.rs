@@ -1,27 +1,83 @@
 use std::fs;
 
+use chain_support::{AccountPool, AccountPools, SecretSource};
 use clap::Parser;
 use serde::Deserialize;
 
 use crate::{
     cli_config::CliConfig,
-    endowment::{perform_endowments, Endowment},
+    endowment::{perform_endowments, read_secret},
 };
 
 mod cli_config;
 mod endowment;
 
+/// The part of `Timetable.toml` that is needed for endowing accounts.
 #[derive(Clone, Debug, Deserialize)]
-struct Config {
-    pub endowments: Vec<Endowment>,
+struct Timetable {
+    #[serde(default)]
+    account_pools: Vec<AccountPool>,
+    environments: Vec<Environment>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Environment {
+    name: String,
+    #[serde(default)]
+    secret: SecretSource,
+    #[serde(default)]
+    scenarios: Vec<Scenario>,
+}
+
+/// The part of a scenario entry that is needed for endowing accounts.
+#[derive(Clone, Debug, Deserialize)]
+struct Scenario {
+    /// A scenario with its own `secret` derives its accounts from it.
+    #[serde(default)]
+    secret: Option<SecretSource>,
+}
+
+impl Timetable {
+    /// Environment named `name` or the first one, if no name is given.
+    fn environment(&self, name: Option<&str>) -> &Environment {
+        match name {
+            Some(name) => self
+                .environments
+                .iter()
+                .find(|environment| environment.name == name)
+                .unwrap_or_else(|| panic!("There is no environment named `{}`", name)),
+            None => self
+                .environments
+                .first()
+                .expect("Timetable should define at least one environment"),
+        }
+    }
 }
 
 fn main() {
     let cli_config: CliConfig = CliConfig::parse();
 
-    let config_content = fs::read_to_string(cli_config.config_file.clone())
-        .expect("Config file should exist and be readable");
-    let config: Config = toml::from_str(&*config_content).expect("Should deserialize");
+    let timetable_content = fs::read_to_string(cli_config.timetable.clone())
+        .expect("Timetable should exist and be readable");
+    let timetable: Timetable = toml::from_str(&*timetable_content).expect("Should deserialize");
+
+    let environment = timetable.environment(cli_config.environment.as_deref());
+    let secret = cli_config
+        .secret()
+        .unwrap_or_else(|| environment.secret.clone());
+    let base_seed = read_secret(&secret);
+
+    let mut scenario_base_seeds = environment
+        .scenarios
+        .iter()
+        .filter_map(|scenario| scenario.secret.as_ref())
+        .map(read_secret)
+        .filter(|scenario_base_seed| *scenario_base_seed != base_seed)
+        .collect::<Vec<_>>();
+    scenario_base_seeds.sort();
+    scenario_base_seeds.dedup();
+
+    let pools = AccountPools::new(&timetable.account_pools).expect("Should read account pools");
 
-    perform_endowments(&cli_config, &config.endowments);
+    perform_endowments(&cli_config, &pools, &base_seed, &scenario_base_seeds);
 }
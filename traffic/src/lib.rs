@@ -1,4 +1,5 @@
-pub use schedule::{run_schedule, EventListener};
+pub use logger::{setup_logging, Logger};
+pub use schedule::{run_once, run_schedule, EventListener};
 
 mod logger;
 mod schedule;
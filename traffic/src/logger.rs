@@ -5,23 +5,55 @@ use std::{
 
 use chrono::Local;
 use futures::channel::mpsc::UnboundedSender;
-use log::{Log, Metadata, Record};
+use log::{LevelFilter, Log, Metadata, Record};
 
-use common::Ident;
+use common::{split_log_target, Ident};
 
 pub type LogLine = (Ident, String);
 
+/// Scenario identifiers are unique only within an environment, so subscriptions are kept per
+/// environment and scenario.
+type Subscriptions = HashMap<(String, Ident), Vec<UnboundedSender<LogLine>>>;
+
+/// Forwards log records of scenarios to their subscribers.
 #[derive(Default, Clone)]
 pub struct Logger {
-    subscriptions: Arc<Mutex<HashMap<Ident, Vec<UnboundedSender<LogLine>>>>>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+}
+
+/// Installs a new `Logger` as the global logger and returns it (for subscribing).
+///
+/// The global logger can be set only once per process, so this should be called just once and
+/// the returned logger shared by all the schedules.
+pub fn setup_logging() -> Logger {
+    let logger = Logger::default();
+    if log::set_boxed_logger(Box::new(logger.clone())).is_ok() {
+        let level = match option_env!("MAX_LOG_LEVEL")
+            .unwrap_or("DEBUG")
+            .to_lowercase()
+            .as_str()
+        {
+            "off" => LevelFilter::Off,
+            "error" => LevelFilter::Error,
+            "warn" => LevelFilter::Warn,
+            "info" => LevelFilter::Info,
+            "trace" => LevelFilter::Trace,
+            _ => LevelFilter::Debug,
+        };
+        log::set_max_level(level);
+        logger
+    } else {
+        panic!("Cannot setup logger")
+    }
 }
 
 impl Logger {
-    pub fn subscribe(&self, target: Ident, sender: UnboundedSender<LogLine>) {
+    /// Forwards the log records of scenario `ident` scheduled in `environment` to `sender`.
+    pub fn subscribe(&self, environment: &str, ident: Ident, sender: UnboundedSender<LogLine>) {
         self.subscriptions
             .lock()
             .expect("Should acquire lock")
-            .entry(target)
+            .entry((environment.to_string(), ident))
             .or_insert_with(Vec::new)
             .push(sender);
     }
@@ -42,15 +74,19 @@ impl Log for Logger {
     }
 
     fn log(&self, record: &Record) {
-        let target = record.target().into();
+        let (environment, ident) = match split_log_target(record.target()) {
+            Some(source) => source,
+            None => return,
+        };
+        let key = (environment.to_string(), ident.clone());
         if let Some(senders) = self
             .subscriptions
             .lock()
             .expect("Should acquire lock")
-            .get_mut(&target)
+            .get_mut(&key)
         {
             senders.retain(|s| {
-                s.unbounded_send((target.clone(), Self::format(record)))
+                s.unbounded_send((ident.clone(), Self::format(record)))
                     .is_ok()
             })
         }
@@ -2,10 +2,15 @@ use std::sync::{Arc, Mutex};
 
 use futures::{
     channel::{mpsc, mpsc::UnboundedReceiver},
-    StreamExt,
+    select, FutureExt, StreamExt,
+};
+use log::{error, info, warn};
+use tokio::{
+    signal::ctrl_c,
+    sync::watch::{self, Receiver},
+    task::JoinHandle,
+    time::Interval,
 };
-use log::{error, LevelFilter};
-use tokio::task::JoinHandle;
 
 use common::{Ident, ScheduledScenario};
 
@@ -17,6 +22,8 @@ pub trait EventListener: Send + Sync + Clone {
     fn report_success(&mut self, scenario_ident: Ident);
     fn report_launch(&mut self, scenario_ident: Ident);
     fn report_failure(&mut self, scenario_ident: Ident);
+    fn report_skip(&mut self, scenario_ident: Ident);
+    fn report_disabled(&mut self, scenario_ident: Ident, reason: String);
     fn report_logs(&mut self, scenario_ident: Ident, log: String);
 }
 
@@ -37,56 +44,118 @@ impl<EL: EventListener> EventListener for Arc<Mutex<EL>> {
         self.lock().unwrap().report_failure(scenario_ident)
     }
 
+    fn report_skip(&mut self, scenario_ident: Ident) {
+        self.lock().unwrap().report_skip(scenario_ident)
+    }
+
+    fn report_disabled(&mut self, scenario_ident: Ident, reason: String) {
+        self.lock().unwrap().report_disabled(scenario_ident, reason)
+    }
+
     fn report_logs(&mut self, scenario_ident: Ident, log: String) {
         self.lock().unwrap().report_logs(scenario_ident, log)
     }
 }
 
-/// Firstly schedules all the scenarios according to their declared intervals. Then, in a loop,
-/// waits for the next ready scenario and launches it.
+/// Firstly sets up all the scenarios and schedules them according to their declared intervals.
+/// Then, in a loop, waits for the next ready scenario and launches it.
+///
+/// Scenarios that fail their setup are disabled, the rest keeps running. Runs falling on a period
+/// when no node is available (or when the scenario health check fails) are skipped.
+///
+/// Logs of the scenarios are passed to `event_listener` through `logger` (see `setup_logging`),
+/// which may be shared by many schedules (e.g. of different environments).
+///
+/// Returns after Ctrl+C, once all the scenarios have finished their current runs and have been
+/// torn down.
 pub async fn run_schedule<C: Send + Sync + 'static, EL: 'static + EventListener>(
     scenarios: Vec<ScheduledScenario<C>>,
     event_listener: EL,
+    logger: Logger,
 ) {
-    let logger = setup_logging();
     let (report_logs, receive_logs) = mpsc::unbounded();
 
     forward_logging(receive_logs, event_listener.clone());
 
+    let (stop, shutdown) = watch::channel(false);
+    tokio::spawn(async move {
+        if ctrl_c().await.is_ok() {
+            let _ = stop.send(true);
+        }
+    });
+
     let handles = scenarios
         .into_iter()
         .map(|s| {
-            logger.subscribe(s.ident(), report_logs.clone());
-            tokio::spawn(schedule_scenario(s, event_listener.clone()))
+            logger.subscribe(s.environment(), s.ident(), report_logs.clone());
+            tokio::spawn(schedule_scenario(
+                s,
+                event_listener.clone(),
+                shutdown.clone(),
+            ))
         })
         .collect::<Vec<JoinHandle<_>>>();
 
     for handle in handles {
-        let _ = handle.await;
-        error!("Should never stop scheduling scenario")
+        if handle.await.is_err() {
+            error!("Scheduling of some scenario has panicked")
+        }
     }
 }
 
-fn setup_logging() -> Logger {
-    let logger = Logger::default();
-    if log::set_boxed_logger(Box::new(logger.clone())).is_ok() {
-        let level = match option_env!("MAX_LOG_LEVEL")
-            .unwrap_or("DEBUG")
-            .to_lowercase()
-            .as_str()
-        {
-            "off" => LevelFilter::Off,
-            "error" => LevelFilter::Error,
-            "warn" => LevelFilter::Warn,
-            "info" => LevelFilter::Info,
-            "trace" => LevelFilter::Trace,
-            _ => LevelFilter::Debug,
-        };
-        log::set_max_level(level);
-        logger
-    } else {
-        panic!("Cannot setup logger")
+/// Launches `scenario` just once (right away), printing its logs to stdout. Returns whether the
+/// run succeeded.
+///
+/// A run that cannot be launched, because no node is available, is considered failed.
+pub async fn run_once<C: Send + Sync + 'static>(
+    mut scenario: ScheduledScenario<C>,
+    logger: Logger,
+) -> bool {
+    let (report_logs, mut receive_logs) = mpsc::unbounded();
+    logger.subscribe(scenario.environment(), scenario.ident(), report_logs);
+
+    let id = scenario.ident();
+    let connection = match scenario.next_connection() {
+        Some(connection) => connection,
+        None => {
+            println!("No node is available. Cannot launch `{}`", id.0);
+            return false;
+        }
+    };
+
+    let lifecycle = async {
+        if let Err(e) = scenario.setup(&connection).await {
+            println!("Setup of `{}` failed: {}", id.0, e);
+            return false;
+        }
+        if let Err(e) = scenario.health_check(&connection).await {
+            println!("Health check of `{}` failed: {}", id.0, e);
+            return false;
+        }
+        let succeeded = scenario.play(&connection).await.is_ok();
+        if let Err(e) = scenario.teardown(&connection).await {
+            println!("Teardown of `{}` failed: {}", id.0, e);
+        }
+        succeeded
+    };
+
+    let succeeded = {
+        let mut play = Box::pin(lifecycle.fuse());
+        loop {
+            select! {
+                succeeded = play => break succeeded,
+                line = receive_logs.next() => if let Some((_, line)) = line {
+                    println!("{}", line)
+                },
+            }
+        }
+    };
+
+    receive_logs.close();
+    while let Some((_, line)) = receive_logs.next().await {
+        println!("{}", line);
     }
+    succeeded
 }
 
 fn forward_logging<EL: 'static + EventListener>(
@@ -104,23 +173,172 @@ fn forward_logging<EL: 'static + EventListener>(
     });
 }
 
+/// Waits for the next tick of `interval`. Returns `false` if shutdown has been requested first.
+async fn next_tick(interval: &mut Interval, shutdown: &mut Receiver<bool>) -> bool {
+    if *shutdown.borrow() {
+        return false;
+    }
+    select! {
+        _ = interval.tick().fuse() => true,
+        _ = shutdown.changed().fuse() => false,
+    }
+}
+
 async fn schedule_scenario<C: Send + Sync + 'static, EL: 'static + EventListener>(
     mut scenario: ScheduledScenario<C>,
     mut event_listener: EL,
+    mut shutdown: Receiver<bool>,
 ) -> impl Send {
     event_listener.register_scenario(&scenario);
 
     let id = scenario.ident();
+    let target = scenario.log_target();
     let mut interval = tokio::time::interval(scenario.interval());
 
     interval.tick().await; // this one is immediate
+
+    // Setup is postponed until some node is available.
     loop {
-        interval.tick().await;
+        match scenario.next_connection() {
+            Some(connection) => match scenario.setup(&connection).await {
+                Ok(()) => break,
+                Err(e) => {
+                    error!(target: target.as_str(), "Setup failed: {}. Disabling scenario", e);
+                    event_listener.report_disabled(id.clone(), e.to_string());
+                    return;
+                }
+            },
+            None => {
+                warn!(target: target.as_str(), "No node is available. Postponing setup");
+                if !next_tick(&mut interval, &mut shutdown).await {
+                    return;
+                }
+            }
+        }
+    }
+
+    while next_tick(&mut interval, &mut shutdown).await {
+        let connection = match scenario.next_connection() {
+            Some(connection) => connection,
+            None => {
+                warn!(target: target.as_str(), "No node is available. Skipping this run");
+                event_listener.report_skip(id.clone());
+                continue;
+            }
+        };
+
+        if let Err(e) = scenario.health_check(&connection).await {
+            warn!(target: target.as_str(), "Health check failed: {}. Skipping this run", e);
+            event_listener.report_skip(id.clone());
+            continue;
+        }
 
         event_listener.report_launch(id.clone());
-        match scenario.play().await {
+        match scenario.play(&connection).await {
             Ok(()) => event_listener.report_success(id.clone()),
             Err(_) => event_listener.report_failure(id.clone()),
         }
     }
+
+    match scenario.next_connection() {
+        Some(connection) => match scenario.teardown(&connection).await {
+            Ok(()) => info!(target: target.as_str(), "Torn down"),
+            Err(e) => error!(target: target.as_str(), "Teardown failed: {}", e),
+        },
+        None => warn!(target: target.as_str(), "No node is available. Skipping teardown"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use anyhow::Result as AnyResult;
+    use tokio::runtime::Builder;
+
+    use common::{ConnectionProvider, Ident, Scenario, ScenarioLogging, ScheduledScenario};
+
+    use crate::{
+        logger::setup_logging,
+        schedule::{run_schedule, EventListener},
+    };
+
+    /// Always offers the (unit) connection.
+    struct Available;
+
+    impl ConnectionProvider<()> for Available {
+        fn next_connection(&self) -> Option<()> {
+            Some(())
+        }
+    }
+
+    /// Logs the name of its environment on every run.
+    struct Greeting(&'static str);
+
+    #[async_trait::async_trait]
+    impl Scenario<()> for Greeting {
+        async fn play(&mut self, _connection: &(), logger: &ScenarioLogging) -> AnyResult<()> {
+            logger.info(format!("Hello from {}", self.0));
+            Ok(())
+        }
+    }
+
+    /// Collects the logs reported to it (and ignores everything else).
+    #[derive(Clone, Default)]
+    struct Logs(Arc<Mutex<Vec<(Ident, String)>>>);
+
+    impl EventListener for Logs {
+        fn register_scenario<C: Send + Sync + 'static>(&mut self, _: &ScheduledScenario<C>) {}
+        fn report_success(&mut self, _: Ident) {}
+        fn report_launch(&mut self, _: Ident) {}
+        fn report_failure(&mut self, _: Ident) {}
+        fn report_skip(&mut self, _: Ident) {}
+        fn report_disabled(&mut self, _: Ident, _: String) {}
+
+        fn report_logs(&mut self, scenario_ident: Ident, log: String) {
+            self.0.lock().unwrap().push((scenario_ident, log))
+        }
+    }
+
+    fn greeting(environment: &'static str) -> ScheduledScenario<()> {
+        ScheduledScenario::new(
+            environment.to_string(),
+            Ident::from("Greeting"),
+            Duration::from_millis(10),
+            Available,
+            Greeting(environment),
+        )
+    }
+
+    #[test]
+    fn environments_sharing_idents_get_their_own_logs() {
+        let logger = setup_logging();
+        let (first, second) = (Logs::default(), Logs::default());
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            tokio::spawn(run_schedule(
+                vec![greeting("first")],
+                first.clone(),
+                logger.clone(),
+            ));
+            tokio::spawn(run_schedule(
+                vec![greeting("second")],
+                second.clone(),
+                logger,
+            ));
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        });
+
+        for (logs, environment) in [(first, "first"), (second, "second")] {
+            let logs = logs.0.lock().unwrap();
+            assert!(!logs.is_empty());
+            assert!(logs.iter().all(
+                |(ident, line)| ident == &Ident::from("Greeting") && line.contains(environment)
+            ));
+        }
+    }
 }